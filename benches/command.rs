@@ -0,0 +1,40 @@
+//! Benchmarks of constructing commands with typical numbers of arguments.
+//!
+//! Compare the effect of the inline capacity of small vectors by running with
+//! different features, e.g. `cargo bench --features smallvec_8`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use pstoedit::Command;
+use std::hint::black_box;
+
+/// Command with `flags` flags, besides format, input and output.
+fn command(flags: usize) -> Command {
+    let mut cmd = Command::new();
+    for _ in 0..flags {
+        cmd.arg("-dt").unwrap();
+    }
+    cmd.format("svg")
+        .unwrap()
+        .input("input.ps")
+        .unwrap()
+        .output("output.svg")
+        .unwrap();
+    cmd
+}
+
+fn bench_command(c: &mut Criterion) {
+    let mut group = c.benchmark_group("command");
+    for flags in [0, 3, 7] {
+        group.bench_with_input(BenchmarkId::new("build", flags), &flags, |b, &flags| {
+            b.iter(|| command(black_box(flags)))
+        });
+        let cmd = command(flags);
+        group.bench_with_input(BenchmarkId::new("clone", flags), &cmd, |b, cmd| {
+            b.iter(|| black_box(cmd).clone())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_command);
+criterion_main!(benches);
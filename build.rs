@@ -0,0 +1,12 @@
+use std::env;
+
+fn main() {
+    // Run-time search paths of the pstoedit library, set by pstoedit-sys on
+    // macOS, so that binaries and tests find it without DYLD_LIBRARY_PATH
+    println!("cargo:rerun-if-env-changed=DEP_PSTOEDIT_RPATH");
+    if let Some(rpath) = env::var_os("DEP_PSTOEDIT_RPATH") {
+        for dir in env::split_paths(&rpath) {
+            println!("cargo:rustc-link-arg=-Wl,-rpath,{}", dir.display());
+        }
+    }
+}
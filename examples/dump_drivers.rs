@@ -2,7 +2,6 @@
 
 use pstoedit::driver_info::DriverDescription;
 use pstoedit::{DriverInfo, Result};
-use std::collections::HashSet;
 
 // Concatenate all the stuff this backend supports
 fn support_string(driver: DriverDescription) -> String {
@@ -34,11 +33,11 @@ fn print_driver(driver: DriverDescription) -> Result<()> {
     println!("    Extension:       {}", driver.extension()?);
     println!("    Explanation:     {}", driver.explanation()?);
     let info = driver.additional_info()?;
-    if info.len() > 0 {
+    if !info.is_empty() {
         println!("    Additional info: {}", driver.additional_info()?);
     }
     let support = support_string(driver);
-    if support.len() > 0 {
+    if !support.is_empty() {
         println!("    Support for:     {}", support);
     }
     Ok(())
@@ -48,24 +47,18 @@ fn main() -> Result<()> {
     pstoedit::init()?;
 
     // Print all native drivers
-    let native_drivers = DriverInfo::get_native()?;
-    let mut native_formats = HashSet::new();
     println!("Native drivers:");
-    for driver in &native_drivers {
-        native_formats.insert(driver.symbolic_name()?);
+    for driver in &DriverInfo::get_native()? {
         print_driver(driver)?;
-        println!("");
+        println!();
     }
 
     // Print all non-native drivers
     let drivers = DriverInfo::get()?;
     println!("Non-native drivers:");
-    for driver in &drivers {
-        if native_formats.contains(driver.symbolic_name()?) {
-            continue;
-        }
+    for driver in drivers.plugins_only()? {
         print_driver(driver)?;
-        println!("");
+        println!();
     }
 
     Ok(())
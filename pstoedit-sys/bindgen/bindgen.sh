@@ -9,6 +9,9 @@ bindgen_opts=(
     --blocklist-function ignoreVersionCheck
     # These type definitions are not used in the C API
     --blocklist-type '.*_func(tion)?'
+    # These are maintained by hand in src/output.rs
+    --blocklist-function setPstoeditOutputFunction
+    --blocklist-type write_callback_type
     # Output to source directory
     --output ../src/bindings.rs
 )
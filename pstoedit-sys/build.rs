@@ -1,3 +1,176 @@
+use std::env;
+use std::path::PathBuf;
+
 fn main() {
-    println!("cargo:rustc-link-lib=pstoedit");
+    println!("cargo:rerun-if-env-changed=PSTOEDIT_LIB_NAME");
+    println!("cargo:rerun-if-env-changed=PSTOEDIT_LIB_DIR");
+    let mut dirs: Vec<PathBuf> = env::var_os("PSTOEDIT_LIB_DIR")
+        .map(|dirs| env::split_paths(&dirs).collect())
+        .unwrap_or_default();
+    let name = env::var("PSTOEDIT_LIB_NAME").unwrap_or_else(|_| "pstoedit".into());
+    if env::var("CARGO_CFG_TARGET_OS").as_deref() == Ok("macos") {
+        macos_dirs(&name, &mut dirs);
+    }
+    for dir in &dirs {
+        println!("cargo:rustc-link-search=native={}", dir.display());
+    }
+    if cfg!(feature = "static") {
+        link_static(&name);
+    } else {
+        #[cfg(windows)]
+        import_library(&name, &dirs);
+        println!("cargo:rustc-link-lib=dylib={}", name);
+    }
+    #[cfg(feature = "bindgen")]
+    generate_bindings();
+}
+
+/// Add the library directory of the Homebrew or MacPorts prefix that contains
+/// the library to `dirs`, and embed all of `dirs` as run-time search paths.
+///
+/// Homebrew installs into `/opt/homebrew` on arm64 and `/usr/local` on
+/// x86_64, neither of which is searched by default on arm64. The search paths
+/// are used by the tests of this crate, and passed to dependents as
+/// `DEP_PSTOEDIT_RPATH` for their own binaries and tests.
+fn macos_dirs(name: &str, dirs: &mut Vec<PathBuf>) {
+    println!("cargo:rerun-if-env-changed=HOMEBREW_PREFIX");
+    let homebrew = match env::var("CARGO_CFG_TARGET_ARCH").as_deref() {
+        Ok("aarch64") => "/opt/homebrew",
+        _ => "/usr/local",
+    };
+    let defaults = [homebrew, "/opt/local"];
+    let prefixes = env::var_os("HOMEBREW_PREFIX")
+        .map(PathBuf::from)
+        .into_iter()
+        .chain(defaults.iter().map(PathBuf::from));
+    let extension = if cfg!(feature = "static") {
+        "a"
+    } else {
+        "dylib"
+    };
+    let lib = format!("lib{}.{}", name, extension);
+    let found = prefixes
+        .map(|prefix| prefix.join("lib"))
+        .find(|dir| dir.join(&lib).is_file());
+    dirs.extend(found);
+    for dir in dirs.iter() {
+        println!("cargo:rustc-link-arg=-Wl,-rpath,{}", dir.display());
+    }
+    if let Ok(rpath) = env::join_paths(dirs.iter()) {
+        println!("cargo:rpath={}", rpath.to_string_lossy());
+    }
+}
+
+/// Link the static archive of pstoedit, followed by the C++ runtime it
+/// depends on, which is linked statically as well with feature `static_cxx`.
+///
+/// The MSVC toolchain links the C++ runtime by itself.
+fn link_static(name: &str) {
+    println!("cargo:rustc-link-lib=static={}", name);
+    if env::var("CARGO_CFG_TARGET_ENV").as_deref() == Ok("msvc") {
+        return;
+    }
+    let cxx = match env::var("CARGO_CFG_TARGET_OS").as_deref() {
+        Ok("macos") | Ok("ios") | Ok("freebsd") | Ok("openbsd") => "c++",
+        _ => "stdc++",
+    };
+    let kind = if cfg!(feature = "static_cxx") {
+        "static"
+    } else {
+        "dylib"
+    };
+    println!("cargo:rustc-link-lib={}={}", kind, cxx);
+}
+
+/// Functions exported by the pstoedit DLL that are bound by this crate.
+#[cfg(windows)]
+const EXPORTS: &[&str] = &[
+    "pstoedit_checkversion",
+    "pstoedit_plainC",
+    "getPstoeditDriverInfo_plainC",
+    "getPstoeditNativeDriverInfo_plainC",
+    "clearPstoeditDriverInfo_plainC",
+    "loadpstoeditplugins_plainC",
+    "unloadpstoeditplugins",
+    "setPstoeditOutputFunction",
+];
+
+/// Generate an import library for the pstoedit DLL when targeting MSVC and
+/// none is found in the search directories or `LIB`.
+///
+/// pstoedit is distributed without import library, which is required to link
+/// a DLL with the MSVC toolchain. It is generated from a module definition
+/// listing [`EXPORTS`] using `lib.exe`.
+#[cfg(windows)]
+fn import_library(name: &str, dirs: &[PathBuf]) {
+    use std::fs;
+
+    println!("cargo:rerun-if-env-changed=LIB");
+    if env::var("CARGO_CFG_TARGET_ENV").as_deref() != Ok("msvc") {
+        return;
+    }
+    let lib = format!("{}.lib", name);
+    let lib_dirs = env::var_os("LIB").map_or_else(Vec::new, |d| env::split_paths(&d).collect());
+    if dirs
+        .iter()
+        .chain(&lib_dirs)
+        .any(|dir| dir.join(&lib).is_file())
+    {
+        return;
+    }
+
+    let out = PathBuf::from(env::var_os("OUT_DIR").unwrap());
+    let def = out.join(format!("{}.def", name));
+    let mut contents = format!("LIBRARY {}.dll\nEXPORTS\n", name);
+    for export in EXPORTS {
+        contents.push_str(&format!("    {}\n", export));
+    }
+    fs::write(&def, contents).expect("failed to write module definition");
+
+    let machine = match env::var("CARGO_CFG_TARGET_ARCH").as_deref() {
+        Ok("x86_64") => "X64",
+        Ok("x86") => "X86",
+        Ok("aarch64") => "ARM64",
+        arch => panic!("unsupported architecture for import library: {:?}", arch),
+    };
+    let target = env::var("TARGET").unwrap();
+    let status = cc::windows_registry::find(&target, "lib.exe")
+        .expect("lib.exe not found, required to generate import library")
+        .arg(format!("/DEF:{}", def.display()))
+        .arg(format!("/OUT:{}", out.join(&lib).display()))
+        .arg(format!("/MACHINE:{}", machine))
+        .status()
+        .expect("failed to run lib.exe");
+    assert!(
+        status.success(),
+        "lib.exe failed to generate import library"
+    );
+    println!("cargo:rustc-link-search=native={}", out.display());
+}
+
+/// Generate bindings from the installed pstoedit header, using the same
+/// options as `bindgen/bindgen.sh`.
+#[cfg(feature = "bindgen")]
+fn generate_bindings() {
+    println!("cargo:rerun-if-changed=bindgen/bindings.h");
+    println!("cargo:rerun-if-env-changed=PSTOEDIT_INCLUDE_DIR");
+    let mut builder = bindgen::Builder::default()
+        .header("bindgen/bindings.h")
+        // This function is present in header file but not in shared library
+        .blocklist_function("ignoreVersionCheck")
+        // These type definitions are not used in the C API
+        .blocklist_type(".*_func(tion)?")
+        // These are maintained by hand in src/output.rs
+        .blocklist_function("setPstoeditOutputFunction")
+        .blocklist_type("write_callback_type")
+        .parse_callbacks(Box::new(bindgen::CargoCallbacks::new()));
+    if let Some(dir) = env::var_os("PSTOEDIT_INCLUDE_DIR") {
+        builder = builder.clang_arg(format!("-I{}", PathBuf::from(dir).display()));
+    }
+    let out = PathBuf::from(env::var_os("OUT_DIR").unwrap()).join("bindings.rs");
+    builder
+        .generate()
+        .expect("failed to generate bindings from pstoedit.h")
+        .write_to_file(out)
+        .expect("failed to write bindings");
 }
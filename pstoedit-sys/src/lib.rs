@@ -14,21 +14,62 @@
 //!   with future 4.xx releases.
 //! - No feature starting with `pstoedit_`: compatible with pstoedit version
 //!   3.17&ndash;3.78.
+//!
+//! # Linking
+//! The `pstoedit` library is linked dynamically. The following environment
+//! variables can be set at build time to adjust how it is found.
+//!
+//! - `PSTOEDIT_LIB_NAME`: name of the library to link instead of `pstoedit`,
+//!   e.g. `pstoedit-0` on MSYS2.
+//! - `PSTOEDIT_LIB_DIR`: additional directories to search for the library,
+//!   separated like the `PATH` environment variable.
+//!
+//! With the MSVC toolchain, an import library is generated using `lib.exe` if
+//! none is found in these directories or in `LIB`.
+//!
+//! # Generating bindings
+//! By default, pre-generated bindings are used. With the `bindgen` feature,
+//! bindings are instead generated at build time from the installed
+//! `pstoedit/pstoedit.h`, so they match the installed version of pstoedit.
+//! The header is searched in the default include paths and in the directory
+//! set by the `PSTOEDIT_INCLUDE_DIR` environment variable. This requires
+//! `libclang`, see the [bindgen documentation] for details. Note that the
+//! features starting with `pstoedit_` do not affect generated bindings.
+//!
+//! [bindgen documentation]: https://rust-lang.github.io/rust-bindgen/requirements.html
 
 #![cfg_attr(docsrs, feature(doc_cfg))]
 
 #[allow(non_camel_case_types)]
 #[allow(non_snake_case)]
 #[allow(non_upper_case_globals)]
+#[cfg(not(feature = "bindgen"))]
 mod bindings;
 
+#[allow(non_camel_case_types)]
+#[allow(non_snake_case)]
+#[allow(non_upper_case_globals)]
+#[cfg(feature = "bindgen")]
+mod bindings {
+    include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
+}
+
+#[allow(non_camel_case_types)]
+#[allow(non_snake_case)]
+mod output;
+
+mod status;
+
 pub use bindings::*;
+pub use output::*;
+pub use status::*;
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::os::raw::{c_char, c_int};
-    use std::{env, ptr};
+    use std::env;
+    use std::os::raw::{c_char, c_int, c_ulong, c_void};
+    use std::ptr;
 
     #[test]
     fn dll_version() {
@@ -47,7 +88,7 @@ mod tests {
     fn driver_info() {
         init();
         let drivers: *mut DriverDescription_S = unsafe { getPstoeditDriverInfo_plainC() };
-        assert!(drivers != ptr::null_mut());
+        assert!(!drivers.is_null());
         unsafe { clearPstoeditDriverInfo_plainC(drivers) };
     }
 
@@ -55,7 +96,7 @@ mod tests {
     fn native_driver_info() {
         init();
         let drivers: *mut DriverDescription_S = unsafe { getPstoeditNativeDriverInfo_plainC() };
-        assert!(drivers != ptr::null_mut());
+        assert!(!drivers.is_null());
         unsafe { clearPstoeditDriverInfo_plainC(drivers) };
     }
 
@@ -72,6 +113,16 @@ mod tests {
         let psinterpreter = b"gs\0".as_ptr() as *const c_char;
         env::set_var("GS", "should_not_be_used");
         let result = unsafe { pstoedit_plainC(argc, argv.as_ptr(), psinterpreter) };
-        assert_eq!(result, 0);
+        assert_eq!(result, PSTOEDIT_OK);
+    }
+
+    #[test]
+    fn output_function() {
+        unsafe extern "C" fn write(_: *mut c_void, _: *const c_char, length: c_ulong) -> c_int {
+            length as c_int
+        }
+        init();
+        unsafe { setPstoeditOutputFunction(ptr::null_mut(), Some(write)) };
+        unsafe { setPstoeditOutputFunction(ptr::null_mut(), None) };
     }
 }
@@ -0,0 +1,21 @@
+//! Bindings to the output redirection API of pstoedit.
+//!
+//! These are declared in `pstoedit/pstoedit.h` outside of the plain C API, so
+//! they are not picked up when generating bindings and are maintained by hand.
+
+use std::os::raw::{c_char, c_int, c_ulong, c_void};
+
+/// Callback receiving diagnostic output of pstoedit.
+///
+/// Called with the data pointer passed to [`setPstoeditOutputFunction`] and
+/// `length` bytes of text, which are not nul-terminated. Should return the
+/// number of bytes handled.
+pub type write_callback_type = ::std::option::Option<
+    unsafe extern "C" fn(cb_data: *mut c_void, text: *const c_char, length: c_ulong) -> c_int,
+>;
+
+extern "C" {
+    /// Redirect diagnostic output of pstoedit to `cbFunction`, which is called
+    /// with `cbData`. Passing `None` restores output to standard error.
+    pub fn setPstoeditOutputFunction(cbData: *mut c_void, cbFunction: write_callback_type);
+}
@@ -0,0 +1,20 @@
+//! Return codes of [`pstoedit_plainC`][crate::pstoedit_plainC].
+//!
+//! The header of pstoedit does not name these codes, so they cannot be
+//! generated and are maintained by hand. They are the same for all supported
+//! versions of pstoedit.
+
+use std::os::raw::c_int;
+
+/// The conversion succeeded.
+pub const PSTOEDIT_OK: c_int = 0;
+
+/// The conversion failed, e.g. because of invalid arguments, an unreadable
+/// input file, or an error of the PostScript interpreter. Details are written
+/// to the diagnostic output, see
+/// [`setPstoeditOutputFunction`][crate::setPstoeditOutputFunction].
+pub const PSTOEDIT_ERROR: c_int = 1;
+
+/// [`pstoedit_checkversion`][crate::pstoedit_checkversion] was not called
+/// successfully before.
+pub const PSTOEDIT_NOT_INITIALIZED: c_int = -1;
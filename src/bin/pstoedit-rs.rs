@@ -0,0 +1,200 @@
+// Command-line interface built on the safe pstoedit API.
+//
+// Usage:
+//     pstoedit-rs drivers [--native]
+//     pstoedit-rs convert FORMAT INPUT OUTPUT
+//     pstoedit-rs batch FORMAT OUTPUT_DIR INPUT...
+
+use pstoedit::driver_info::DriverDescription;
+use pstoedit::{Command, DriverInfo, OutputTemplate};
+use std::error::Error;
+use std::ffi::{OsStr, OsString};
+use std::fmt::Write;
+use std::path::Path;
+use std::process::ExitCode;
+use std::{env, fmt};
+
+const USAGE: &str = "\
+Usage:
+    pstoedit-rs drivers [--native]
+    pstoedit-rs convert FORMAT INPUT OUTPUT
+    pstoedit-rs batch FORMAT OUTPUT_DIR INPUT...";
+
+type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+// Error for invalid command-line usage
+#[derive(Debug)]
+struct UsageError;
+
+impl Error for UsageError {}
+
+impl fmt::Display for UsageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(USAGE)
+    }
+}
+
+// Append string to JSON output, quoted and escaped
+fn json_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+// Append driver description to JSON output as object
+fn json_driver(out: &mut String, driver: DriverDescription) -> Result<()> {
+    let strings = [
+        ("symbolic_name", driver.symbolic_name()?),
+        ("extension", driver.extension()?),
+        ("explanation", driver.explanation()?),
+        ("additional_info", driver.additional_info()?),
+    ];
+    let flags = [
+        ("subpaths", driver.subpath_support()),
+        ("curveto", driver.curveto_support()),
+        ("merging", driver.merging_support()),
+        ("text", driver.text_support()),
+        ("images", driver.image_support()),
+        ("multiple_pages", driver.multipage_support()),
+    ];
+    out.push('{');
+    for (key, value) in &strings {
+        json_string(out, key);
+        out.push(':');
+        json_string(out, value);
+        out.push(',');
+    }
+    for (key, value) in &flags {
+        json_string(out, key);
+        write!(out, ":{},", value)?;
+    }
+    out.pop();
+    out.push('}');
+    Ok(())
+}
+
+// Print all (or only native) drivers as JSON array
+fn drivers(args: &[OsString]) -> Result<()> {
+    let info = match args {
+        [] => DriverInfo::get()?,
+        [flag] if flag == "--native" => DriverInfo::get_native()?,
+        _ => return Err(UsageError.into()),
+    };
+    let mut out = String::from("[");
+    for driver in &info {
+        json_driver(&mut out, driver)?;
+        out.push(',');
+    }
+    if out.len() > 1 {
+        out.pop();
+    }
+    out.push(']');
+    println!("{}", out);
+    Ok(())
+}
+
+// Convert a single file
+fn convert(args: &[OsString]) -> Result<()> {
+    match args {
+        [format, input, output] => {
+            Command::new()
+                .args_os_slice(&[OsStr::new("-f"), format, input, output])?
+                .run()?;
+            Ok(())
+        }
+        _ => Err(UsageError.into()),
+    }
+}
+
+// Find the file name extension of a driver, ignoring driver options
+fn extension(format: &str) -> Result<String> {
+    let name = format.split(':').next().unwrap_or(format);
+    match DriverInfo::get()?.find(name) {
+        Some(driver) => Ok(driver.extension()?.to_owned()),
+        None => Err(pstoedit::Error::UnknownFormat(name.to_owned()).into()),
+    }
+}
+
+// Convert multiple files to a directory, reporting progress on stderr, unless
+// output names collide
+fn batch(args: &[OsString]) -> Result<()> {
+    let (format, dir, inputs) = match args {
+        [format, dir, inputs @ ..] if !inputs.is_empty() => (format, Path::new(dir), inputs),
+        _ => return Err(UsageError.into()),
+    };
+    let format = format.to_str().ok_or("format is not valid UTF-8")?;
+    let template = OutputTemplate::new(&format!("{{stem}}.{}", extension(format)?))?;
+    let mut outputs = Vec::with_capacity(inputs.len());
+    for input in inputs {
+        let stem = Path::new(input)
+            .file_stem()
+            .ok_or("input without file name")?
+            .to_str()
+            .ok_or("input file name is not valid UTF-8")?;
+        let output = dir.join(template.render(stem, 1));
+        if outputs.contains(&output) {
+            return Err(pstoedit::Error::OutputCollision(output).into());
+        }
+        outputs.push(output);
+    }
+    let mut failed = 0;
+    for (i, (input, output)) in inputs.iter().zip(&outputs).enumerate() {
+        eprint!(
+            "[{}/{}] {} -> {} ... ",
+            i + 1,
+            inputs.len(),
+            Path::new(input).display(),
+            output.display()
+        );
+        let result = Command::new()
+            .args_os_slice(&[
+                OsStr::new("-f"),
+                OsStr::new(format),
+                input,
+                output.as_os_str(),
+            ])
+            .and_then(|cmd| cmd.run());
+        match result {
+            Ok(()) => eprintln!("ok"),
+            Err(err) => {
+                eprintln!("failed: {}", err);
+                failed += 1;
+            }
+        }
+    }
+    if failed > 0 {
+        Err(format!("{} of {} conversions failed", failed, inputs.len()).into())
+    } else {
+        Ok(())
+    }
+}
+
+fn main() -> ExitCode {
+    // Paths need not be valid UTF-8, so arguments are kept as OS strings
+    let args: Vec<_> = env::args_os().skip(1).collect();
+    let result = pstoedit::init()
+        .map_err(Into::into)
+        .and_then(|()| match args.split_first() {
+            Some((cmd, rest)) if cmd == "drivers" => drivers(rest),
+            Some((cmd, rest)) if cmd == "convert" => convert(rest),
+            Some((cmd, rest)) if cmd == "batch" => batch(rest),
+            _ => Err(UsageError.into()),
+        });
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
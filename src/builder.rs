@@ -0,0 +1,329 @@
+use crate::formats::DriverSpec;
+use crate::{Color, ColorModel, Command, Error, Result, RetryPolicy, TextMode};
+use std::ffi::{CStr, OsStr};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Consuming builder for a [`Command`].
+///
+/// This offers the methods of [`Command`] that configure it by value instead
+/// of by mutable reference, which composes better with functions that
+/// construct and return commands. Errors are deferred until
+/// [`build`][CommandBuilder::build]; methods called after the first error
+/// have no effect.
+///
+/// # Examples
+/// ```no_run
+/// use pstoedit::{Command, CommandBuilder};
+///
+/// fn to_svg(input: &str, output: &str) -> CommandBuilder {
+///     Command::builder().format("svg").input(input).output(output)
+/// }
+///
+/// pstoedit::init()?;
+/// to_svg("input.ps", "output.svg").arg("-dt").build()?.run()?;
+/// # Ok::<(), pstoedit::Error>(())
+/// ```
+#[derive(Debug, Default)]
+pub struct CommandBuilder {
+    command: Command,
+    error: Option<Error>,
+}
+
+impl CommandBuilder {
+    /// Create a builder for a command with program name and without arguments.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply fallible configuration, unless an error occurred before.
+    fn apply<F>(mut self, f: F) -> Self
+    where
+        F: FnOnce(&mut Command) -> Result<&mut Command>,
+    {
+        if self.error.is_none() {
+            if let Err(err) = f(&mut self.command) {
+                self.error = Some(err);
+            }
+        }
+        self
+    }
+
+    /// See [`Command::arg`].
+    pub fn arg<S>(self, arg: S) -> Self
+    where
+        S: Into<Vec<u8>>,
+    {
+        self.apply(|cmd| cmd.arg(arg))
+    }
+
+    /// See [`Command::arg_cstr`].
+    pub fn arg_cstr(self, arg: &CStr) -> Self {
+        self.apply(|cmd| Ok(cmd.arg_cstr(arg)))
+    }
+
+    /// See [`Command::args`].
+    pub fn args<I>(self, args: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<Vec<u8>>,
+    {
+        self.apply(|cmd| cmd.args(args))
+    }
+
+    /// See [`Command::args_slice`].
+    pub fn args_slice<S>(self, args: &[S]) -> Self
+    where
+        S: AsRef<str>,
+    {
+        self.apply(|cmd| cmd.args_slice(args))
+    }
+
+    /// See [`Command::args_os_slice`].
+    pub fn args_os_slice<S>(self, args: &[S]) -> Self
+    where
+        S: AsRef<OsStr>,
+    {
+        self.apply(|cmd| cmd.args_os_slice(args))
+    }
+
+    /// See [`Command::option`].
+    pub fn option<V>(self, flag: &str, value: V) -> Self
+    where
+        V: fmt::Display,
+    {
+        self.apply(|cmd| cmd.option(flag, value))
+    }
+
+    /// See [`Command::clear_args`].
+    pub fn clear_args(self) -> Self {
+        self.apply(|cmd| Ok(cmd.clear_args()))
+    }
+
+    /// See [`Command::remove_flag`].
+    pub fn remove_flag(self, flag: &str) -> Self {
+        self.apply(|cmd| Ok(cmd.remove_flag(flag)))
+    }
+
+    /// See [`Command::remove_option`].
+    pub fn remove_option(self, flag: &str) -> Self {
+        self.apply(|cmd| Ok(cmd.remove_option(flag)))
+    }
+
+    /// See [`Command::format`].
+    pub fn format<S>(self, format: S) -> Self
+    where
+        S: Into<Vec<u8>>,
+    {
+        self.apply(|cmd| cmd.format(format))
+    }
+
+    /// See [`Command::format_with_options`].
+    pub fn format_with_options(self, spec: &DriverSpec) -> Self {
+        self.apply(|cmd| cmd.format_with_options(spec))
+    }
+
+    /// See [`Command::input`].
+    pub fn input<S>(self, input: S) -> Self
+    where
+        S: Into<Vec<u8>>,
+    {
+        self.apply(|cmd| cmd.input(input))
+    }
+
+    /// See [`Command::output`].
+    pub fn output<S>(self, output: S) -> Self
+    where
+        S: Into<Vec<u8>>,
+    {
+        self.apply(|cmd| cmd.output(output))
+    }
+
+    /// See [`Command::input_utf8`].
+    #[cfg(feature = "camino")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "camino")))]
+    pub fn input_utf8<P>(self, input: P) -> Self
+    where
+        P: AsRef<camino::Utf8Path>,
+    {
+        self.apply(|cmd| cmd.input_utf8(input))
+    }
+
+    /// See [`Command::output_utf8`].
+    #[cfg(feature = "camino")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "camino")))]
+    pub fn output_utf8<P>(self, output: P) -> Self
+    where
+        P: AsRef<camino::Utf8Path>,
+    {
+        self.apply(|cmd| cmd.output_utf8(output))
+    }
+
+    /// See [`Command::output_dir`].
+    pub fn output_dir<P>(self, dir: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.apply(|cmd| Ok(cmd.output_dir(dir)))
+    }
+
+    /// See [`Command::verify_output`].
+    pub fn verify_output(self, enable: bool) -> Self {
+        self.apply(|cmd| Ok(cmd.verify_output(enable)))
+    }
+
+    /// See [`Command::deterministic`].
+    pub fn deterministic(self, enable: bool) -> Self {
+        self.apply(|cmd| Ok(cmd.deterministic(enable)))
+    }
+
+    /// See [`Command::incremental`].
+    pub fn incremental(self, enable: bool) -> Self {
+        self.apply(|cmd| Ok(cmd.incremental(enable)))
+    }
+
+    /// See [`Command::pre_process`].
+    pub fn pre_process<F>(self, hook: F) -> Self
+    where
+        F: Fn(&Path, &Path) -> Result<()> + Send + Sync + 'static,
+    {
+        self.apply(|cmd| Ok(cmd.pre_process(hook)))
+    }
+
+    /// See [`Command::post_process`].
+    pub fn post_process<F>(self, hook: F) -> Self
+    where
+        F: Fn(&Path) -> Result<()> + Send + Sync + 'static,
+    {
+        self.apply(|cmd| Ok(cmd.post_process(hook)))
+    }
+
+    /// See [`Command::use_input_bbox`].
+    pub fn use_input_bbox(self, enable: bool) -> Self {
+        self.apply(|cmd| Ok(cmd.use_input_bbox(enable)))
+    }
+
+    /// See [`Command::color_model`].
+    pub fn color_model(self, model: ColorModel) -> Self {
+        self.apply(|cmd| Ok(cmd.color_model(model)))
+    }
+
+    /// See [`Command::text_mode`].
+    pub fn text_mode(self, mode: TextMode) -> Self {
+        self.apply(|cmd| Ok(cmd.text_mode(mode)))
+    }
+
+    /// See [`Command::type2_fonts_as_type1`].
+    pub fn type2_fonts_as_type1(self, enable: bool) -> Self {
+        self.apply(|cmd| Ok(cmd.type2_fonts_as_type1(enable)))
+    }
+
+    /// See [`Command::default_font`].
+    pub fn default_font<S>(self, font: S) -> Self
+    where
+        S: Into<Vec<u8>>,
+    {
+        self.apply(|cmd| cmd.default_font(font))
+    }
+
+    /// See [`Command::default_font_in`].
+    pub fn default_font_in<P>(self, font: &str, fontmap: P) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        self.apply(|cmd| cmd.default_font_in(font, fontmap))
+    }
+
+    /// See [`Command::flatness`].
+    pub fn flatness<F>(self, flatness: F) -> Self
+    where
+        F: Into<Option<f64>>,
+    {
+        self.apply(|cmd| Ok(cmd.flatness(flatness)))
+    }
+
+    /// See [`Command::background`].
+    pub fn background<C>(self, color: C) -> Self
+    where
+        C: Into<Option<Color>>,
+    {
+        self.apply(|cmd| Ok(cmd.background(color)))
+    }
+
+    /// See [`Command::gs`].
+    pub fn gs<S>(self, gs: S) -> Self
+    where
+        S: Into<Vec<u8>>,
+    {
+        self.apply(|cmd| cmd.gs(gs))
+    }
+
+    /// See [`Command::gs_registry_base`].
+    #[cfg(windows)]
+    #[cfg_attr(docsrs, doc(cfg(windows)))]
+    pub fn gs_registry_base<S>(self, base: S) -> Self
+    where
+        S: Into<Vec<u8>>,
+    {
+        self.apply(|cmd| cmd.gs_registry_base(base))
+    }
+
+    /// See [`Command::retry`].
+    pub fn retry(self, policy: RetryPolicy) -> Self {
+        self.apply(|cmd| Ok(cmd.retry(policy)))
+    }
+
+    /// See [`Command::temp_dir`].
+    pub fn temp_dir<P>(self, dir: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.apply(|cmd| Ok(cmd.temp_dir(dir)))
+    }
+
+    /// Finish building the command.
+    ///
+    /// # Errors
+    /// The first error raised by a method of the builder, see
+    /// [`Command`][Command#errors].
+    pub fn build(self) -> Result<Command> {
+        match self.error {
+            Some(err) => Err(err),
+            None => Ok(self.command),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build() {
+        let cmd = Command::builder()
+            .arg("-dt")
+            .format("svg")
+            .input("input.ps")
+            .output("output.svg")
+            .build()
+            .unwrap();
+        let argv: Vec<_> = cmd.argv().iter().map(|s| s.to_str().unwrap()).collect();
+        assert_eq!(
+            argv,
+            ["pstoedit", "-dt", "-f", "svg", "input.ps", "output.svg"]
+        );
+    }
+
+    #[test]
+    fn deferred_error() {
+        let result = Command::builder()
+            .arg("a\0b")
+            .input("c\0d")
+            .arg("-dt")
+            .build();
+        match result {
+            Err(Error::NulError(err)) => assert_eq!(err.nul_position(), 1),
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,194 @@
+//! Manifests of previous runs, to skip conversions that are up to date.
+
+use crate::{Command, Result};
+use std::ffi::CStr;
+use std::fmt::Write as _;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+
+/// Name of the manifest in the directory of the output files.
+pub(crate) const MANIFEST: &str = ".pstoedit-cache";
+
+/// Options naming files whose contents affect the output.
+const FILE_OPTIONS: [&str; 2] = ["-fontmap", "-include"];
+
+/// Serializes updates of manifests within this process.
+static MANIFEST_LOCK: Mutex<()> = Mutex::new(());
+
+/// Key of a run in the manifest next to its output file.
+#[derive(Debug)]
+pub(crate) struct Entry {
+    manifest: PathBuf,
+    output: PathBuf,
+    name: String,
+    key: String,
+}
+
+impl Entry {
+    /// Entry for a resolved command, derived from the contents of its input
+    /// file and its arguments.
+    ///
+    /// Returns `None` if the command has no input or output file, or the
+    /// name of the output file cannot be recorded.
+    pub(crate) fn new(command: &Command, deterministic: bool) -> Result<Option<Self>> {
+        let path = |path: Option<&CStr>| path.and_then(crate::cstr_path).map(Path::to_owned);
+        let (input, output) = match (path(command.input_path()), path(command.output_path())) {
+            (Some(input), Some(output)) => (input, output),
+            _ => return Ok(None),
+        };
+        let name = match output.file_name().and_then(|name| name.to_str()) {
+            Some(name) if !name.contains('\n') => name.to_owned(),
+            _ => return Ok(None),
+        };
+        let argv = command.argv();
+        let mut hash = Fnv1a::new();
+        for arg in argv.iter().chain(command.gs_path().as_ref()) {
+            hash.update(arg.to_bytes_with_nul());
+        }
+        hash.update(&[u8::from(deterministic)]);
+        // The background is applied to the input and does not show in argv
+        if let Some(color) = command.background_color() {
+            hash.update(&[color.red(), color.green(), color.blue()]);
+        }
+        // Referenced files that cannot be read are left to pstoedit to report
+        for pair in argv.windows(2) {
+            let flag = pair[0].to_bytes();
+            if FILE_OPTIONS.iter().any(|option| option.as_bytes() == flag) {
+                if let Some(path) = crate::cstr_path(pair[1]) {
+                    let _ = hash.update_file(path);
+                }
+            }
+        }
+        hash.update_file(&input)?;
+        let manifest = output.with_file_name(MANIFEST);
+        let key = format!("{:032x}", hash.0);
+        Ok(Some(Self {
+            manifest,
+            output,
+            name,
+            key,
+        }))
+    }
+
+    /// Output file of the run.
+    pub(crate) fn output(&self) -> &Path {
+        &self.output
+    }
+
+    /// Whether the output file exists and was written by a run with the same
+    /// key.
+    pub(crate) fn is_fresh(&self) -> bool {
+        if !self.output.is_file() {
+            return false;
+        }
+        let _guard = MANIFEST_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+        let manifest = fs::read_to_string(&self.manifest).unwrap_or_default();
+        let fresh = parse(&manifest).any(|(key, name)| key == self.key && name == self.name);
+        fresh
+    }
+
+    /// Record a successful run in the manifest, replacing the previous entry
+    /// for the output file.
+    pub(crate) fn store(&self) -> Result<()> {
+        let _guard = MANIFEST_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+        let manifest = match fs::read_to_string(&self.manifest) {
+            Ok(manifest) => manifest,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(err.into()),
+        };
+        let mut updated = String::with_capacity(manifest.len() + 64);
+        for (key, name) in parse(&manifest).filter(|(_, name)| *name != self.name) {
+            writeln!(updated, "{} {}", key, name).unwrap();
+        }
+        writeln!(updated, "{} {}", self.key, self.name).unwrap();
+        fs::write(&self.manifest, updated)?;
+        Ok(())
+    }
+}
+
+/// Entries of a manifest, as pairs of key and file name.
+fn parse(manifest: &str) -> impl Iterator<Item = (&str, &str)> {
+    manifest.lines().filter_map(|line| line.split_once(' '))
+}
+
+/// 128-bit FNV-1a hash, which is stable across platforms and versions.
+struct Fnv1a(u128);
+
+impl Fnv1a {
+    const OFFSET_BASIS: u128 = 0x6c62272e07bb014262b821756295c58d;
+    const PRIME: u128 = 0x0000000001000000000000000000013b;
+
+    fn new() -> Self {
+        Self(Self::OFFSET_BASIS)
+    }
+
+    fn update(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= u128::from(byte);
+            self.0 = self.0.wrapping_mul(Self::PRIME);
+        }
+    }
+
+    fn update_file(&mut self, path: &Path) -> io::Result<()> {
+        let mut file = File::open(path)?;
+        let mut buf = [0; 8192];
+        loop {
+            match file.read(&mut buf)? {
+                0 => return Ok(()),
+                n => self.update(&buf[..n]),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp::TempDir;
+
+    #[test]
+    fn fnv1a() {
+        let hash = |bytes: &[u8]| {
+            let mut hash = Fnv1a::new();
+            hash.update(bytes);
+            hash.0
+        };
+        assert_eq!(hash(b""), Fnv1a::OFFSET_BASIS);
+        assert_eq!(hash(b"a"), 0xd228cb696f1a8caf78912b704e4a8964);
+    }
+
+    #[test]
+    fn entry() {
+        let dir = TempDir::new().unwrap();
+        let input = dir.path().join("input.ps");
+        let output = dir.path().join("output.svg");
+        fs::write(&input, "%!PS\n").unwrap();
+        let mut cmd = Command::new();
+        cmd.format("svg").unwrap();
+        cmd.input(crate::path_bytes(&input).unwrap()).unwrap();
+        cmd.output(crate::path_bytes(&output).unwrap()).unwrap();
+        let entry = || Entry::new(&cmd, false).unwrap().unwrap();
+        assert!(!entry().is_fresh());
+        fs::write(&output, "<svg/>").unwrap();
+        assert!(!entry().is_fresh());
+        entry().store().unwrap();
+        assert!(entry().is_fresh());
+        assert!(!Entry::new(&cmd, true).unwrap().unwrap().is_fresh());
+        fs::write(&input, "%!PS\nshowpage\n").unwrap();
+        assert!(!entry().is_fresh());
+        entry().store().unwrap();
+        let manifest = fs::read_to_string(dir.path().join(MANIFEST)).unwrap();
+        assert_eq!(manifest.lines().count(), 1);
+
+        let fontmap = dir.path().join("fonts.fmp");
+        fs::write(&fontmap, "Helvetica Arial\n").unwrap();
+        cmd.option("-fontmap", fontmap.display()).unwrap();
+        let entry = || Entry::new(&cmd, false).unwrap().unwrap();
+        entry().store().unwrap();
+        assert!(entry().is_fresh());
+        fs::write(&fontmap, "Helvetica Courier\n").unwrap();
+        assert!(!entry().is_fresh());
+    }
+}
@@ -1,12 +1,88 @@
-use crate::{smallvec, Result, SmallVec};
-use std::ffi::CString;
+use crate::cache;
+use crate::conversion::ConversionHandle;
+use crate::deterministic;
+use crate::engine::Engine;
+use crate::formats::DriverSpec;
+use crate::gs;
+use crate::hook::{Hooks, PostProcess, PreProcess};
+use crate::input;
+#[cfg(any(feature = "log", feature = "tracing"))]
+use crate::input::BoundingBox;
+use crate::normalize::{self, Normalize};
+use crate::options;
+use crate::output::{self, OutputTemplate};
+use crate::temp::TempDir;
+use crate::{
+    smallvec, Color, ColorModel, CommandBuilder, DriverInfo, Error, Result, RetryPolicy, RunReport,
+    SmallVec, TextMode, Warning,
+};
+#[cfg(feature = "camino")]
+use camino::{Utf8Path, Utf8PathBuf};
+use std::borrow::Cow;
+use std::ffi::{CStr, CString, NulError, OsStr, OsString};
+use std::iter::FromIterator;
+use std::os::raw::c_char;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use std::{fmt, fs, ptr};
+
+/// Function executing pstoedit for a prepared command.
+pub(crate) type Exec<'a> = &'a dyn Fn(&Command) -> Result<Vec<Warning>>;
+
+/// Flag to use the bounding box of the input.
+const USE_INPUT_BBOX: &str = "-usebbfrominput";
+
+/// Flag to convert colors to RGB.
+const RGB: &str = "-rgb";
+
+/// Flag to handle Type 2 (CFF) fonts as Type 1 fonts.
+const TYPE2_AS_TYPE1: &str = "-t2fontsast1";
+
+/// Option to set the font replacing fonts that are not available.
+const DEFAULT_FONT: &str = "-df";
+
+/// Option to set the file mapping fonts to their replacements.
+const FONTMAP: &str = "-fontmap";
+
+/// Option to set the flatness with which curves are approximated.
+const FLATNESS: &str = "-flat";
+
+/// Flag to write every page to a separate file.
+const SPLIT: &str = "-split";
+
+/// Flag to only test whether ghostscript can be run.
+const GS_TEST: &str = "-gstest";
+
+/// Option to set the registry key under which ghostscript is looked up.
+#[cfg(windows)]
+const GS_REGISTRY_BASE: &str = "-gsregbase";
+
+/// Pointers to the full argument list of a [`Command`], kept up to date on
+/// every mutation so that runs do not need to collect them.
+#[derive(Default)]
+struct Ptrs(SmallVec<*const c_char>);
+
+// Safety: the pointers refer to heap-allocated C strings owned by the same
+// command, which are only dropped or replaced through &mut access
+unsafe impl Send for Ptrs {}
+unsafe impl Sync for Ptrs {}
+
+impl fmt::Debug for Ptrs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Ptrs").field(&self.0.len()).finish()
+    }
+}
 
 /// Command builder for generic pstoedit interaction.
 ///
 /// Commands are the main way to interact with pstoedit. A command is typically
 /// constructed using [`arg`][Command::arg], [`args`][Command::args] and/or
-/// [`args_slice`][Command::args_slice]. It can be run using
-/// [`run`][Command::run], multiple times if necessary.
+/// [`args_slice`][Command::args_slice]. The output format, input file and
+/// output file can also be set separately using [`format`][Command::format],
+/// [`input`][Command::input] and [`output`][Command::output]. It can be run
+/// using [`run`][Command::run], multiple times if necessary.
 ///
 /// # Examples
 /// ```
@@ -25,14 +101,39 @@ use std::ffi::CString;
 /// # Ok::<(), pstoedit::Error>(())
 /// ```
 ///
+/// ```no_run
+/// use pstoedit::Command;
+///
+/// pstoedit::init()?;
+/// Command::new().format("latex2e")?.input("input.ps")?.output("output.tex")?.run()?;
+/// # Ok::<(), pstoedit::Error>(())
+/// ```
+///
 /// # Errors
 /// Most methods can raise [`NulError`][crate::Error::NulError] if a passed
 /// string contains an internal nul byte. Only [`run`][Command::run] can raise
 /// different errors.
-#[derive(Clone, Debug)]
+#[derive(Debug)]
 pub struct Command {
     args: SmallVec<CString>,
+    format: Option<CString>,
+    input: Option<CString>,
+    output: Option<CString>,
+    output_dir: Option<PathBuf>,
+    verify_output: bool,
+    deterministic: bool,
+    incremental: bool,
+    pre_process: Hooks<PreProcess>,
+    post_process: Hooks<PostProcess>,
+    background: Option<Color>,
     gs: Option<CString>,
+    retry: Option<RetryPolicy>,
+    temp_dir: Option<PathBuf>,
+    deferred: Option<NulError>,
+    /// Format and the extension of its driver, looked up by
+    /// [`prepare`][Command::prepare].
+    extension: Option<(CString, String)>,
+    ptrs: Ptrs,
 }
 
 impl Command {
@@ -42,10 +143,34 @@ impl Command {
     /// using [`arg`][Command::arg], [`args`][Command::args], or
     /// [`args_slice`][Command::args_slice].
     pub fn new() -> Self {
-        Self {
+        let mut command = Self {
             args: smallvec![CString::new("pstoedit").unwrap()],
+            format: None,
+            input: None,
+            output: None,
+            output_dir: None,
+            verify_output: false,
+            deterministic: false,
+            incremental: false,
+            pre_process: Hooks::default(),
+            post_process: Hooks::default(),
+            background: None,
             gs: None,
-        }
+            retry: None,
+            temp_dir: None,
+            deferred: None,
+            extension: None,
+            ptrs: Ptrs::default(),
+        };
+        command.updated();
+        command
+    }
+
+    /// Create a consuming builder for a command.
+    ///
+    /// See [`CommandBuilder`] for details.
+    pub fn builder() -> CommandBuilder {
+        CommandBuilder::new()
     }
 
     /// Add a single argument.
@@ -56,7 +181,18 @@ impl Command {
         S: Into<Vec<u8>>,
     {
         self.args.push(CString::new(arg.into())?);
-        Ok(self)
+        Ok(self.updated())
+    }
+
+    /// Add a single argument that is already a C string.
+    ///
+    /// Unlike [`arg`][Command::arg], this cannot fail, as the argument cannot
+    /// contain an internal nul byte. The string is still copied; use
+    /// [`CommandRef`][crate::CommandRef] to run pstoedit with borrowed
+    /// arguments instead.
+    pub fn arg_cstr(&mut self, arg: &CStr) -> &mut Self {
+        self.args.push(arg.to_owned());
+        self.updated()
     }
 
     /// Add multiple arguments.
@@ -110,84 +246,2224 @@ impl Command {
         Ok(self)
     }
 
-    /// Specify ghostscript executable.
+    /// Add multiple arguments from slice of OS strings.
     ///
-    /// By default pstoedit tries to automatically determine this value. The
-    /// specifics of this are platform-dependent.
+    /// This allows passing arguments obtained from the operating system, such
+    /// as through [`env::args_os`][std::env::args_os], without lossy
+    /// conversion. See [`args_slice`][Command::args_slice] for details.
     ///
     /// # Examples
     /// ```no_run
     /// use pstoedit::Command;
+    /// use std::env;
     ///
     /// pstoedit::init()?;
-    /// // Use personal ghostscript executable that is not in PATH
-    /// let gs = "/home/user/projects/ghostscript/bin/gs";
-    /// Command::new().arg("-gstest")?.gs(gs)?.run()?;
+    /// let args: Vec<_> = env::args_os().skip(1).collect();
+    /// Command::new().args_os_slice(&args)?.run()?;
     /// # Ok::<(), pstoedit::Error>(())
     /// ```
-    pub fn gs<S>(&mut self, gs: S) -> Result<&mut Self>
+    ///
+    /// # Errors
+    /// [`NulError`][crate::Error::NulError] if a passed string contains an
+    /// internal nul byte. On platforms other than Unix,
+    /// [`IoError`][crate::Error::IoError] if a passed string is not valid
+    /// UTF-8. Only the arguments before this string will have been added.
+    pub fn args_os_slice<S>(&mut self, args: &[S]) -> Result<&mut Self>
     where
-        S: Into<Vec<u8>>,
+        S: AsRef<OsStr>,
     {
-        self.gs = Some(CString::new(gs.into())?);
+        for arg in args {
+            self.arg(crate::os_str_bytes(arg.as_ref())?)?;
+        }
         Ok(self)
     }
 
-    /// Run the command.
+    /// Add flag followed by its value.
     ///
-    /// This can be done multiple times for the same [`Command`].
+    /// The value is formatted using its [`Display`][fmt::Display]
+    /// implementation.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use pstoedit::Command;
+    ///
+    /// pstoedit::init()?;
+    /// Command::new()
+    ///     .option("-scale", 2.5)?
+    ///     .option("-rotate", 90)?
+    ///     .format("svg")?
+    ///     .input("input.ps")?
+    ///     .output("output.svg")?
+    ///     .run()?;
+    /// # Ok::<(), pstoedit::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    /// [`NulError`][crate::Error::NulError] if the flag or formatted value
+    /// contains an internal nul byte. Neither will have been added.
+    pub fn option<V>(&mut self, flag: &str, value: V) -> Result<&mut Self>
+    where
+        V: fmt::Display,
+    {
+        let flag = CString::new(flag)?;
+        let value = CString::new(value.to_string())?;
+        self.args.push(flag);
+        self.args.push(value);
+        Ok(self.updated())
+    }
+
+    /// Remove all arguments, keeping the program name.
+    ///
+    /// The format, input and output set using [`format`][Command::format],
+    /// [`input`][Command::input] and [`output`][Command::output], and other
+    /// configuration of the command, are kept. A nul byte error deferred by
+    /// [`Extend`] is discarded together with the arguments.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use pstoedit::Command;
+    ///
+    /// pstoedit::init()?;
+    /// let mut cmd = Command::new();
+    /// cmd.format("svg")?.input("input.ps")?.output("output.svg")?;
+    /// cmd.arg("-dt")?.run()?;
+    /// // Convert again without the -dt flag
+    /// cmd.clear_args().run()?;
+    /// # Ok::<(), pstoedit::Error>(())
+    /// ```
+    pub fn clear_args(&mut self) -> &mut Self {
+        self.args.truncate(1);
+        self.deferred = None;
+        self.updated()
+    }
+
+    /// Remove all occurrences of a flag without value, e.g. `"-dt"`.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use pstoedit::Command;
+    ///
+    /// pstoedit::init()?;
+    /// let mut template = Command::new();
+    /// template.args_slice(&["-dt", "-f", "svg"])?;
+    /// let mut cmd = template.clone();
+    /// cmd.remove_flag("-dt").args_slice(&["input.ps", "output.svg"])?.run()?;
+    /// # Ok::<(), pstoedit::Error>(())
+    /// ```
+    pub fn remove_flag(&mut self, flag: &str) -> &mut Self {
+        self.set_flag(flag, false)
+    }
+
+    /// Remove all occurrences of a flag together with the value following
+    /// it, e.g. `"-scale"`.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use pstoedit::Command;
+    ///
+    /// pstoedit::init()?;
+    /// let mut cmd = Command::new();
+    /// cmd.option("-scale", 2)?.args_slice(&["-f", "svg", "input.ps", "output.svg"])?;
+    /// cmd.remove_option("-scale").option("-scale", 3)?.run()?;
+    /// # Ok::<(), pstoedit::Error>(())
+    /// ```
+    pub fn remove_option(&mut self, flag: &str) -> &mut Self {
+        // Skip the program name
+        let mut i = 1;
+        while i < self.args.len() {
+            if self.args[i].as_bytes() == flag.as_bytes() {
+                let end = (i + 2).min(self.args.len());
+                self.args.drain(i..end);
+            } else {
+                i += 1;
+            }
+        }
+        self.updated()
+    }
+
+    /// Specify output format.
+    ///
+    /// This is equivalent to passing `-f` and `format` as arguments, and can
+    /// include driver options (e.g. `"svg:-nfw"`). Setting the format again
+    /// replaces the previous value.
     ///
     /// # Examples
     /// See [`Command`][Command#examples].
+    pub fn format<S>(&mut self, format: S) -> Result<&mut Self>
+    where
+        S: Into<Vec<u8>>,
+    {
+        self.format = Some(CString::new(format.into())?);
+        Ok(self.updated())
+    }
+
+    /// Specify output format with driver-specific options.
+    ///
+    /// This is equivalent to [`format`][Command::format], but checks that the
+    /// options can be passed to pstoedit.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use pstoedit::formats::DriverSpec;
+    /// use pstoedit::Command;
+    ///
+    /// pstoedit::init()?;
+    /// let mut spec = DriverSpec::new("svg");
+    /// spec.flag("-nfw").option("-title", "Annual report");
+    /// Command::new()
+    ///     .format_with_options(&spec)?
+    ///     .input("input.ps")?
+    ///     .output("output.svg")?
+    ///     .run()?;
+    /// # Ok::<(), pstoedit::Error>(())
+    /// ```
     ///
     /// # Errors
-    /// - [`NotInitialized`][crate::Error::NotInitialized] if
-    /// [`init`][crate::init] was not called successfully.
-    /// - [`PstoeditError`][crate::Error::PstoeditError] if pstoedit returns
-    /// with a non-zero status code.
-    pub fn run(&self) -> Result<()> {
-        crate::pstoedit_cstr(&self.args, self.gs.as_ref())
+    /// - [`InvalidDriverOption`][crate::Error::InvalidDriverOption] if the
+    ///   driver name is empty or contains a colon or whitespace, or an option
+    ///   contains a double quote.
+    /// - [`NulError`][crate::Error::NulError] if the driver name or an option
+    ///   contains a nul byte.
+    pub fn format_with_options(&mut self, spec: &DriverSpec) -> Result<&mut Self> {
+        spec.check()?;
+        self.format(spec.to_string())
     }
-}
 
-impl Default for Command {
-    fn default() -> Self {
-        Self::new()
+    /// Specify input file.
+    ///
+    /// The input file is passed after all other arguments. Setting the input
+    /// again replaces the previous value.
+    ///
+    /// # Examples
+    /// See [`Command`][Command#examples].
+    pub fn input<S>(&mut self, input: S) -> Result<&mut Self>
+    where
+        S: Into<Vec<u8>>,
+    {
+        self.input = Some(CString::new(input.into())?);
+        Ok(self.updated())
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::env;
+    /// Specify output file.
+    ///
+    /// The output file is passed after the input file, so an input file
+    /// should be set as well. Setting the output again replaces the previous
+    /// value.
+    ///
+    /// If no format is set, neither using [`format`][Command::format] nor
+    /// with a `-f` argument, the driver is selected by the extension of the
+    /// output file when the command is run, see
+    /// [`DriverInfo::find_by_extension`].
+    ///
+    /// # Examples
+    /// See [`Command`][Command#examples].
+    pub fn output<S>(&mut self, output: S) -> Result<&mut Self>
+    where
+        S: Into<Vec<u8>>,
+    {
+        self.output = Some(CString::new(output.into())?);
+        Ok(self.updated())
+    }
 
-    fn prep() {
-        crate::init().unwrap();
-        // Ensure ghostscript is not obtained through environment
-        env::set_var("GS", "should_not_be_used");
+    /// Specify input file by UTF-8 path.
+    ///
+    /// This is equivalent to [`input`][Command::input].
+    #[cfg(feature = "camino")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "camino")))]
+    pub fn input_utf8<P>(&mut self, input: P) -> Result<&mut Self>
+    where
+        P: AsRef<Utf8Path>,
+    {
+        self.input(input.as_ref().as_str())
     }
 
-    #[test]
-    fn arg_gs() {
-        prep();
-        Command::new()
-            .arg("-gstest")
-            .unwrap()
-            .gs("gs")
-            .unwrap()
-            .run()
-            .unwrap();
+    /// Specify output file by UTF-8 path.
+    ///
+    /// This is equivalent to [`output`][Command::output].
+    #[cfg(feature = "camino")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "camino")))]
+    pub fn output_utf8<P>(&mut self, output: P) -> Result<&mut Self>
+    where
+        P: AsRef<Utf8Path>,
+    {
+        self.output(output.as_ref().as_str())
     }
 
-    #[test]
-    fn args_gs() {
-        prep();
-        Command::new()
-            .args_slice(&["-gstest"])
-            .unwrap()
-            .gs("gs")
-            .unwrap()
-            .run()
+    /// Specify directory for the output file, deriving its name.
+    ///
+    /// If no output file is set using [`output`][Command::output], the output
+    /// file is placed in `dir`, named after the file stem of the input set
+    /// using [`input`][Command::input] and the extension of the driver. The
+    /// name is derived when the command is run.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use pstoedit::Command;
+    ///
+    /// pstoedit::init()?;
+    /// let mut cmd = Command::new();
+    /// cmd.format("svg")?.output_dir("out");
+    /// for input in &["a.ps", "b.ps"] {
+    ///     // Writes out/a.svg and out/b.svg
+    ///     cmd.input(*input)?.run()?;
+    /// }
+    /// # Ok::<(), pstoedit::Error>(())
+    /// ```
+    pub fn output_dir<P>(&mut self, dir: P) -> &mut Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.output_dir = Some(dir.into());
+        self
+    }
+
+    /// Verify that the output file is written.
+    ///
+    /// pstoedit may report success without writing any output for unusual
+    /// input. If enabled, a run only succeeds if the output file set using
+    /// [`output`][Command::output] or [`output_dir`][Command::output_dir]
+    /// exists and is not empty. Commands without such an output file, and
+    /// pages written by [`run_split`][Command::run_split], are not verified.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use pstoedit::Command;
+    ///
+    /// pstoedit::init()?;
+    /// Command::new()
+    ///     .verify_output(true)
+    ///     .format("svg")?
+    ///     .input("input.ps")?
+    ///     .output("output.svg")?
+    ///     .run()?;
+    /// # Ok::<(), pstoedit::Error>(())
+    /// ```
+    pub fn verify_output(&mut self, enable: bool) -> &mut Self {
+        self.verify_output = enable;
+        self
+    }
+
+    /// Strip volatile content, such as creation dates, from the output file.
+    ///
+    /// If enabled, the output file set using [`output`][Command::output] or
+    /// [`output_dir`][Command::output_dir] is rewritten after every
+    /// successful run according to the rules for the driver, so identical
+    /// input yields identical output. See
+    /// [`deterministic`][crate::deterministic] for details.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use pstoedit::Command;
+    ///
+    /// pstoedit::init()?;
+    /// Command::new()
+    ///     .deterministic(true)
+    ///     .format("ps")?
+    ///     .input("input.ps")?
+    ///     .output("output.ps")?
+    ///     .run()?;
+    /// # Ok::<(), pstoedit::Error>(())
+    /// ```
+    pub fn deterministic(&mut self, enable: bool) -> &mut Self {
+        self.deterministic = enable;
+        self
+    }
+
+    /// Skip runs whose output file is up to date.
+    ///
+    /// If enabled, a successful run is recorded in a manifest named
+    /// `.pstoedit-cache` in the directory of the output file set using
+    /// [`output`][Command::output] or [`output_dir`][Command::output_dir],
+    /// keyed by a hash of the contents of the input file, the arguments, and
+    /// the contents of files named by the `-fontmap` and `-include` options.
+    /// Later runs are skipped, including their hooks, if the output file
+    /// exists and none of these changed. Commands without both an input and
+    /// an output file always run.
+    ///
+    /// The hooks registered using [`pre_process`][Command::pre_process] and
+    /// [`post_process`][Command::post_process], other files read by pstoedit
+    /// or the document, and the versions of pstoedit and ghostscript, are not
+    /// part of the key. After upgrading either, remove the manifest to
+    /// convert all files again.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use pstoedit::Command;
+    ///
+    /// pstoedit::init()?;
+    /// let mut cmd = Command::new();
+    /// cmd.incremental(true).format("svg")?.output_dir("out");
+    /// for input in &["a.ps", "b.ps"] {
+    ///     let report = cmd.input(*input)?.run_report()?;
+    ///     if report.cached() {
+    ///         println!("{} is up to date", input);
+    ///     }
+    /// }
+    /// # Ok::<(), pstoedit::Error>(())
+    /// ```
+    pub fn incremental(&mut self, enable: bool) -> &mut Self {
+        self.incremental = enable;
+        self
+    }
+
+    /// Register a hook to rewrite the input file before every run.
+    ///
+    /// The hook receives the path of the input file set using
+    /// [`input`][Command::input] and the path of a temporary file, to which
+    /// it must write the rewritten input, e.g. with a `setpagedevice` fix
+    /// injected. The temporary file, which has the same file name
+    /// extension as the input, is passed to pstoedit instead and removed
+    /// afterwards. Hooks run in order of registration, each receiving the
+    /// file written by the previous one, and are not run for commands without
+    /// such an input file.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use pstoedit::Command;
+    /// use std::fs;
+    ///
+    /// pstoedit::init()?;
+    /// Command::new()
+    ///     .pre_process(|input, rewritten| {
+    ///         let ps = fs::read_to_string(input)?;
+    ///         let fix = "<< /PageSize [595 842] >> setpagedevice\n";
+    ///         Ok(fs::write(rewritten, ps.replacen("\n", &format!("\n{}", fix), 1))?)
+    ///     })
+    ///     .format("svg")?
+    ///     .input("input.ps")?
+    ///     .output("output.svg")?
+    ///     .run()?;
+    /// # Ok::<(), pstoedit::Error>(())
+    /// ```
+    pub fn pre_process<F>(&mut self, hook: F) -> &mut Self
+    where
+        F: Fn(&Path, &Path) -> Result<()> + Send + Sync + 'static,
+    {
+        self.pre_process.push(Arc::new(hook));
+        self
+    }
+
+    /// Register a hook to process the output file after a successful run.
+    ///
+    /// The hook receives the path of the output file set using
+    /// [`output`][Command::output] or [`output_dir`][Command::output_dir],
+    /// and may modify the file in place, e.g. to minify it. Hooks run in
+    /// order of registration, after [`deterministic`][Command::deterministic]
+    /// and [`verify_output`][Command::verify_output], and are not run for
+    /// commands without such an output file. An error returned by a hook is
+    /// returned by the run, without retrying.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use pstoedit::Command;
+    /// use std::fs;
+    ///
+    /// pstoedit::init()?;
+    /// Command::new()
+    ///     .post_process(|path| {
+    ///         let svg = fs::read_to_string(path)?;
+    ///         let lines: Vec<_> = svg.lines().map(str::trim).collect();
+    ///         Ok(fs::write(path, lines.concat())?)
+    ///     })
+    ///     .format("svg")?
+    ///     .input("input.ps")?
+    ///     .output("output.svg")?
+    ///     .run()?;
+    /// # Ok::<(), pstoedit::Error>(())
+    /// ```
+    pub fn post_process<F>(&mut self, hook: F) -> &mut Self
+    where
+        F: Fn(&Path) -> Result<()> + Send + Sync + 'static,
+    {
+        self.post_process.push(Arc::new(hook));
+        self
+    }
+
+    /// Use the bounding box from the input file.
+    ///
+    /// By default pstoedit determines the bounding box itself. If enabled, the
+    /// `%%BoundingBox` comment of the input is used instead. With the `log` or
+    /// `tracing` feature, a warning is emitted when running a command whose
+    /// input lacks this comment.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use pstoedit::Command;
+    ///
+    /// pstoedit::init()?;
+    /// Command::new()
+    ///     .use_input_bbox(true)
+    ///     .format("svg")?
+    ///     .input("input.eps")?
+    ///     .output("output.svg")?
+    ///     .run()?;
+    /// # Ok::<(), pstoedit::Error>(())
+    /// ```
+    pub fn use_input_bbox(&mut self, enable: bool) -> &mut Self {
+        self.set_flag(USE_INPUT_BBOX, enable)
+    }
+
+    /// Set the color model in which colors are passed to the driver.
+    ///
+    /// By default, colors are passed in the color model of the document, so
+    /// drivers that support CMYK can preserve it. Setting the color model
+    /// again replaces the previous value.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use pstoedit::{ColorModel, Command};
+    ///
+    /// pstoedit::init()?;
+    /// Command::new()
+    ///     .color_model(ColorModel::Rgb)
+    ///     .format("svg")?
+    ///     .input("input.eps")?
+    ///     .output("output.svg")?
+    ///     .run()?;
+    /// # Ok::<(), pstoedit::Error>(())
+    /// ```
+    pub fn color_model(&mut self, model: ColorModel) -> &mut Self {
+        self.set_flag(RGB, model == ColorModel::Rgb)
+    }
+
+    /// Paint every page in a background color before it is drawn, or remove
+    /// the background with `None`.
+    ///
+    /// pstoedit has no option for this, so a temporary copy of the input is
+    /// rewritten to fill each page, as if by a hook registered using
+    /// [`pre_process`][Command::pre_process] that runs after all other hooks.
+    /// The background becomes a filled rectangle in the output, such that
+    /// figures can match the page they are placed on.
+    ///
+    /// # Errors
+    /// Running the command raises
+    /// [`UnsupportedInput`][crate::Error::UnsupportedInput] if the input is
+    /// not PostScript or EPS in text form, e.g. PDF.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use pstoedit::Command;
+    ///
+    /// pstoedit::init()?;
+    /// Command::new()
+    ///     .background("#fdf6e3".parse::<pstoedit::Color>()?)
+    ///     .format("svg")?
+    ///     .input("input.ps")?
+    ///     .output("output.svg")?
+    ///     .run()?;
+    /// # Ok::<(), pstoedit::Error>(())
+    /// ```
+    pub fn background<C>(&mut self, color: C) -> &mut Self
+    where
+        C: Into<Option<Color>>,
+    {
+        self.background = color.into();
+        self
+    }
+
+    /// Set the flatness with which curves are approximated by line segments,
+    /// or restore the default of ghostscript with `None`.
+    ///
+    /// The flatness is the maximum distance in device pixels between a curve
+    /// and its approximation. Lower values give more precise coordinates at
+    /// the cost of larger output files, higher values fewer line segments.
+    /// Drivers that support curves natively are only affected by it where
+    /// they approximate curves, see
+    /// [`Capabilities::CURVETO`][crate::driver_info::Capabilities::CURVETO].
+    /// The value is clamped to the range accepted by PostScript, 0.2 to 100.
+    /// Setting the flatness again replaces the previous value.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use pstoedit::Command;
+    ///
+    /// pstoedit::init()?;
+    /// Command::new()
+    ///     .flatness(0.2)
+    ///     .format("dxf")?
+    ///     .input("input.ps")?
+    ///     .output("output.dxf")?
+    ///     .run()?;
+    /// # Ok::<(), pstoedit::Error>(())
+    /// ```
+    pub fn flatness<F>(&mut self, flatness: F) -> &mut Self
+    where
+        F: Into<Option<f64>>,
+    {
+        match flatness.into() {
+            Some(flatness) => {
+                let flatness = if flatness.is_nan() {
+                    0.2
+                } else {
+                    flatness.clamp(0.2, 100.0)
+                };
+                self.set_option(FLATNESS, CString::new(flatness.to_string()).unwrap())
+            }
+            None => self.remove_option(FLATNESS),
+        }
+    }
+
+    /// Set whether text is passed to the driver as text or drawn as
+    /// outlines.
+    ///
+    /// Outlines preserve the appearance of text in exotic or unavailable
+    /// fonts, at the cost of the text no longer being editable or
+    /// searchable. Setting the mode again replaces the previous value.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use pstoedit::{Command, TextMode};
+    ///
+    /// pstoedit::init()?;
+    /// Command::new()
+    ///     .text_mode(TextMode::Outline)
+    ///     .format("svg")?
+    ///     .input("input.ps")?
+    ///     .output("output.svg")?
+    ///     .run()?;
+    /// # Ok::<(), pstoedit::Error>(())
+    /// ```
+    pub fn text_mode(&mut self, mode: TextMode) -> &mut Self {
+        for flag in TextMode::FLAGS.iter() {
+            self.set_flag(flag, false);
+        }
+        match mode.flag() {
+            Some(flag) => self.set_flag(flag, true),
+            None => self,
+        }
+    }
+
+    /// Handle Type 2 (CFF) fonts, as embedded in PDF documents, as Type 1
+    /// fonts (`-t2fontsast1`).
+    ///
+    /// This lets drivers that support Type 1 fonts pass the glyphs of such
+    /// fonts through instead of falling back to other fonts or outlines.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use pstoedit::Command;
+    ///
+    /// pstoedit::init()?;
+    /// Command::new()
+    ///     .type2_fonts_as_type1(true)
+    ///     .format("ps")?
+    ///     .input("input.pdf")?
+    ///     .output("output.ps")?
+    ///     .run()?;
+    /// # Ok::<(), pstoedit::Error>(())
+    /// ```
+    pub fn type2_fonts_as_type1(&mut self, enable: bool) -> &mut Self {
+        self.set_flag(TYPE2_AS_TYPE1, enable)
+    }
+
+    /// Set the font that replaces fonts which are not embedded in the
+    /// document and not available, e.g. `"Helvetica"`.
+    ///
+    /// By default the replacement depends on the fonts installed where
+    /// pstoedit runs, so setting it makes text conversions predictable
+    /// across machines. Setting the font again replaces the previous value.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use pstoedit::Command;
+    ///
+    /// pstoedit::init()?;
+    /// Command::new()
+    ///     .default_font("Courier")?
+    ///     .format("svg")?
+    ///     .input("input.ps")?
+    ///     .output("output.svg")?
+    ///     .run()?;
+    /// # Ok::<(), pstoedit::Error>(())
+    /// ```
+    pub fn default_font<S>(&mut self, font: S) -> Result<&mut Self>
+    where
+        S: Into<Vec<u8>>,
+    {
+        let font = CString::new(font.into())?;
+        Ok(self.set_option(DEFAULT_FONT, font))
+    }
+
+    /// Set the default font like [`default_font`][Command::default_font],
+    /// after verifying that it is listed in the fontmap file at `fontmap`,
+    /// which is then also used for the run.
+    ///
+    /// A font is listed if the fontmap maps it to a replacement, or maps
+    /// another font to it.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use pstoedit::Command;
+    ///
+    /// pstoedit::init()?;
+    /// Command::new()
+    ///     .default_font_in("Arial", "fonts/msfonts.fmp")?
+    ///     .format("svg")?
+    ///     .input("input.ps")?
+    ///     .output("output.svg")?
+    ///     .run()?;
+    /// # Ok::<(), pstoedit::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    /// - [`IoError`][crate::Error::IoError] if the fontmap cannot be read
+    /// - [`UnknownFont`][crate::Error::UnknownFont] if the font is not listed
+    /// - [`NulError`][crate::Error::NulError] if the font or path contains an
+    ///   internal nul byte
+    ///
+    /// The command is unchanged if an error is returned.
+    pub fn default_font_in<P>(&mut self, font: &str, fontmap: P) -> Result<&mut Self>
+    where
+        P: AsRef<Path>,
+    {
+        let fontmap = fontmap.as_ref();
+        if !options::fontmap_contains(fontmap, font)? {
+            return Err(Error::UnknownFont(font.to_owned()));
+        }
+        let font = CString::new(font)?;
+        let fontmap = CString::new(crate::path_bytes(fontmap)?)?;
+        self.set_option(FONTMAP, fontmap);
+        Ok(self.set_option(DEFAULT_FONT, font))
+    }
+
+    /// Background color, see [`background`][Command::background].
+    pub(crate) fn background_color(&self) -> Option<Color> {
+        self.background
+    }
+
+    /// Specify ghostscript executable.
+    ///
+    /// By default pstoedit tries to automatically determine this value. The
+    /// specifics of this are platform-dependent.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use pstoedit::Command;
+    ///
+    /// pstoedit::init()?;
+    /// // Use personal ghostscript executable that is not in PATH
+    /// let gs = "/home/user/projects/ghostscript/bin/gs";
+    /// Command::new().arg("-gstest")?.gs(gs)?.run()?;
+    /// # Ok::<(), pstoedit::Error>(())
+    /// ```
+    pub fn gs<S>(&mut self, gs: S) -> Result<&mut Self>
+    where
+        S: Into<Vec<u8>>,
+    {
+        self.gs = Some(CString::new(gs.into())?);
+        Ok(self)
+    }
+
+    /// Specify registry key under which ghostscript is looked up.
+    ///
+    /// By default pstoedit searches the registry keys of the ghostscript
+    /// distributions. Applications bundling their own ghostscript can register
+    /// it under a different key, e.g. `HKEY_LOCAL_MACHINE\SOFTWARE\Vendor`,
+    /// and pass that key here. Setting the key again replaces the previous
+    /// value.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use pstoedit::Command;
+    ///
+    /// pstoedit::init()?;
+    /// Command::new()
+    ///     .gs_registry_base(r"HKEY_LOCAL_MACHINE\SOFTWARE\Vendor")?
+    ///     .args_slice(&["-f", "svg", "input.ps", "output.svg"])?
+    ///     .run()?;
+    /// # Ok::<(), pstoedit::Error>(())
+    /// ```
+    #[cfg(windows)]
+    #[cfg_attr(docsrs, doc(cfg(windows)))]
+    pub fn gs_registry_base<S>(&mut self, base: S) -> Result<&mut Self>
+    where
+        S: Into<Vec<u8>>,
+    {
+        let base = CString::new(base.into())?;
+        Ok(self.set_option(GS_REGISTRY_BASE, base))
+    }
+
+    /// Retry failed runs according to a policy.
+    ///
+    /// By default, failed runs are not retried.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use pstoedit::{Command, RetryPolicy};
+    /// use std::time::Duration;
+    ///
+    /// pstoedit::init()?;
+    /// Command::new()
+    ///     .retry(RetryPolicy::new(3).delay(Duration::from_secs(1)))
+    ///     .args_slice(&["-f", "svg", "input.ps", "output.svg"])?
+    ///     .run()?;
+    /// # Ok::<(), pstoedit::Error>(())
+    /// ```
+    pub fn retry(&mut self, policy: RetryPolicy) -> &mut Self {
+        self.retry = Some(policy);
+        self
+    }
+
+    /// Remove duplicate and conflicting options from the arguments.
+    ///
+    /// Options accumulated from layered configuration may repeat a flag, such
+    /// as two `-f` options, or combine mutually exclusive flags. Identical
+    /// repetitions are merged, and conflicts are resolved according to
+    /// `mode`. The format set using [`format`][Command::format] takes part as
+    /// the last `-f` option.
+    ///
+    /// # Examples
+    /// ```
+    /// use pstoedit::{Command, Normalize};
+    ///
+    /// let mut cmd = Command::new();
+    /// cmd.args_slice(&["-f", "svg", "-dt"])?.format("pdf")?;
+    /// cmd.normalize(Normalize::KeepLast)?;
+    /// assert!(cmd.normalize(Normalize::Reject).is_ok());
+    /// # Ok::<(), pstoedit::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    /// [`ConflictingArgs`][crate::Error::ConflictingArgs] if `mode` is
+    /// [`Reject`][Normalize::Reject] and options conflict.
+    pub fn normalize(&mut self, mode: Normalize) -> Result<&mut Self> {
+        normalize::normalize(&mut self.args, self.format.as_deref(), mode)?;
+        Ok(self.updated())
+    }
+
+    /// Set directory for temporary files of pstoedit and ghostscript.
+    ///
+    /// Takes precedence over the directory set by
+    /// [`set_temp_dir`][crate::set_temp_dir], see there for details. Like
+    /// that directory, it only applies to commands run using
+    /// [`Subprocess`][crate::engine::Subprocess], as the environment of the
+    /// process cannot be changed soundly for in-process runs.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use pstoedit::engine::Subprocess;
+    /// use pstoedit::Command;
+    ///
+    /// let mut cmd = Command::new();
+    /// cmd.temp_dir("/scratch/pstoedit")
+    ///     .args_slice(&["-f", "svg", "input.ps", "output.svg"])?;
+    /// cmd.run_with(&Subprocess::new())?;
+    /// # Ok::<(), pstoedit::Error>(())
+    /// ```
+    pub fn temp_dir<P>(&mut self, dir: P) -> &mut Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.temp_dir = Some(dir.into());
+        self
+    }
+
+    /// Do the work of running the command that does not depend on its input
+    /// up front, so that it is not repeated on every run.
+    ///
+    /// The arguments are kept as C strings, so running a command does not
+    /// convert them again anyway. What remains is looking up drivers: if no
+    /// format is set, it is selected by the extension of the output file and
+    /// set as if by [`format`][Command::format], and if an
+    /// [`output_dir`][Command::output_dir] is set, the extension of the driver
+    /// is looked up and kept for naming the output files of later runs, as
+    /// long as the format is not changed. This speeds up converting many
+    /// files with the same command.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use pstoedit::Command;
+    ///
+    /// pstoedit::init()?;
+    /// let mut cmd = Command::new();
+    /// cmd.format("svg")?.output_dir("out").prepare()?;
+    /// for input in &["a.ps", "b.ps"] {
+    ///     cmd.input(*input)?.run()?;
+    /// }
+    /// # Ok::<(), pstoedit::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    /// - [`NotInitialized`][crate::Error::NotInitialized] if
+    ///   [`init`][crate::init] was not called successfully.
+    /// - [`NulError`][crate::Error::NulError] if an argument added through
+    ///   [`Extend`] contained an internal nul byte.
+    /// - [`UnknownFormat`][crate::Error::UnknownFormat] if an output directory
+    ///   is set, but the driver of the format is not available.
+    /// - [`AmbiguousExtension`][crate::Error::AmbiguousExtension] if the
+    ///   driver is to be selected by the extension of the output file, but
+    ///   multiple drivers match.
+    pub fn prepare(&mut self) -> Result<&mut Self> {
+        if let Some(err) = &self.deferred {
+            return Err(err.clone().into());
+        }
+        if self.format_name().is_none() {
+            if let Some(format) = self.output_format()? {
+                self.format(format)?;
+            }
+        }
+        if let (Some(format), Some(_)) = (self.format_name(), &self.output_dir) {
+            let extension = self.driver_extension(format)?.into_owned();
+            self.extension = Some((format.to_owned(), extension));
+        }
+        Ok(self)
+    }
+
+    /// Run the command.
+    ///
+    /// This can be done multiple times for the same [`Command`].
+    ///
+    /// # Examples
+    /// See [`Command`][Command#examples].
+    ///
+    /// # Errors
+    /// - [`NotInitialized`][crate::Error::NotInitialized] if
+    ///   [`init`][crate::init] was not called successfully.
+    /// - [`PstoeditError`][crate::Error::PstoeditError] if pstoedit returns
+    ///   with a non-zero status code.
+    /// - [`InterpreterError`][crate::Error::InterpreterError] instead if
+    ///   pstoedit fails because of an error reported by ghostscript.
+    /// - [`MissingGhostscript`][crate::Error::MissingGhostscript] if the
+    ///   ghostscript executable set using [`gs`][Command::gs] does not exist,
+    ///   or pstoedit fails and its ghostscript test (`-gstest`) fails as well.
+    /// - [`NulError`][crate::Error::NulError] if an argument added through
+    ///   [`Extend`] contained an internal nul byte.
+    /// - [`UnknownFormat`][crate::Error::UnknownFormat] if the output file
+    ///   name is derived from a driver that is not available, see
+    ///   [`output_dir`][Command::output_dir].
+    /// - [`MissingOutputName`][crate::Error::MissingOutputName] if the
+    ///   output file name is to be derived, but no input or format is set.
+    /// - Errors returned by hooks, see [`pre_process`][Command::pre_process]
+    ///   and [`post_process`][Command::post_process].
+    /// - [`EmptyOutput`][crate::Error::EmptyOutput] if the output file is
+    ///   missing or empty, see [`verify_output`][Command::verify_output].
+    /// - [`AmbiguousExtension`][crate::Error::AmbiguousExtension] if the
+    ///   driver is to be selected by the extension of the output file, but
+    ///   multiple drivers match.
+    pub fn run(&self) -> Result<()> {
+        self.run_via(&Command::run_ffi)
+    }
+
+    /// Run the command using an engine.
+    ///
+    /// This is equivalent to [`engine.run(self)`][Engine::run], so that the
+    /// engine can be chosen at run time without changing call sites.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use pstoedit::engine::{Engine, InProcess, Subprocess};
+    /// use pstoedit::Command;
+    ///
+    /// let engine: Box<dyn Engine> = if std::env::var_os("PSTOEDIT_ISOLATE").is_some() {
+    ///     Box::new(Subprocess::new())
+    /// } else {
+    ///     pstoedit::init()?;
+    ///     Box::new(InProcess)
+    /// };
+    /// Command::new()
+    ///     .format("svg")?
+    ///     .input("input.ps")?
+    ///     .output("output.svg")?
+    ///     .run_with(&*engine)?;
+    /// # Ok::<(), pstoedit::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    /// Errors of the engine, see [`run`][Command::run] for the in-process
+    /// engine.
+    pub fn run_with<E>(&self, engine: &E) -> Result<()>
+    where
+        E: Engine + ?Sized,
+    {
+        engine.run(self)
+    }
+
+    /// Run the command and report on the run.
+    ///
+    /// This behaves like [`run`][Command::run], but additionally measures the
+    /// run, inspects the output file set using [`output`][Command::output],
+    /// and collects the [warnings][crate::Warning] reported by pstoedit and
+    /// ghostscript.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use pstoedit::Command;
+    ///
+    /// pstoedit::init()?;
+    /// let report = Command::new()
+    ///     .format("svg")?
+    ///     .input("input.ps")?
+    ///     .output("output.svg")?
+    ///     .run_report()?;
+    /// println!("Converted in {:?}", report.elapsed());
+    /// for warning in report.warnings() {
+    ///     eprintln!("warning: {}", warning);
+    /// }
+    /// # Ok::<(), pstoedit::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    /// See [`run`][Command::run].
+    pub fn run_report(&self) -> Result<RunReport> {
+        self.run_report_via(&Command::run_ffi)
+    }
+
+    /// Run the command, writing every page to a separate file.
+    ///
+    /// The pages are written to `dir`, named according to `template`. The
+    /// `{stem}` placeholder refers to the input set using
+    /// [`input`][Command::input]. Any output set using
+    /// [`output`][Command::output] is replaced. The output format should
+    /// support multiple pages.
+    ///
+    /// Returns the paths of the pages, in order. Output verification and
+    /// stripping of volatile content apply to every page, while
+    /// [`incremental`][Command::incremental] runs are not supported and always
+    /// convert the input.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use pstoedit::{Command, OutputTemplate};
+    ///
+    /// pstoedit::init()?;
+    /// let template = OutputTemplate::new("{stem}-{page:03}.svg")?;
+    /// let pages = Command::new()
+    ///     .format("svg")?
+    ///     .input("input.ps")?
+    ///     .run_split("pages", &template)?;
+    /// println!("Wrote {} pages", pages.len());
+    /// # Ok::<(), pstoedit::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    /// - Errors of [`run`][Command::run].
+    /// - [`OutputCollision`][crate::Error::OutputCollision] if the names of
+    ///   two pages are the same, or a file with that name exists. No pages are
+    ///   written in that case.
+    /// - [`IoError`][crate::Error::IoError] if the pages cannot be moved to
+    ///   `dir`.
+    pub fn run_split<P>(&self, dir: P, template: &OutputTemplate) -> Result<Vec<PathBuf>>
+    where
+        P: AsRef<Path>,
+    {
+        let pages = TempDir::new()?;
+        let output = crate::path_bytes(&pages.path().join(output::PAGE_PATTERN))?;
+        let mut split = self.clone();
+        // The output is a pattern rather than a file, so the pages are
+        // inspected separately once moved
+        split.verify_output = false;
+        split.deterministic = false;
+        split.incremental = false;
+        split.set_flag(SPLIT, true).output(output)?.run()?;
+        let stem = self
+            .input
+            .as_deref()
+            .and_then(crate::cstr_path)
+            .and_then(Path::file_stem)
+            .map(|stem| stem.to_string_lossy())
+            .unwrap_or_default();
+        let pages = output::move_pages(pages.path(), dir.as_ref(), template, &stem)?;
+        for page in &pages {
+            self.strip_file(page)?;
+            self.check_file(page)?;
+        }
+        Ok(pages)
+    }
+
+    /// Run the command, writing every page to a separate file, and return
+    /// UTF-8 paths.
+    ///
+    /// This is equivalent to [`run_split`][Command::run_split]. The paths of
+    /// the pages are valid UTF-8, as `dir` is and the name of the input is
+    /// converted lossily.
+    ///
+    /// # Errors
+    /// See [`run_split`][Command::run_split].
+    #[cfg(feature = "camino")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "camino")))]
+    pub fn run_split_utf8<P>(&self, dir: P, template: &OutputTemplate) -> Result<Vec<Utf8PathBuf>>
+    where
+        P: AsRef<Utf8Path>,
+    {
+        let pages = self.run_split(dir.as_ref(), template)?.into_iter();
+        let utf8 = |page| Utf8PathBuf::from_path_buf(page).expect("page paths are UTF-8");
+        Ok(pages.map(utf8).collect())
+    }
+
+    /// Run command on a new thread.
+    ///
+    /// Returns a handle that can be polled for the state of the conversion
+    /// without blocking, see [`conversion`][crate::conversion].
+    pub fn spawn(&self) -> ConversionHandle {
+        ConversionHandle::spawn(self.clone())
+    }
+
+    /// Strip volatile content from the output file if enabled, see
+    /// [`deterministic`][Command::deterministic].
+    fn strip_output(&self) -> Result<()> {
+        match self.output.as_deref().and_then(crate::cstr_path) {
+            Some(path) => self.strip_file(path),
+            None => Ok(()),
+        }
+    }
+
+    /// Strip volatile content from an output file like
+    /// [`strip_output`][Command::strip_output].
+    fn strip_file(&self, path: &Path) -> Result<()> {
+        let driver = self.format_name().and_then(|format| format.to_str().ok());
+        let driver = match driver {
+            Some(driver) if self.deterministic => driver,
+            _ => return Ok(()),
+        };
+        if !deterministic::has_rules(driver) {
+            return Ok(());
+        }
+        let data = fs::read(path)?;
+        if let Cow::Owned(stripped) = deterministic::strip_volatile(driver, &data) {
+            fs::write(path, stripped)?;
+        }
+        Ok(())
+    }
+
+    /// Check the output file if enabled, see
+    /// [`verify_output`][Command::verify_output].
+    fn check_output(&self) -> Result<()> {
+        match self.output.as_deref().and_then(crate::cstr_path) {
+            Some(path) => self.check_file(path),
+            None => Ok(()),
+        }
+    }
+
+    /// Check an output file like [`check_output`][Command::check_output].
+    fn check_file(&self, path: &Path) -> Result<()> {
+        if !self.verify_output {
+            return Ok(());
+        }
+        let missing = match fs::metadata(path) {
+            Ok(metadata) if metadata.len() > 0 => return Ok(()),
+            Ok(_) => false,
+            Err(_) => true,
+        };
+        Err(Error::EmptyOutput {
+            path: path.to_owned(),
+            missing,
+        })
+    }
+
+    /// Command ready to run, with the derivations of
+    /// [`resolved`][Command::resolved] and the input rewritten by the hooks
+    /// registered using [`pre_process`][Command::pre_process]. The returned
+    /// directory holds the rewritten input and must be kept until the run is
+    /// finished.
+    fn prepared(&self) -> Result<(Cow<'_, Self>, Option<TempDir>)> {
+        let mut command = self.resolved()?;
+        let input = command.input.as_deref().and_then(crate::cstr_path);
+        let (input, name) = match input.zip(input.and_then(Path::file_name)) {
+            Some(input)
+                if (!self.pre_process.is_empty() || self.background.is_some())
+                    && self.deferred.is_none() =>
+            {
+                input
+            }
+            _ => return Ok((command, None)),
+        };
+        let dir = TempDir::new()?;
+        let mut current = input.to_owned();
+        for (i, hook) in self.pre_process.iter().enumerate() {
+            let mut file_name = OsString::from(format!("{}-", i));
+            file_name.push(name);
+            let rewritten = dir.path().join(file_name);
+            hook(&current, &rewritten)?;
+            current = rewritten;
+        }
+        if let Some(color) = self.background {
+            let mut file_name = OsString::from("background-");
+            file_name.push(name);
+            let rewritten = dir.path().join(file_name);
+            options::write_with_background(&current, &rewritten, color)?;
+            current = rewritten;
+        }
+        command.to_mut().input(crate::path_bytes(&current)?)?;
+        Ok((command, Some(dir)))
+    }
+
+    /// Command with the format and output file name derived, if necessary,
+    /// see [`output`][Command::output] and [`output_dir`][Command::output_dir].
+    pub(crate) fn resolved(&self) -> Result<Cow<'_, Self>> {
+        let mut command = Cow::Borrowed(self);
+        if self.deferred.is_some() {
+            return Ok(command);
+        }
+        if self.format_name().is_none() {
+            if let Some(format) = self.output_format()? {
+                command.to_mut().format(format)?;
+            }
+        }
+        if let (None, Some(dir)) = (&command.output, &self.output_dir) {
+            let output = command.derived_output(dir)?;
+            command.to_mut().output(crate::path_bytes(&output)?)?;
+        }
+        Ok(command)
+    }
+
+    /// Name of the driver matching the extension of the output file, if any.
+    fn output_format(&self) -> Result<Option<String>> {
+        let extension = self
+            .output
+            .as_deref()
+            .and_then(crate::cstr_path)
+            .and_then(Path::extension)
+            .and_then(OsStr::to_str);
+        let extension = match extension {
+            Some(extension) => extension,
+            None => return Ok(None),
+        };
+        let info = DriverInfo::get()?;
+        let driver = info.find_by_extension(extension)?;
+        driver
+            .map(|driver| driver.symbolic_name().map(str::to_owned))
+            .transpose()
+    }
+
+    /// Output file in `dir` named after the input and the extension of the
+    /// driver.
+    fn derived_output(&self, dir: &Path) -> Result<PathBuf> {
+        let stem = self
+            .input
+            .as_deref()
+            .and_then(crate::cstr_path)
+            .and_then(Path::file_stem)
+            .ok_or(Error::MissingOutputName)?;
+        let format = self.format_name().ok_or(Error::MissingOutputName)?;
+        // Appended rather than replaced, as the stem may contain dots itself
+        let mut name = stem.to_owned();
+        let extension = self.driver_extension(format)?;
+        if !extension.is_empty() {
+            name.push(".");
+            name.push(&*extension);
+        }
+        Ok(dir.join(name))
+    }
+
+    /// Extension of the driver of `format`, as kept by
+    /// [`prepare`][Command::prepare] or otherwise looked up.
+    fn driver_extension(&self, format: &CStr) -> Result<Cow<'_, str>> {
+        if let Some((prepared, extension)) = &self.extension {
+            if prepared.as_c_str() == format {
+                return Ok(Cow::Borrowed(extension));
+            }
+        }
+        let format = format.to_str()?;
+        let format = format.split(':').next().unwrap_or(format);
+        let info = DriverInfo::get()?;
+        let driver = info
+            .find(format)
+            .ok_or_else(|| Error::UnknownFormat(format.to_owned()))?;
+        Ok(Cow::Owned(driver.extension()?.to_owned()))
+    }
+
+    /// Format set using [`format`][Command::format], or otherwise the last
+    /// `-f` option.
+    fn format_name(&self) -> Option<&CStr> {
+        self.format.as_deref().or_else(|| {
+            self.args
+                .windows(2)
+                .rev()
+                .find(|pair| pair[0].as_bytes() == b"-f")
+                .map(|pair| pair[1].as_c_str())
+        })
+    }
+
+    /// Run the command, executing pstoedit using `exec`.
+    ///
+    /// This applies everything configured on the command around the actual
+    /// execution, so that engines other than the in-process one only need to
+    /// provide `exec`.
+    pub(crate) fn run_via(&self, exec: Exec<'_>) -> Result<()> {
+        self.run_measured(exec).map(drop)
+    }
+
+    /// Run the command like [`run_via`][Command::run_via], and report on the
+    /// run.
+    pub(crate) fn run_report_via(&self, exec: Exec<'_>) -> Result<RunReport> {
+        let mut report = self.run_measured(exec)?;
+        // Only inspected when reporting, as it reads the input once more
+        report.pages = self.input_pages();
+        Ok(report)
+    }
+
+    /// Run the command like [`run_via`][Command::run_via], and report on the
+    /// run without inspecting the input.
+    fn run_measured(&self, exec: Exec<'_>) -> Result<RunReport> {
+        let entry = if self.incremental && self.deferred.is_none() {
+            cache::Entry::new(&*self.resolved()?, self.deterministic)?
+        } else {
+            None
+        };
+        if let Some(entry) = entry.as_ref().filter(|entry| entry.is_fresh()) {
+            #[cfg(feature = "log")]
+            log::info!("skipping pstoedit, output is up to date");
+            #[cfg(feature = "tracing")]
+            tracing::debug!("skipping pstoedit, output is up to date");
+            return Ok(RunReport {
+                elapsed: Duration::default(),
+                output_size: fs::metadata(entry.output())
+                    .ok()
+                    .map(|metadata| metadata.len()),
+                pages: None,
+                warnings: Vec::new(),
+                cached: true,
+            });
+        }
+        let (command, _staged) = self.prepared()?;
+        let (elapsed, warnings) = command.run_timed(exec)?;
+        if let Some(entry) = &entry {
+            entry.store()?;
+        }
+        let output_size = command
+            .output
+            .as_deref()
+            .and_then(crate::cstr_path)
+            .and_then(|path| fs::metadata(path).ok())
+            .map(|metadata| metadata.len());
+        Ok(RunReport {
+            elapsed,
+            output_size,
+            pages: None,
+            warnings,
+            cached: false,
+        })
+    }
+
+    /// Pages declared by the input file, see [`RunReport::pages`].
+    fn input_pages(&self) -> Option<u32> {
+        let input = self.input.as_deref().and_then(crate::cstr_path)?;
+        input::declared_pages(input)
+    }
+
+    /// Run the command, retrying if necessary, measure the time spent in
+    /// pstoedit, and collect the warnings of the successful attempt.
+    fn run_timed(&self, exec: Exec<'_>) -> Result<(Duration, Vec<Warning>)> {
+        if let Some(err) = &self.deferred {
+            return Err(err.clone().into());
+        }
+        if self
+            .gs
+            .as_deref()
+            .and_then(crate::cstr_path)
+            .is_some_and(gs::is_missing)
+        {
+            return Err(Error::MissingGhostscript);
+        }
+        #[cfg(feature = "tracing")]
+        let span = self.span().entered();
+        #[cfg(any(feature = "log", feature = "tracing"))]
+        self.check_input_bbox();
+        let mut elapsed = Duration::default();
+        let mut attempt = 1;
+        let warnings = loop {
+            let (result, attempt_elapsed) = self.run_once(exec);
+            let result = result
+                .and_then(|warnings| self.strip_output().map(|()| warnings))
+                .and_then(|warnings| self.check_output().map(|()| warnings));
+            elapsed += attempt_elapsed;
+            match (result, &self.retry) {
+                (Err(err), Some(policy)) if policy.should_retry(attempt, &err) => {
+                    let delay = policy.delay_after(attempt);
+                    #[cfg(feature = "log")]
+                    log::info!("retrying pstoedit in {:?} after error: {}", delay, err);
+                    #[cfg(feature = "tracing")]
+                    tracing::info!(?delay, error = %err, "retrying pstoedit");
+                    thread::sleep(delay);
+                    attempt += 1;
+                }
+                (result, _) => break result?,
+            }
+        };
+        #[cfg(feature = "log")]
+        for warning in &warnings {
+            log::warn!("pstoedit warning: {}", warning);
+        }
+        #[cfg(feature = "tracing")]
+        for warning in &warnings {
+            tracing::warn!(%warning, "pstoedit warning");
+        }
+        // Only inspected if the span is recorded, as it reads the input
+        #[cfg(feature = "tracing")]
+        if !span.is_disabled() {
+            if let Some(pages) = self.input_pages() {
+                span.record("pages", pages);
+            }
+        }
+        if let Some(path) = self.output.as_deref().and_then(crate::cstr_path) {
+            for hook in self.post_process.iter() {
+                hook(path)?;
+            }
+        }
+        Ok((elapsed, warnings))
+    }
+
+    /// Run the command once and measure how long it takes.
+    fn run_once(&self, exec: Exec<'_>) -> (Result<Vec<Warning>>, Duration) {
+        #[cfg(feature = "log")]
+        log::debug!("running pstoedit with arguments {:?}", self.argv());
+        #[cfg(feature = "tracing")]
+        tracing::debug!(argv = ?self.argv(), "running pstoedit");
+        let start = Instant::now();
+        let result = exec(self);
+        let elapsed = start.elapsed();
+        #[cfg(feature = "log")]
+        match &result {
+            Ok(_) => log::info!("pstoedit finished in {:?}", elapsed),
+            Err(err) => log::info!("pstoedit failed after {:?}: {}", elapsed, err),
+        }
+        #[cfg(feature = "tracing")]
+        match &result {
+            Ok(_) => tracing::debug!(?elapsed, "pstoedit finished"),
+            Err(err) => tracing::debug!(?elapsed, error = %err, "pstoedit failed"),
+        }
+        (result, elapsed)
+    }
+
+    /// Run pstoedit in-process.
+    ///
+    /// If pstoedit fails and its output does not tell whether ghostscript
+    /// could be started, its ghostscript test is run to detect whether
+    /// ghostscript is missing.
+    fn run_ffi(&self) -> Result<Vec<Warning>> {
+        let gs = self.gs.as_deref().map_or(ptr::null(), CStr::as_ptr);
+        // Safety: the pointers refer to C strings owned by this command
+        let (code, output) = unsafe { crate::pstoedit_captured(&self.ptrs.0, gs) };
+        let result = crate::pstoedit_outcome(code, &output);
+        match result {
+            Err(Error::PstoeditError(_))
+                if !self.has_flag(GS_TEST) && gs::missing_from_output(&output).is_none() =>
+            {
+                const GS_TEST_ARG: &[u8] = b"-gstest\0";
+                let test = CStr::from_bytes_with_nul(GS_TEST_ARG).unwrap();
+                let argv = [self.args[0].as_ptr(), test.as_ptr()];
+                // Safety: the pointers refer to C strings owned by this
+                // command or static
+                match unsafe { crate::pstoedit_raw(&argv, gs) } {
+                    Ok(_) => result,
+                    Err(_) => Err(Error::MissingGhostscript),
+                }
+            }
+            result => result,
+        }
+    }
+
+    /// Add flag if not yet present, or remove all occurrences of it.
+    fn set_flag(&mut self, flag: &str, enable: bool) -> &mut Self {
+        let present = self.has_flag(flag);
+        if enable && !present {
+            self.args.push(CString::new(flag).unwrap());
+        } else if !enable && present {
+            self.args.retain(|arg| arg.as_bytes() != flag.as_bytes());
+        }
+        self.updated()
+    }
+
+    /// Set option to value, replacing all previous occurrences of it.
+    fn set_option(&mut self, flag: &str, value: CString) -> &mut Self {
+        self.remove_option(flag);
+        self.args.push(CString::new(flag).unwrap());
+        self.args.push(value);
+        self.updated()
+    }
+
+    /// Whether flag is present in the arguments.
+    fn has_flag(&self, flag: &str) -> bool {
+        self.args
+            .iter()
+            .any(|arg| arg.as_bytes() == flag.as_bytes())
+    }
+
+    /// Warn if the input bounding box is used, but the input does not have one.
+    #[cfg(any(feature = "log", feature = "tracing"))]
+    fn check_input_bbox(&self) {
+        if !self.has_flag(USE_INPUT_BBOX) {
+            return;
+        }
+        let path = match self.input.as_deref().and_then(crate::cstr_path) {
+            Some(path) => path,
+            None => return,
+        };
+        if let Ok(None) = BoundingBox::from_file(path) {
+            #[cfg(feature = "log")]
+            log::warn!("input {} has no BoundingBox comment", path.display());
+            #[cfg(feature = "tracing")]
+            tracing::warn!(input = %path.display(), "input has no BoundingBox comment");
+        }
+    }
+
+    /// Bring the cached argument pointers up to date after a mutation.
+    fn updated(&mut self) -> &mut Self {
+        let ptrs = self.argv().iter().map(|arg| arg.as_ptr()).collect();
+        self.ptrs = Ptrs(ptrs);
+        self
+    }
+
+    /// Full argument list, including format, input and output.
+    pub(crate) fn argv(&self) -> SmallVec<&CStr> {
+        const FORMAT_FLAG: &[u8] = b"-f\0";
+        let mut argv: SmallVec<_> = self.args.iter().map(CString::as_c_str).collect();
+        if let Some(format) = &self.format {
+            argv.push(CStr::from_bytes_with_nul(FORMAT_FLAG).unwrap());
+            argv.push(format);
+        }
+        argv.extend(self.input.as_deref());
+        argv.extend(self.output.as_deref());
+        argv
+    }
+
+    /// Input file, if set.
+    pub(crate) fn input_path(&self) -> Option<&CStr> {
+        self.input.as_deref()
+    }
+
+    /// Output file, if set.
+    pub(crate) fn output_path(&self) -> Option<&CStr> {
+        self.output.as_deref()
+    }
+
+    /// Whether a directory for the output file is set.
+    #[cfg(feature = "notify")]
+    pub(crate) fn has_output_dir(&self) -> bool {
+        self.output_dir.is_some()
+    }
+
+    /// Directory for temporary files, if set.
+    pub(crate) fn temp_dir_path(&self) -> Option<&Path> {
+        self.temp_dir.as_deref()
+    }
+
+    /// Ghostscript executable, if set.
+    pub(crate) fn gs_path(&self) -> Option<&CStr> {
+        self.gs.as_deref()
+    }
+
+    /// Span covering a single run, with fields for the typed arguments and
+    /// the pages of the input, recorded once the run finished.
+    ///
+    /// pstoedit runs the interpreter and the driver within a single call, so
+    /// there are no separate events for these phases, only for the run as a
+    /// whole.
+    #[cfg(feature = "tracing")]
+    fn span(&self) -> tracing::Span {
+        use tracing::field::Empty;
+
+        let span = tracing::info_span!(
+            "pstoedit",
+            driver = Empty,
+            input = Empty,
+            output = Empty,
+            pages = Empty
+        );
+        let fields = [
+            ("driver", self.format_name()),
+            ("input", self.input.as_deref()),
+            ("output", self.output.as_deref()),
+        ];
+        for (field, value) in &fields {
+            if let Some(value) = value {
+                span.record(*field, value.to_string_lossy().as_ref());
+            }
+        }
+        span
+    }
+}
+
+impl Clone for Command {
+    fn clone(&self) -> Self {
+        let mut command = Self {
+            args: self.args.clone(),
+            format: self.format.clone(),
+            input: self.input.clone(),
+            output: self.output.clone(),
+            output_dir: self.output_dir.clone(),
+            verify_output: self.verify_output,
+            deterministic: self.deterministic,
+            incremental: self.incremental,
+            pre_process: self.pre_process.clone(),
+            post_process: self.post_process.clone(),
+            background: self.background,
+            gs: self.gs.clone(),
+            retry: self.retry.clone(),
+            temp_dir: self.temp_dir.clone(),
+            deferred: self.deferred.clone(),
+            extension: self.extension.clone(),
+            ptrs: Ptrs::default(),
+        };
+        // The pointers must refer to the strings of the clone
+        command.updated();
+        command
+    }
+}
+
+impl Default for Command {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Add arguments from an iterator.
+///
+/// As errors cannot be returned here, checking for nul bytes is deferred: if
+/// an argument contains an internal nul byte, it and all later arguments are
+/// not added, and the error is returned when running the command.
+impl<S> Extend<S> for Command
+where
+    S: Into<Vec<u8>>,
+{
+    fn extend<I>(&mut self, iter: I)
+    where
+        I: IntoIterator<Item = S>,
+    {
+        if self.deferred.is_some() {
+            return;
+        }
+        for arg in iter {
+            match CString::new(arg) {
+                Ok(arg) => self.args.push(arg),
+                Err(err) => {
+                    self.deferred = Some(err);
+                    break;
+                }
+            }
+        }
+        self.updated();
+    }
+}
+
+/// Create a command from an iterator over arguments, excluding the program
+/// name.
+///
+/// See [`Extend`][Command#impl-Extend<S>-for-Command] for the handling of nul
+/// bytes.
+///
+/// # Examples
+/// ```no_run
+/// use pstoedit::Command;
+///
+/// pstoedit::init()?;
+/// let cmd: Command = ["-f", "svg", "input.ps", "output.svg"].iter().copied().collect();
+/// cmd.run()?;
+/// # Ok::<(), pstoedit::Error>(())
+/// ```
+impl<S> FromIterator<S> for Command
+where
+    S: Into<Vec<u8>>,
+{
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+    {
+        let mut command = Self::new();
+        command.extend(iter);
+        command
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp::TempDir;
+    use std::env;
+
+    fn prep() {
+        crate::init().unwrap();
+        // Ensure ghostscript is not obtained through environment
+        env::set_var("GS", "should_not_be_used");
+    }
+
+    #[test]
+    fn arg_gs() {
+        prep();
+        Command::new()
+            .arg("-gstest")
+            .unwrap()
+            .gs("gs")
+            .unwrap()
+            .run()
+            .unwrap();
+    }
+
+    #[test]
+    fn missing_gs() {
+        prep();
+        let result = Command::new()
+            .arg("-gstest")
+            .unwrap()
+            .gs("/nonexistent/gs")
+            .unwrap()
+            .run();
+        assert!(matches!(result, Err(Error::MissingGhostscript)));
+    }
+
+    #[test]
+    fn args_gs() {
+        prep();
+        Command::new()
+            .args_slice(&["-gstest"])
+            .unwrap()
+            .gs("gs")
+            .unwrap()
+            .run()
+            .unwrap();
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn run_with() {
+        use crate::engine::Subprocess;
+        let mut cmd = Command::new();
+        cmd.args_slice(&["-c", "exit 3"]).unwrap();
+        let engine = Subprocess::new().program("sh");
+        let result = cmd.run_with(&engine as &dyn Engine);
+        assert!(matches!(result, Err(Error::SubprocessError(_))));
+    }
+
+    #[test]
+    fn run_report() {
+        prep();
+        let report = Command::new()
+            .arg("-gstest")
+            .unwrap()
+            .gs("gs")
+            .unwrap()
+            .run_report()
+            .unwrap();
+        assert!(report.output_size().is_none());
+        assert!(report.pages().is_none());
+    }
+
+    #[test]
+    fn output_dir() {
+        prep();
+        let dir = TempDir::new().unwrap();
+        let input = dir.path().join("input.ps");
+        fs::write(&input, "%!PS\n").unwrap();
+        let mut cmd = Command::new();
+        cmd.output_dir(dir.path());
+        assert!(matches!(cmd.run(), Err(Error::MissingOutputName)));
+        cmd.input(crate::path_bytes(&input).unwrap()).unwrap();
+        cmd.format("no-such-driver").unwrap();
+        assert!(matches!(cmd.run(), Err(Error::UnknownFormat(_))));
+
+        let mut cmd = Command::new();
+        cmd.args_slice(&["-f", "psf"]).unwrap();
+        cmd.input(crate::path_bytes(&input).unwrap()).unwrap();
+        let report = cmd.output_dir(dir.path()).run_report().unwrap();
+        let output = dir.path().join("input.fps");
+        assert!(fs::metadata(&output).unwrap().len() > 0);
+        assert_eq!(
+            report.output_size(),
+            Some(fs::metadata(&output).unwrap().len())
+        );
+
+        let input = dir.path().join("fig.v2.ps");
+        fs::write(&input, "%!PS\n").unwrap();
+        cmd.input(crate::path_bytes(&input).unwrap()).unwrap();
+        cmd.run().unwrap();
+        assert!(fs::metadata(dir.path().join("fig.v2.fps")).unwrap().len() > 0);
+        assert!(!dir.path().join("fig.fps").exists());
+    }
+
+    #[test]
+    fn output_format() {
+        prep();
+        let dir = TempDir::new().unwrap();
+        let input = dir.path().join("input.ps");
+        fs::write(&input, "%!PS\n").unwrap();
+        let mut cmd = Command::new();
+        cmd.input(crate::path_bytes(&input).unwrap()).unwrap();
+        cmd.output(crate::path_bytes(&dir.path().join("output.FIG")).unwrap())
+            .unwrap();
+        assert_eq!(cmd.output_format().unwrap().as_deref(), Some("fig"));
+        cmd.run().unwrap();
+        cmd.arg("-f").unwrap().arg("psf").unwrap();
+        assert_eq!(cmd.resolved().unwrap().format, None);
+    }
+
+    #[test]
+    fn prepare() {
+        prep();
+        let mut cmd = Command::new();
+        cmd.output("output.fig").unwrap().prepare().unwrap();
+        assert_eq!(cmd.format.as_deref().unwrap().to_str(), Ok("fig"));
+
+        let mut cmd = Command::new();
+        cmd.args_slice(&["-f", "psf"]).unwrap();
+        cmd.output_dir("out").prepare().unwrap();
+        // The kept extension is used instead of looking up the driver again
+        cmd.extension.as_mut().unwrap().1 = "kept".to_owned();
+        let output = |cmd: &Command| {
+            let cmd = cmd.resolved().unwrap();
+            crate::cstr_path(cmd.output_path().unwrap())
+                .unwrap()
+                .to_owned()
+        };
+        cmd.input("a.ps").unwrap();
+        assert_eq!(output(&cmd), Path::new("out").join("a.kept"));
+        cmd.input("b.ps").unwrap();
+        assert_eq!(output(&cmd), Path::new("out").join("b.kept"));
+        cmd.format("fig").unwrap();
+        assert_eq!(output(&cmd), Path::new("out").join("b.fig"));
+
+        let mut cmd = Command::new();
+        cmd.extend(["a\0b"].iter().copied());
+        assert!(matches!(cmd.prepare(), Err(crate::Error::NulError(_))));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn verify_output() {
+        use crate::engine::Subprocess;
+        let dir = TempDir::new().unwrap();
+        let output = dir.path().join("output.fps");
+        let engine = Subprocess::new().program("sh");
+        let run = |script: &str, verify: bool| {
+            let mut cmd = Command::new();
+            // The format avoids looking up the driver by extension
+            cmd.args_slice(&["-c", script, "-f", "psf"]).unwrap();
+            cmd.output(crate::path_bytes(&output).unwrap()).unwrap();
+            cmd.verify_output(verify).run_with(&engine)
+        };
+        run(": > \"$2\"", false).unwrap();
+        assert!(matches!(
+            run(": > \"$2\"", true),
+            Err(Error::EmptyOutput { missing: false, .. })
+        ));
+        assert!(matches!(
+            run("rm -f \"$2\"", true),
+            Err(Error::EmptyOutput { missing: true, .. })
+        ));
+        run("echo %!PS > \"$2\"", true).unwrap();
+        prep();
+        let mut cmd = Command::new();
+        cmd.arg("-gstest").unwrap();
+        cmd.verify_output(true).run().unwrap();
+    }
+
+    #[test]
+    fn incremental() {
+        prep();
+        let dir = TempDir::new().unwrap();
+        let input = dir.path().join("input.ps");
+        let output = dir.path().join("output.fps");
+        fs::write(&input, "%!PS\n").unwrap();
+        let mut cmd = Command::new();
+        cmd.format("psf").unwrap().incremental(true);
+        cmd.input(crate::path_bytes(&input).unwrap()).unwrap();
+        cmd.output(crate::path_bytes(&output).unwrap()).unwrap();
+        assert!(!cmd.run_report().unwrap().cached());
+        let report = cmd.run_report().unwrap();
+        assert!(report.cached());
+        let size = fs::metadata(&output).unwrap().len();
+        assert_eq!(report.output_size(), Some(size));
+        fs::write(&input, "%!PS\nshowpage\n").unwrap();
+        assert!(!cmd.run_report().unwrap().cached());
+        fs::remove_file(&output).unwrap();
+        assert!(!cmd.run_report().unwrap().cached());
+        assert!(!cmd.incremental(false).run_report().unwrap().cached());
+    }
+
+    #[test]
+    fn deterministic() {
+        prep();
+        let dir = TempDir::new().unwrap();
+        let input = dir.path().join("input.ps");
+        let output = dir.path().join("output.fps");
+        fs::write(&input, "%!PS\n%%CreationDate: today\n").unwrap();
+        let mut cmd = Command::new();
+        cmd.format("psf").unwrap();
+        cmd.input(crate::path_bytes(&input).unwrap()).unwrap();
+        cmd.output(crate::path_bytes(&output).unwrap()).unwrap();
+        cmd.deterministic(true).run().unwrap();
+        let first = fs::read(&output).unwrap();
+        let text = String::from_utf8_lossy(&first);
+        assert!(!text.lines().any(|line| line.starts_with("%%CreationDate:")));
+        assert!(!text.lines().any(|line| line.starts_with("%%Creator:")));
+        cmd.run().unwrap();
+        assert_eq!(fs::read(&output).unwrap(), first);
+    }
+
+    #[test]
+    fn post_process() {
+        prep();
+        let dir = TempDir::new().unwrap();
+        let input = dir.path().join("input.ps");
+        let output = dir.path().join("output.fps");
+        fs::write(&input, "%!PS\n").unwrap();
+        let mut cmd = Command::new();
+        cmd.format("psf").unwrap();
+        cmd.input(crate::path_bytes(&input).unwrap()).unwrap();
+        cmd.output(crate::path_bytes(&output).unwrap()).unwrap();
+        cmd.post_process(|path| Ok(fs::write(path, "first")?));
+        cmd.post_process(|path| {
+            let data = fs::read_to_string(path)?;
+            Ok(fs::write(path, data + " second")?)
+        });
+        cmd.clone().run().unwrap();
+        assert_eq!(fs::read_to_string(&output).unwrap(), "first second");
+        cmd.post_process(|_| Err(Error::QueueStopped));
+        assert!(matches!(cmd.run(), Err(Error::QueueStopped)));
+    }
+
+    #[test]
+    fn pre_process() {
+        use std::sync::{Arc, Mutex};
+        prep();
+        let dir = TempDir::new().unwrap();
+        let input = dir.path().join("input.ps");
+        let output = dir.path().join("output.fps");
+        fs::write(&input, "%!PS\n").unwrap();
+        let mut cmd = Command::new();
+        cmd.format("psf").unwrap();
+        cmd.input(crate::path_bytes(&input).unwrap()).unwrap();
+        cmd.output(crate::path_bytes(&output).unwrap()).unwrap();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let first = Arc::clone(&seen);
+        cmd.pre_process(move |input, rewritten| {
+            assert_eq!(rewritten.extension().unwrap(), "ps");
+            first.lock().unwrap().push(input.to_owned());
+            let data = fs::read_to_string(input)?;
+            Ok(fs::write(rewritten, data + "showpage\n")?)
+        });
+        let second = Arc::clone(&seen);
+        cmd.pre_process(move |input, rewritten| {
+            assert_eq!(fs::read_to_string(input)?, "%!PS\nshowpage\n");
+            second.lock().unwrap().push(input.to_owned());
+            Ok(fs::copy(input, rewritten).map(drop)?)
+        });
+        cmd.run().unwrap();
+        let seen = seen.lock().unwrap();
+        assert_eq!(seen.len(), 2);
+        assert_eq!(seen[0], input);
+        assert_ne!(seen[1], input);
+        assert_eq!(fs::read_to_string(&input).unwrap(), "%!PS\n");
+        assert!(fs::metadata(&output).unwrap().len() > 0);
+    }
+
+    #[test]
+    fn run_split() {
+        prep();
+        let dir = TempDir::new().unwrap();
+        let template = OutputTemplate::new("page-{page}.svg").unwrap();
+        let pages = Command::new()
+            .arg("-gstest")
+            .unwrap()
+            .run_split(dir.path(), &template)
+            .unwrap();
+        assert!(pages.is_empty());
+
+        let input = dir.path().join("input.ps");
+        fs::write(&input, "%!PS\nshowpage\nshowpage\n").unwrap();
+        let mut cmd = Command::new();
+        cmd.format("psf").unwrap();
+        cmd.input(crate::path_bytes(&input).unwrap()).unwrap();
+        let pages = cmd
+            .verify_output(true)
+            .run_split(dir.path(), &template)
+            .unwrap();
+        for page in &pages {
+            assert!(fs::metadata(page).unwrap().len() > 0);
+        }
+    }
+
+    #[test]
+    fn temp_dir() {
+        prep();
+        let dir = TempDir::new().unwrap();
+        let previous = env::var_os("TMPDIR");
+        Command::new()
+            .arg("-gstest")
+            .unwrap()
+            .gs("gs")
+            .unwrap()
+            .temp_dir(dir.path())
+            .run()
+            .unwrap();
+        assert_eq!(env::var_os("TMPDIR"), previous);
+    }
+
+    #[cfg(feature = "camino")]
+    #[test]
+    fn utf8_paths() {
+        use camino::Utf8Path;
+        let mut cmd = Command::new();
+        cmd.input_utf8(Utf8Path::new("in.ps")).unwrap();
+        cmd.output_utf8(Utf8Path::new("out.svg")).unwrap();
+        let mut expected = Command::new();
+        expected.input("in.ps").unwrap().output("out.svg").unwrap();
+        assert_eq!(cmd.argv(), expected.argv());
+    }
+
+    fn check_ptrs(cmd: &Command) {
+        let argv: Vec<_> = cmd.argv().iter().map(|arg| arg.as_ptr()).collect();
+        assert_eq!(cmd.ptrs.0[..], argv[..]);
+    }
+
+    #[test]
+    fn cached_ptrs() {
+        let mut cmd = Command::new();
+        check_ptrs(&cmd);
+        cmd.format("svg").unwrap().input("input.ps").unwrap();
+        check_ptrs(&cmd);
+        cmd.use_input_bbox(true).arg("-dt").unwrap();
+        check_ptrs(&cmd);
+        check_ptrs(&cmd.clone());
+    }
+
+    #[test]
+    fn args_os() {
+        let mut cmd = Command::new();
+        cmd.args_os_slice(&[OsStr::new("-dt"), OsStr::new("input.ps")])
+            .unwrap();
+        let argv: Vec<_> = cmd.argv().iter().map(|s| s.to_str().unwrap()).collect();
+        assert_eq!(argv, ["pstoedit", "-dt", "input.ps"]);
+    }
+
+    #[test]
+    fn from_iter() {
+        let mut cmd: Command = vec!["-dt", "input.ps"].into_iter().collect();
+        cmd.extend(Some(String::from("output.svg")));
+        let argv: Vec<_> = cmd.argv().iter().map(|s| s.to_str().unwrap()).collect();
+        assert_eq!(argv, ["pstoedit", "-dt", "input.ps", "output.svg"]);
+        check_ptrs(&cmd);
+    }
+
+    #[test]
+    fn extend_deferred_error() {
+        prep();
+        let mut cmd = Command::new();
+        cmd.extend(["-gstest", "a\0b", "-dt"].iter().copied());
+        assert_eq!(cmd.argv().len(), 2);
+        assert!(matches!(cmd.run(), Err(crate::Error::NulError(_))));
+    }
+
+    #[test]
+    fn format_with_options() {
+        let mut spec = DriverSpec::new("svg");
+        spec.option("-title", "A B");
+        let mut cmd = Command::new();
+        cmd.format_with_options(&spec).unwrap();
+        assert_eq!(
+            cmd.format_name().unwrap().to_str(),
+            Ok("svg:-title \"A B\"")
+        );
+        spec.option("-x", "\"");
+        let result = cmd.format_with_options(&spec);
+        assert!(matches!(result, Err(Error::InvalidDriverOption(_))));
+    }
+
+    #[test]
+    fn arg_cstr() {
+        let arg = CString::new("-dt").unwrap();
+        let mut cmd = Command::new();
+        cmd.arg_cstr(&arg).arg_cstr(&arg);
+        let argv: Vec<_> = cmd.argv().iter().map(|s| s.to_str().unwrap()).collect();
+        assert_eq!(argv, ["pstoedit", "-dt", "-dt"]);
+    }
+
+    #[test]
+    fn option() {
+        let mut cmd = Command::new();
+        cmd.option("-scale", 2.5)
+            .unwrap()
+            .option("-page", 3)
+            .unwrap();
+        let argv: Vec<_> = cmd.argv().iter().map(|s| s.to_str().unwrap()).collect();
+        assert_eq!(argv, ["pstoedit", "-scale", "2.5", "-page", "3"]);
+        assert!(cmd.option("-include", "a\0b").is_err());
+        assert_eq!(cmd.argv().len(), 5);
+    }
+
+    #[test]
+    fn remove_args() {
+        let argv = |cmd: &Command| -> Vec<String> {
+            let argv = cmd.argv();
+            argv.iter()
+                .map(|s| s.to_str().unwrap().to_owned())
+                .collect()
+        };
+        let mut cmd = Command::new();
+        cmd.args_slice(&["-dt", "-scale", "2", "-dt", "-nc", "-scale"])
+            .unwrap();
+        cmd.format("svg").unwrap();
+        cmd.remove_flag("-dt");
+        assert_eq!(
+            argv(&cmd),
+            ["pstoedit", "-scale", "2", "-nc", "-scale", "-f", "svg"]
+        );
+        cmd.remove_option("-scale");
+        assert_eq!(argv(&cmd), ["pstoedit", "-nc", "-f", "svg"]);
+        cmd.extend(["a\0b"].iter().copied());
+        cmd.clear_args();
+        assert_eq!(argv(&cmd), ["pstoedit", "-f", "svg"]);
+        assert!(cmd.deferred.is_none());
+    }
+
+    #[test]
+    fn color_model() {
+        let mut cmd = Command::new();
+        cmd.color_model(ColorModel::Rgb)
+            .color_model(ColorModel::Rgb);
+        assert_eq!(cmd.argv().len(), 2);
+        assert_eq!(cmd.argv()[1].to_str().unwrap(), RGB);
+        cmd.color_model(ColorModel::AsDocument);
+        assert_eq!(cmd.argv().len(), 1);
+    }
+
+    #[test]
+    fn flatness() {
+        let mut cmd = Command::new();
+        let args = |cmd: &Command| {
+            cmd.argv()[1..]
+                .iter()
+                .map(|arg| arg.to_str().unwrap().to_owned())
+                .collect::<Vec<_>>()
+        };
+        cmd.flatness(0.5).flatness(2.0);
+        assert_eq!(args(&cmd), [FLATNESS, "2"]);
+        cmd.flatness(0.0);
+        assert_eq!(args(&cmd), [FLATNESS, "0.2"]);
+        cmd.flatness(f64::NAN);
+        assert_eq!(args(&cmd), [FLATNESS, "0.2"]);
+        cmd.flatness(1e9);
+        assert_eq!(args(&cmd), [FLATNESS, "100"]);
+        cmd.flatness(None);
+        assert!(args(&cmd).is_empty());
+    }
+
+    #[test]
+    fn font_conversion() {
+        let mut cmd = Command::new();
+        let args = |cmd: &Command| {
+            cmd.argv()[1..]
+                .iter()
+                .map(|arg| arg.to_str().unwrap().to_owned())
+                .collect::<Vec<_>>()
+        };
+        cmd.text_mode(TextMode::Outline)
+            .text_mode(TextMode::PassThrough)
+            .type2_fonts_as_type1(true)
+            .type2_fonts_as_type1(true);
+        assert_eq!(args(&cmd), ["-ndt", TYPE2_AS_TYPE1]);
+        cmd.text_mode(TextMode::OutlineNonStandard);
+        assert_eq!(args(&cmd), [TYPE2_AS_TYPE1, "-adt"]);
+        cmd.text_mode(TextMode::Auto).type2_fonts_as_type1(false);
+        assert!(args(&cmd).is_empty());
+    }
+
+    #[test]
+    fn default_font() {
+        let dir = TempDir::new().unwrap();
+        let fontmap = dir.path().join("fonts.fmp");
+        fs::write(&fontmap, "Helvetica Arial\n").unwrap();
+        let mut cmd = Command::new();
+        cmd.default_font("Courier").unwrap();
+        let result = cmd.default_font_in("Courier", &fontmap);
+        assert!(matches!(result, Err(Error::UnknownFont(font)) if font == "Courier"));
+        assert_eq!(cmd.argv().len(), 3);
+        cmd.default_font_in("Arial", &fontmap).unwrap();
+        let args: Vec<_> = cmd.argv()[1..]
+            .iter()
+            .map(|arg| arg.to_str().unwrap())
+            .collect();
+        let fontmap = fontmap.to_str().unwrap();
+        assert_eq!(args, [FONTMAP, fontmap, DEFAULT_FONT, "Arial"]);
+    }
+
+    #[test]
+    fn background() {
+        prep();
+        let dir = TempDir::new().unwrap();
+        let input = dir.path().join("input.ps");
+        let output = dir.path().join("output.fps");
+        fs::write(&input, "%!PS\nshowpage\n").unwrap();
+        let mut cmd = Command::new();
+        cmd.format("psf").unwrap();
+        cmd.input(crate::path_bytes(&input).unwrap()).unwrap();
+        cmd.output(crate::path_bytes(&output).unwrap()).unwrap();
+        cmd.background(Color::WHITE);
+        cmd.run().unwrap();
+        assert_eq!(fs::read_to_string(&input).unwrap(), "%!PS\nshowpage\n");
+        cmd.background(None).run().unwrap();
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn background_staged() {
+        use crate::engine::Subprocess;
+        let dir = TempDir::new().unwrap();
+        let input = dir.path().join("input.ps");
+        let output = dir.path().join("output.fps");
+        fs::write(&input, "%!PS\nshowpage\n").unwrap();
+        let mut cmd = Command::new();
+        // Copy the input pstoedit would receive to the output
+        cmd.args_slice(&["-c", "cp \"$2\" \"$3\""]).unwrap();
+        cmd.format("psf").unwrap();
+        cmd.input(crate::path_bytes(&input).unwrap()).unwrap();
+        cmd.output(crate::path_bytes(&output).unwrap()).unwrap();
+        let engine = Subprocess::new().program("sh");
+        cmd.background(Color::WHITE).run_with(&engine).unwrap();
+        let staged = fs::read_to_string(&output).unwrap();
+        assert!(staged.contains("setrgbcolor clippath fill"));
+        assert_eq!(fs::read_to_string(&input).unwrap(), "%!PS\nshowpage\n");
+        cmd.background(None).run_with(&engine).unwrap();
+        assert_eq!(fs::read_to_string(&output).unwrap(), "%!PS\nshowpage\n");
+    }
+
+    #[test]
+    fn input_bbox() {
+        let mut cmd = Command::new();
+        cmd.use_input_bbox(true).use_input_bbox(true);
+        assert_eq!(cmd.argv().len(), 2);
+        assert_eq!(cmd.argv()[1].to_str().unwrap(), USE_INPUT_BBOX);
+        cmd.use_input_bbox(false);
+        assert_eq!(cmd.argv().len(), 1);
+    }
+
+    #[test]
+    #[cfg(windows)]
+    fn gs_registry_base() {
+        let mut cmd = Command::new();
+        cmd.gs_registry_base("HKEY_CURRENT_USER\\Software\\A")
+            .unwrap()
+            .arg("-dt")
+            .unwrap()
+            .gs_registry_base("HKEY_CURRENT_USER\\Software\\B")
+            .unwrap();
+        let argv: Vec<_> = cmd.argv().iter().map(|s| s.to_str().unwrap()).collect();
+        assert_eq!(
+            argv,
+            [
+                "pstoedit",
+                "-dt",
+                GS_REGISTRY_BASE,
+                "HKEY_CURRENT_USER\\Software\\B"
+            ]
+        );
+        assert!(cmd.gs_registry_base("a\0b").is_err());
+    }
+
+    #[test]
+    fn typed_args() {
+        let mut cmd = Command::new();
+        cmd.output("output.svg")
+            .unwrap()
+            .input("input.ps")
+            .unwrap()
+            .format("svg")
+            .unwrap()
+            .arg("-dt")
             .unwrap();
+        let argv: Vec<_> = cmd.argv().iter().map(|s| s.to_str().unwrap()).collect();
+        assert_eq!(
+            argv,
+            ["pstoedit", "-dt", "-f", "svg", "input.ps", "output.svg"]
+        );
     }
 }
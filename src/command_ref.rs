@@ -0,0 +1,143 @@
+use crate::{smallvec, Result, SmallVec};
+use std::ffi::CStr;
+use std::fmt;
+use std::marker::PhantomData;
+use std::os::raw::c_char;
+use std::ptr;
+
+/// Program name passed as first argument.
+const PROGRAM: &[u8] = b"pstoedit\0";
+
+/// Command borrowing its arguments as C strings.
+///
+/// This is a minimal alternative to [`Command`][crate::Command] for callers
+/// that already own nul-terminated strings. Only pointers to the arguments
+/// are stored, so adding arguments and running the command do not copy or
+/// check them. Features of [`Command`][crate::Command] that inspect or
+/// rewrite the arguments, such as deriving the output file, verifying it, or
+/// hooks, are not available.
+///
+/// # Examples
+/// ```no_run
+/// use pstoedit::CommandRef;
+/// use std::ffi::CString;
+///
+/// pstoedit::init()?;
+/// let args = ["-f", "svg", "input.ps", "output.svg"]
+///     .iter()
+///     .map(|arg| CString::new(*arg))
+///     .collect::<Result<Vec<_>, _>>()?;
+/// let mut cmd = CommandRef::new();
+/// cmd.args(args.iter().map(CString::as_c_str));
+/// cmd.run()?;
+/// # Ok::<(), pstoedit::Error>(())
+/// ```
+pub struct CommandRef<'a> {
+    argv: SmallVec<*const c_char>,
+    gs: Option<&'a CStr>,
+    _args: PhantomData<&'a CStr>,
+}
+
+// Safety: the pointers refer to C strings borrowed for 'a, which are only read
+unsafe impl Send for CommandRef<'_> {}
+unsafe impl Sync for CommandRef<'_> {}
+
+impl<'a> CommandRef<'a> {
+    /// Create a command with program name and without arguments.
+    pub fn new() -> Self {
+        Self {
+            argv: smallvec![CStr::from_bytes_with_nul(PROGRAM).unwrap().as_ptr()],
+            gs: None,
+            _args: PhantomData,
+        }
+    }
+
+    /// Add a single argument.
+    pub fn arg(&mut self, arg: &'a CStr) -> &mut Self {
+        self.argv.push(arg.as_ptr());
+        self
+    }
+
+    /// Add multiple arguments.
+    pub fn args<I>(&mut self, args: I) -> &mut Self
+    where
+        I: IntoIterator<Item = &'a CStr>,
+    {
+        self.argv.extend(args.into_iter().map(CStr::as_ptr));
+        self
+    }
+
+    /// Specify ghostscript executable, see
+    /// [`Command::gs`][crate::Command::gs].
+    pub fn gs(&mut self, gs: &'a CStr) -> &mut Self {
+        self.gs = Some(gs);
+        self
+    }
+
+    /// Run the command.
+    ///
+    /// This can be done multiple times for the same [`CommandRef`].
+    ///
+    /// # Errors
+    /// - [`NotInitialized`][crate::Error::NotInitialized] if
+    ///   [`init`][crate::init] was not called successfully.
+    /// - [`PstoeditError`][crate::Error::PstoeditError] if pstoedit returns
+    ///   with a non-zero status code.
+    /// - [`InterpreterError`][crate::Error::InterpreterError] instead if
+    ///   pstoedit fails because of an error reported by ghostscript.
+    pub fn run(&self) -> Result<()> {
+        let gs = self.gs.map_or(ptr::null(), CStr::as_ptr);
+        // Safety: the pointers refer to C strings borrowed for 'a or static
+        unsafe { crate::pstoedit_raw(&self.argv, gs) }.map(drop)
+    }
+
+    /// Arguments, including the program name.
+    fn args_cstr(&self) -> impl Iterator<Item = &CStr> {
+        // Safety: the pointers refer to C strings borrowed for 'a or static
+        self.argv.iter().map(|&arg| unsafe { CStr::from_ptr(arg) })
+    }
+}
+
+impl Default for CommandRef<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for CommandRef<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CommandRef")
+            .field("args", &self.args_cstr().collect::<Vec<_>>())
+            .field("gs", &self.gs)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp::TempDir;
+    use std::ffi::CString;
+    use std::fs;
+
+    #[test]
+    fn run() {
+        crate::init().unwrap();
+        let dir = TempDir::new().unwrap();
+        let input = dir.path().join("input.ps");
+        let output = dir.path().join("output.fps");
+        fs::write(&input, "%!PS\n").unwrap();
+        let args = [
+            CString::new("-f").unwrap(),
+            CString::new("psf").unwrap(),
+            CString::new(crate::path_bytes(&input).unwrap()).unwrap(),
+            CString::new(crate::path_bytes(&output).unwrap()).unwrap(),
+        ];
+        let mut cmd = CommandRef::new();
+        cmd.arg(&args[0])
+            .args(args[1..].iter().map(CString::as_c_str));
+        assert_eq!(cmd.args_cstr().count(), 5);
+        cmd.run().unwrap();
+        assert!(fs::metadata(&output).unwrap().len() > 0);
+    }
+}
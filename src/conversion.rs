@@ -0,0 +1,256 @@
+//! Conversions running in the background.
+//!
+//! [`Command::spawn`] runs a command on a new thread and returns a
+//! [`ConversionHandle`], which can be polled for the [`State`] of the
+//! conversion without blocking, e.g. from the UI thread of a GUI application.
+//! Conversions started by [`Subprocess::spawn`] can also be cancelled.
+//!
+//! # Examples
+//! ```no_run
+//! use pstoedit::conversion::State;
+//! use pstoedit::Command;
+//!
+//! pstoedit::init()?;
+//! let handle = Command::new()
+//!     .format("svg")?
+//!     .input("input.ps")?
+//!     .output("output.svg")?
+//!     .spawn();
+//! while !handle.state().is_finished() {
+//!     // ... update UI ...
+//! #   std::thread::yield_now();
+//! }
+//! handle.wait()?;
+//! # Ok::<(), pstoedit::Error>(())
+//! ```
+//!
+//! [`Subprocess::spawn`]: crate::engine::Subprocess::spawn
+
+use crate::process::Abort;
+use crate::{Command, Result};
+use std::panic;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+const RUNNING: u8 = 0;
+const DONE: u8 = 1;
+const FAILED: u8 = 2;
+
+/// State of a conversion.
+///
+/// pstoedit does not report on its progress, so interpreting the input and
+/// writing the output are not distinguished.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum State {
+    /// Waiting for other conversions to finish, as pstoedit is not reentrant.
+    Queued,
+    /// pstoedit is running.
+    Running,
+    /// Work is done outside of pstoedit, such as resolving the driver and
+    /// rewriting the input before running it, waiting before a retry, or
+    /// processing the output afterwards.
+    Processing,
+    /// The conversion finished successfully.
+    Done,
+    /// The conversion failed.
+    Failed,
+}
+
+impl State {
+    /// Whether the conversion has finished, successfully or not.
+    pub fn is_finished(self) -> bool {
+        matches!(self, State::Done | State::Failed)
+    }
+}
+
+/// Handle to a conversion started by [`Command::spawn`] or
+/// [`Subprocess::spawn`][crate::engine::Subprocess::spawn].
+///
+/// See [module-level documentation][self] for more details.
+#[derive(Debug)]
+pub struct ConversionHandle {
+    thread: JoinHandle<Result<()>>,
+    state: Arc<AtomicU8>,
+    cancel: Option<Cancel>,
+}
+
+/// Cancellation of a conversion in a child process.
+#[derive(Debug)]
+struct Cancel {
+    abort: Abort,
+    on_drop: bool,
+}
+
+impl Drop for Cancel {
+    fn drop(&mut self) {
+        if self.on_drop {
+            self.abort.abort();
+        }
+    }
+}
+
+impl ConversionHandle {
+    pub(crate) fn spawn(command: Command) -> Self {
+        Self::start(move || command.run(), None)
+    }
+
+    /// Start conversion in a child process, which is terminated by `abort`.
+    pub(crate) fn spawn_with<F>(run: F, abort: Abort) -> Self
+    where
+        F: FnOnce() -> Result<()> + Send + 'static,
+    {
+        let cancel = Cancel {
+            abort,
+            on_drop: false,
+        };
+        Self::start(run, Some(cancel))
+    }
+
+    fn start<F>(run: F, cancel: Option<Cancel>) -> Self
+    where
+        F: FnOnce() -> Result<()> + Send + 'static,
+    {
+        let state = Arc::new(AtomicU8::new(RUNNING));
+        let thread_state = Arc::clone(&state);
+        let thread = thread::spawn(move || {
+            let result = run();
+            let finished = if result.is_ok() { DONE } else { FAILED };
+            thread_state.store(finished, Ordering::Release);
+            result
+        });
+        Self {
+            thread,
+            state,
+            cancel,
+        }
+    }
+
+    /// Current state of the conversion, without blocking.
+    pub fn state(&self) -> State {
+        match self.state.load(Ordering::Acquire) {
+            DONE => State::Done,
+            FAILED => State::Failed,
+            // Child processes are not serialized
+            _ if self.cancel.is_some() => State::Running,
+            _ if crate::is_active(self.thread.thread().id()) => State::Running,
+            _ if crate::is_waiting(self.thread.thread().id()) => State::Queued,
+            _ => State::Processing,
+        }
+    }
+
+    /// Cancel the conversion, terminating pstoedit and the processes it
+    /// started.
+    ///
+    /// The conversion then fails with [`Cancelled`][crate::Error::Cancelled],
+    /// unless it already finished, and the partial output file is removed.
+    /// Only conversions started by
+    /// [`Subprocess::spawn`][crate::engine::Subprocess::spawn] can be
+    /// cancelled; this does nothing for conversions running in-process.
+    pub fn cancel(&self) {
+        if let Some(cancel) = &self.cancel {
+            cancel.abort.abort();
+        }
+    }
+
+    /// Whether to cancel the conversion if the handle is dropped without
+    /// waiting for it, see [`cancel`][ConversionHandle::cancel].
+    ///
+    /// By default, conversions continue in the background after the handle is
+    /// dropped.
+    pub fn kill_on_drop(&mut self, kill: bool) -> &mut Self {
+        if let Some(cancel) = &mut self.cancel {
+            cancel.on_drop = kill;
+        }
+        self
+    }
+
+    /// Wait until the conversion has finished and return its result.
+    ///
+    /// # Errors
+    /// Errors from running the command.
+    pub fn wait(self) -> Result<()> {
+        self.thread
+            .join()
+            .unwrap_or_else(|err| panic::resume_unwind(err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn finish(handle: &ConversionHandle) -> State {
+        loop {
+            let state = handle.state();
+            if state.is_finished() {
+                return state;
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn done() {
+        crate::init().unwrap();
+        let handle = Command::new()
+            .arg("-gstest")
+            .unwrap()
+            .gs("gs")
+            .unwrap()
+            .spawn();
+        assert_eq!(finish(&handle), State::Done);
+        handle.wait().unwrap();
+    }
+
+    #[test]
+    fn queued() {
+        crate::init().unwrap();
+        let guard = crate::FFI_LOCK
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let handle = Command::new()
+            .arg("-gstest")
+            .unwrap()
+            .gs("gs")
+            .unwrap()
+            .spawn();
+        while handle.state() == State::Processing {
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(handle.state(), State::Queued);
+        drop(guard);
+        assert_eq!(finish(&handle), State::Done);
+        handle.wait().unwrap();
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn cancel() {
+        use crate::engine::Subprocess;
+        use crate::Error;
+
+        let mut cmd = Command::new();
+        cmd.args_slice(&["-c", "sleep 10"]).unwrap();
+        let handle = Subprocess::new().program("sh").spawn(&cmd);
+        assert_eq!(handle.state(), State::Running);
+        handle.cancel();
+        assert_eq!(finish(&handle), State::Failed);
+        assert!(matches!(handle.wait(), Err(Error::Cancelled)));
+    }
+
+    #[test]
+    fn failed() {
+        crate::init().unwrap();
+        let handle = Command::new()
+            .format("psf")
+            .unwrap()
+            .input("does-not-exist.ps")
+            .unwrap()
+            .spawn();
+        assert_eq!(finish(&handle), State::Failed);
+        assert!(handle.wait().is_err());
+    }
+}
@@ -0,0 +1,159 @@
+//! Removal of volatile content from output files.
+//!
+//! Several drivers embed the creation date or the version of pstoedit in their
+//! output, so converting the same input twice does not give identical files.
+//! The rules in this module strip or normalize such content per driver, which
+//! makes output suitable for reproducible builds and golden-file tests.
+//!
+//! Rules are applied by [`Command::deterministic`], or directly using
+//! [`strip_volatile`]. Drivers without rules are left untouched.
+//!
+//! # Examples
+//! ```
+//! use pstoedit::deterministic::strip_volatile;
+//!
+//! let ps = b"%!PS-Adobe-3.0\n%%CreationDate: Mon Jan  1 00:00:00 2024\n%%EndComments\n";
+//! assert_eq!(strip_volatile("ps", ps), &b"%!PS-Adobe-3.0\n%%EndComments\n"[..]);
+//! ```
+//!
+//! [`Command::deterministic`]: crate::Command::deterministic
+
+use std::borrow::Cow;
+
+/// Rule to remove volatile content.
+enum Rule {
+    /// Remove lines starting with the prefix.
+    RemoveLine(&'static str),
+    /// Remove lines starting with the prefix and containing the marker.
+    RemoveLineWith(&'static str, &'static str),
+    /// Replace the bytes after the marker up to the terminator by zeros. This
+    /// preserves offsets, as required by e.g. the cross-reference table of
+    /// PDF files.
+    Mask(&'static str, u8),
+}
+
+/// Rules for drivers, given by symbolic name.
+const RULES: &[(&[&str], &[Rule])] = &[
+    (
+        &["ps", "psf", "ps2ai", "debug"],
+        &[
+            Rule::RemoveLine("%%CreationDate:"),
+            Rule::RemoveLine("%%Creator:"),
+        ],
+    ),
+    (
+        &["pdf", "plot-pdf"],
+        &[
+            Rule::Mask("/CreationDate (", b')'),
+            Rule::Mask("/ModDate (", b')'),
+            Rule::Mask("/Producer (", b')'),
+        ],
+    ),
+    (
+        &["svg", "plot-svg"],
+        &[Rule::RemoveLineWith("<!--", "pstoedit")],
+    ),
+];
+
+/// Whether rules exist for the driver, see [`strip_volatile`].
+pub fn has_rules(driver: &str) -> bool {
+    rules(driver).is_some()
+}
+
+/// Strip or normalize volatile content from the output of a driver.
+///
+/// `driver` is the symbolic name of the driver, options after a colon are
+/// ignored. The data is returned unchanged if there is nothing to strip.
+pub fn strip_volatile<'a>(driver: &str, data: &'a [u8]) -> Cow<'a, [u8]> {
+    let rules = match rules(driver) {
+        Some(rules) => rules,
+        None => return Cow::Borrowed(data),
+    };
+    let mut output = Vec::with_capacity(data.len());
+    for line in data.split_inclusive(|&b| b == b'\n') {
+        let remove = rules.iter().any(|rule| match rule {
+            Rule::RemoveLine(prefix) => line.starts_with(prefix.as_bytes()),
+            Rule::RemoveLineWith(prefix, marker) => {
+                line.starts_with(prefix.as_bytes()) && find(line, marker.as_bytes()).is_some()
+            }
+            Rule::Mask(..) => false,
+        });
+        if !remove {
+            output.extend_from_slice(line);
+        }
+    }
+    for rule in rules {
+        if let Rule::Mask(marker, terminator) = rule {
+            mask(&mut output, marker.as_bytes(), *terminator);
+        }
+    }
+    if output == data {
+        Cow::Borrowed(data)
+    } else {
+        Cow::Owned(output)
+    }
+}
+
+fn rules(driver: &str) -> Option<&'static [Rule]> {
+    let driver = driver.split(':').next().unwrap_or(driver);
+    RULES
+        .iter()
+        .find(|(drivers, _)| drivers.contains(&driver))
+        .map(|(_, rules)| *rules)
+}
+
+fn find(data: &[u8], needle: &[u8]) -> Option<usize> {
+    data.windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// Replace the bytes after every occurrence of `marker` up to `terminator` by
+/// zeros.
+fn mask(data: &mut [u8], marker: &[u8], terminator: u8) {
+    let mut start = 0;
+    while let Some(pos) = find(&data[start..], marker) {
+        start += pos + marker.len();
+        for b in data[start..].iter_mut().take_while(|b| **b != terminator) {
+            *b = b'0';
+            start += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn postscript() {
+        let data = b"%!PS\n%%Creator: pstoedit 4.01\n%%CreationDate: today\nshowpage\n";
+        assert_eq!(strip_volatile("psf", data), &b"%!PS\nshowpage\n"[..]);
+    }
+
+    #[test]
+    fn pdf() {
+        let data = b"<< /CreationDate (D:20240421) /Producer (pstoedit) >>";
+        let stripped = strip_volatile("pdf", data);
+        assert_eq!(
+            stripped,
+            &b"<< /CreationDate (0000000000) /Producer (00000000) >>"[..]
+        );
+        assert_eq!(stripped.len(), data.len());
+    }
+
+    #[test]
+    fn svg() {
+        let data = b"<?xml?>\n<!-- generated by pstoedit 4.01 -->\n<!-- keep -->\n<svg/>";
+        let stripped = strip_volatile("svg:-nfw", data);
+        assert_eq!(stripped, &b"<?xml?>\n<!-- keep -->\n<svg/>"[..]);
+    }
+
+    #[test]
+    fn unchanged() {
+        let data = b"%%CreationDate: today\n";
+        assert!(matches!(strip_volatile("fig", data), Cow::Borrowed(_)));
+        assert!(matches!(strip_volatile("ps", b"%!PS\n"), Cow::Borrowed(_)));
+        assert!(!has_rules("fig"));
+        assert!(has_rules("svg:-nfw"));
+    }
+}
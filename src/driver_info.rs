@@ -14,11 +14,6 @@
 //! let drivers = DriverInfo::get()?;
 //! let native_drivers = DriverInfo::get_native()?;
 //!
-//! // The number of non-native drivers cannot be negative
-//! let num = drivers.iter().count();
-//! let num_native = native_drivers.iter().count();
-//! assert!(num >= num_native);
-//!
 //! // Ensure all drivers have a unique symbolic name
 //! let mut formats = HashSet::new();
 //! for driver in &drivers {
@@ -26,15 +21,17 @@
 //! }
 //!
 //! // Ensure all native drivers are included in the list of all drivers
-//! for driver in &native_drivers {
-//!     assert!(formats.contains(driver.symbolic_name()?));
-//! }
+//! let num_other = drivers.plugins_only()?.count();
+//! assert_eq!(drivers.len(), native_drivers.len() + num_other);
 //! # Ok::<(), pstoedit::Error>(())
 //! ```
 
 use crate::ffi;
 use crate::{Error, Result};
+use std::cell::OnceCell;
+use std::collections::HashSet;
 use std::ffi::CStr;
+use std::ops::{BitOr, BitOrAssign};
 use std::ptr::NonNull;
 
 /// Format group of pstoedit driver.
@@ -46,6 +43,68 @@ use std::ptr::NonNull;
 #[cfg_attr(docsrs, doc(cfg(feature = "pstoedit_4_00")))]
 pub struct FormatGroup(std::ffi::c_int);
 
+/// Set of features supported by a pstoedit driver.
+///
+/// Capabilities are combined using `|`, and can be used to select drivers
+/// with [`DriverInfo::with_capabilities`].
+///
+/// # Examples
+/// ```
+/// use pstoedit::driver_info::Capabilities;
+///
+/// let required = Capabilities::TEXT | Capabilities::MULTIPAGE;
+/// assert!(required.contains(Capabilities::TEXT));
+/// assert!(!required.contains(Capabilities::IMAGES));
+/// assert!(Capabilities::all().contains(required));
+/// ```
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Capabilities(u8);
+
+impl Capabilities {
+    /// Support for subpaths, see [`DriverDescription::subpath_support`].
+    pub const SUBPATHS: Self = Self(1 << 0);
+    /// Support for curveto, see [`DriverDescription::curveto_support`].
+    pub const CURVETO: Self = Self(1 << 1);
+    /// Support for merging, see [`DriverDescription::merging_support`].
+    pub const MERGING: Self = Self(1 << 2);
+    /// Support for text, see [`DriverDescription::text_support`].
+    pub const TEXT: Self = Self(1 << 3);
+    /// Support for images, see [`DriverDescription::image_support`].
+    pub const IMAGES: Self = Self(1 << 4);
+    /// Support for multiple pages, see
+    /// [`DriverDescription::multipage_support`].
+    pub const MULTIPAGE: Self = Self(1 << 5);
+
+    /// No capabilities.
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    /// All capabilities.
+    pub const fn all() -> Self {
+        Self((1 << 6) - 1)
+    }
+
+    /// Whether all capabilities in `other` are also in `self`.
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for Capabilities {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for Capabilities {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
 /// Description of pstoedit driver.
 ///
 /// Information on pstoedit drivers can be obtained through [`DriverInfo`].
@@ -110,6 +169,25 @@ impl<'a> DriverDescription<'a> {
         self.0.backendSupportsMultiplePages != 0
     }
 
+    /// All features supported by the backend.
+    pub fn capabilities(self) -> Capabilities {
+        let flags = [
+            (self.subpath_support(), Capabilities::SUBPATHS),
+            (self.curveto_support(), Capabilities::CURVETO),
+            (self.merging_support(), Capabilities::MERGING),
+            (self.text_support(), Capabilities::TEXT),
+            (self.image_support(), Capabilities::IMAGES),
+            (self.multipage_support(), Capabilities::MULTIPAGE),
+        ];
+        let mut capabilities = Capabilities::empty();
+        for (supported, capability) in flags.iter() {
+            if *supported {
+                capabilities |= *capability;
+            }
+        }
+        capabilities
+    }
+
     /// Format group of driver.
     #[cfg(feature = "pstoedit_4_00")]
     #[cfg_attr(docsrs, doc(cfg(feature = "pstoedit_4_00")))]
@@ -123,7 +201,8 @@ impl<'a> DriverDescription<'a> {
 /// See [module-level documentation][self] for more details.
 // Holds pointer to first element of DriverDescription_S array
 // The end of the array is indicated by an element with a null pointer as symbolicname
-pub struct DriverInfo(NonNull<ffi::DriverDescription_S>);
+// Also holds the number of drivers once counted
+pub struct DriverInfo(NonNull<ffi::DriverDescription_S>, OnceCell<usize>);
 
 impl DriverInfo {
     /// Inquire driver information.
@@ -143,7 +222,9 @@ impl DriverInfo {
     /// not called successfully.
     pub fn get() -> Result<Self> {
         let info = unsafe { ffi::getPstoeditDriverInfo_plainC() };
-        NonNull::new(info).map(Self).ok_or(Error::NotInitialized)
+        NonNull::new(info)
+            .map(|info| Self(info, OnceCell::new()))
+            .ok_or(Error::NotInitialized)
     }
 
     /// Inquire native driver information.
@@ -151,14 +232,177 @@ impl DriverInfo {
     /// See [`get`][DriverInfo::get] for usage.
     pub fn get_native() -> Result<Self> {
         let info = unsafe { ffi::getPstoeditNativeDriverInfo_plainC() };
-        NonNull::new(info).map(Self).ok_or(Error::NotInitialized)
+        NonNull::new(info)
+            .map(|info| Self(info, OnceCell::new()))
+            .ok_or(Error::NotInitialized)
+    }
+
+    /// Check that the driver information matches the layout of
+    /// `DriverDescription_S` this crate was compiled for.
+    ///
+    /// This is a best-effort check: every driver must have non-null strings
+    /// and boolean flags, and the list must end within a sane number of
+    /// drivers. A mismatching layout shifts fields of later drivers, which
+    /// then fail these conditions.
+    pub(crate) fn check_layout(&self) -> Result<()> {
+        const MAX_DRIVERS: isize = 1024;
+        for offset in 0..MAX_DRIVERS {
+            // Safety: all elements up to and including the terminating one
+            // are valid if the layout matches; for a mismatching layout this
+            // is a best effort to detect it before reading out of bounds
+            let driver = unsafe { &*self.0.as_ptr().offset(offset) };
+            if driver.symbolicname.is_null() {
+                return Ok(());
+            }
+            let strings = [driver.explanation, driver.suffix, driver.additionalInfo];
+            let flags = [
+                driver.backendSupportsSubPaths,
+                driver.backendSupportsCurveto,
+                driver.backendSupportsMerging,
+                driver.backendSupportsText,
+                driver.backendSupportsImages,
+                driver.backendSupportsMultiplePages,
+            ];
+            if strings.iter().any(|s| s.is_null()) || flags.iter().any(|&f| f != 0 && f != 1) {
+                return Err(Error::AbiMismatch);
+            }
+        }
+        Err(Error::AbiMismatch)
+    }
+
+    /// Find driver by its symbolic name, e.g. `"svg"`.
+    ///
+    /// # Examples
+    /// ```
+    /// pstoedit::init().unwrap();
+    /// let drivers = pstoedit::DriverInfo::get().unwrap();
+    /// if let Some(driver) = drivers.find("psf") {
+    ///     assert_eq!(driver.extension().unwrap(), "fps");
+    /// }
+    /// ```
+    pub fn find(&self, symbolic_name: &str) -> Option<DriverDescription<'_>> {
+        self.iter()
+            .find(|driver| driver.symbolic_name().ok() == Some(symbolic_name))
+    }
+
+    /// Find driver by the extension of its output files, e.g. `"svg"`.
+    ///
+    /// Extensions are compared case-insensitively.
+    ///
+    /// # Examples
+    /// ```
+    /// pstoedit::init()?;
+    /// let drivers = pstoedit::DriverInfo::get()?;
+    /// if let Some(driver) = drivers.find_by_extension("fig")? {
+    ///     println!("Using {}", driver.symbolic_name()?);
+    /// }
+    /// # Ok::<(), pstoedit::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    /// [`AmbiguousExtension`][Error::AmbiguousExtension] if multiple drivers
+    /// use the extension.
+    pub fn find_by_extension(&self, extension: &str) -> Result<Option<DriverDescription<'_>>> {
+        let matches: Vec<_> = self
+            .iter()
+            .filter(|driver| {
+                driver
+                    .extension()
+                    .is_ok_and(|ext| ext.eq_ignore_ascii_case(extension))
+            })
+            .collect();
+        if matches.len() <= 1 {
+            return Ok(matches.into_iter().next());
+        }
+        let names = matches
+            .into_iter()
+            .filter_map(|driver| driver.symbolic_name().ok())
+            .map(str::to_owned)
+            .collect();
+        Err(Error::AmbiguousExtension(extension.to_owned(), names))
+    }
+
+    /// Iterate over the drivers that support at least the `required`
+    /// capabilities.
+    ///
+    /// # Examples
+    /// ```
+    /// use pstoedit::driver_info::Capabilities;
+    ///
+    /// pstoedit::init()?;
+    /// let drivers = pstoedit::DriverInfo::get()?;
+    /// for driver in drivers.with_capabilities(Capabilities::TEXT | Capabilities::MULTIPAGE) {
+    ///     assert!(driver.text_support() && driver.multipage_support());
+    /// }
+    /// # Ok::<(), pstoedit::Error>(())
+    /// ```
+    pub fn with_capabilities(
+        &self,
+        required: Capabilities,
+    ) -> impl Iterator<Item = DriverDescription<'_>> {
+        self.iter()
+            .filter(move |driver| driver.capabilities().contains(required))
+    }
+
+    /// Iterate over the drivers in this information that are not native,
+    /// i.e. that are not in [`get_native`][DriverInfo::get_native].
+    ///
+    /// # Examples
+    /// ```
+    /// use pstoedit::DriverInfo;
+    ///
+    /// pstoedit::init()?;
+    /// let drivers = DriverInfo::get()?;
+    /// let native = DriverInfo::get_native()?;
+    /// assert_eq!(drivers.plugins_only()?.count(), drivers.len() - native.len());
+    /// # Ok::<(), pstoedit::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    /// [`NotInitialized`][Error::NotInitialized] if [`init`][crate::init] was
+    /// not called successfully.
+    pub fn plugins_only(&self) -> Result<impl Iterator<Item = DriverDescription<'_>>> {
+        let native: HashSet<_> = DriverInfo::get_native()?
+            .iter()
+            .filter_map(|driver| driver.symbolic_name().ok().map(str::to_owned))
+            .collect();
+        Ok(self.iter().filter(move |driver| {
+            driver
+                .symbolic_name()
+                .map_or(true, |name| !native.contains(name))
+        }))
+    }
+
+    /// Number of drivers.
+    ///
+    /// The drivers are counted on the first call, which does not consume an
+    /// iterator, and this also makes [`Iter`] an [`ExactSizeIterator`].
+    ///
+    /// # Examples
+    /// ```
+    /// pstoedit::init()?;
+    /// let drivers = pstoedit::DriverInfo::get()?;
+    /// let mut names = Vec::with_capacity(drivers.len());
+    /// for driver in &drivers {
+    ///     names.push(driver.symbolic_name()?);
+    /// }
+    /// assert_eq!(names.len(), drivers.len());
+    /// # Ok::<(), pstoedit::Error>(())
+    /// ```
+    pub fn len(&self) -> usize {
+        *self.1.get_or_init(|| self.iter().count())
+    }
+
+    /// Whether there are no drivers.
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
     }
 
     /// Generate iterator over drivers in driver information.
     ///
     /// # Examples
     /// See [`get`][DriverInfo::get].
-    pub fn iter(&self) -> Iter {
+    pub fn iter(&self) -> Iter<'_> {
         Iter {
             driver_info: self,
             offset: 0,
@@ -214,8 +458,15 @@ impl<'a> Iterator for Iter<'a> {
             })
         }
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.driver_info.len() - self.offset as usize;
+        (remaining, Some(remaining))
+    }
 }
 
+impl ExactSizeIterator for Iter<'_> {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -224,14 +475,20 @@ mod tests {
     fn driver_info() {
         crate::init().unwrap();
         let drivers = DriverInfo::get().unwrap();
-        assert!(!drivers.iter().next().is_none());
+        assert!(drivers.iter().next().is_some());
     }
 
     #[test]
     fn driver_info_native() {
         crate::init().unwrap();
         let drivers = DriverInfo::get_native().unwrap();
-        assert!(!drivers.iter().next().is_none());
+        assert!(drivers.iter().next().is_some());
+    }
+
+    #[test]
+    fn driver_layout() {
+        crate::init().unwrap();
+        DriverInfo::get().unwrap().check_layout().unwrap();
     }
 
     #[test]
@@ -256,6 +513,26 @@ mod tests {
         }
     }
 
+    #[test]
+    fn driver_find() {
+        crate::init().unwrap();
+        let info = DriverInfo::get().unwrap();
+        assert_eq!(info.find("psf").unwrap().symbolic_name().unwrap(), "psf");
+        assert!(info.find("no-such-driver").is_none());
+    }
+
+    #[test]
+    fn driver_find_by_extension() {
+        crate::init().unwrap();
+        let info = DriverInfo::get().unwrap();
+        let driver = info.find_by_extension("FPS").unwrap().unwrap();
+        assert_eq!(driver.symbolic_name().unwrap(), "psf");
+        assert!(info
+            .find_by_extension("no-such-extension")
+            .unwrap()
+            .is_none());
+    }
+
     #[test]
     fn psf_driver() {
         crate::init().unwrap();
@@ -272,4 +549,54 @@ mod tests {
         assert!(driver.image_support());
         assert!(driver.multipage_support());
     }
+
+    #[test]
+    fn capabilities() {
+        crate::init().unwrap();
+        let info = DriverInfo::get().unwrap();
+        let psf = info.find("psf").unwrap().capabilities();
+        assert!(psf.contains(Capabilities::TEXT | Capabilities::MULTIPAGE));
+        assert!(!psf.contains(Capabilities::CURVETO));
+        assert!(Capabilities::all().contains(psf));
+        let required = Capabilities::TEXT | Capabilities::IMAGES;
+        let names: Vec<_> = info
+            .with_capabilities(required)
+            .map(|driver| driver.symbolic_name().unwrap())
+            .collect();
+        assert!(names.contains(&"psf"));
+        assert!(info.with_capabilities(Capabilities::all()).count() < info.iter().count());
+        assert_eq!(
+            info.with_capabilities(Capabilities::empty()).count(),
+            info.iter().count()
+        );
+    }
+
+    #[test]
+    fn driver_len() {
+        crate::init().unwrap();
+        let info = DriverInfo::get().unwrap();
+        assert!(!info.is_empty());
+        let count = info.iter().count();
+        assert_eq!(info.len(), count);
+        let mut iter = info.iter();
+        assert_eq!(iter.len(), count);
+        iter.next();
+        assert_eq!(iter.size_hint(), (count - 1, Some(count - 1)));
+        assert_eq!(info.iter().collect::<Vec<_>>().len(), count);
+    }
+
+    #[test]
+    fn driver_plugins_only() {
+        crate::init().unwrap();
+        let info = DriverInfo::get().unwrap();
+        let native = DriverInfo::get_native().unwrap();
+        for driver in info.plugins_only().unwrap() {
+            let name = driver.symbolic_name().unwrap();
+            assert!(native.find(name).is_none());
+        }
+        assert_eq!(
+            info.plugins_only().unwrap().count() + native.len(),
+            info.len()
+        );
+    }
 }
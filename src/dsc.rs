@@ -0,0 +1,388 @@
+//! Parsing of Document Structuring Convention comments.
+//!
+//! PostScript documents conforming to the Document Structuring Conventions
+//! (DSC) describe themselves through comments starting with `%%`. These can be
+//! parsed using [`Document::parse`] without running ghostscript. Multiple
+//! documents can be merged into one using [`concat()`].
+//!
+//! # Examples
+//! ```
+//! use pstoedit::dsc::Document;
+//!
+//! let data = b"%!PS-Adobe-3.0
+//! %%Title: (Example)
+//! %%Pages: 2
+//! %%EndComments
+//! %%Page: 1 1
+//! showpage
+//! %%Page: 2 2
+//! showpage
+//! %%EOF
+//! ";
+//! let doc = Document::parse(data);
+//! assert_eq!(doc.title.as_deref(), Some("Example"));
+//! assert_eq!(doc.pages, Some(2));
+//! assert_eq!(doc.page_list.len(), 2);
+//! ```
+
+use crate::input::{BoundingBox, InputKind};
+use crate::Result;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::ops::Range;
+use std::path::Path;
+
+/// Page of a DSC-conforming document.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Page {
+    /// Label of the page, typically its number.
+    pub label: String,
+    /// Ordinal number of the page, starting at 1.
+    pub ordinal: u32,
+    /// Byte range of the page, from its `%%Page` comment up to the next page
+    /// or trailer.
+    pub range: Range<usize>,
+}
+
+/// Information from the DSC comments of a document.
+///
+/// See [module-level documentation][self] for more details.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[non_exhaustive]
+pub struct Document {
+    /// Title of the document.
+    pub title: Option<String>,
+    /// Creator of the document.
+    pub creator: Option<String>,
+    /// Creation date of the document, in an unspecified format.
+    pub creation_date: Option<String>,
+    /// Number of pages declared by the `%%Pages` comment.
+    pub pages: Option<u32>,
+    /// Bounding box, preferring the high-resolution bounding box.
+    pub bounding_box: Option<BoundingBox>,
+    /// Pages marked by `%%Page` comments.
+    pub page_list: Vec<Page>,
+    /// Byte offset of the `%%Trailer` comment.
+    pub trailer: Option<usize>,
+}
+
+impl Document {
+    /// Parse DSC comments of a document.
+    ///
+    /// Comments deferred to the trailer using `(atend)` are supported, while
+    /// those of embedded documents are ignored. Missing or malformed comments
+    /// result in `None` values.
+    pub fn parse(data: &[u8]) -> Self {
+        let mut doc = Self::default();
+        let mut hires = None;
+        let mut depth = 0usize;
+        for (offset, line) in lines(data) {
+            if line.starts_with(b"%%BeginDocument") {
+                depth += 1;
+            } else if line.starts_with(b"%%EndDocument") {
+                depth = depth.saturating_sub(1);
+            } else if depth > 0 || !line.starts_with(b"%%") {
+                continue;
+            } else if let Some(value) = line.strip_prefix(b"%%Page:") {
+                doc.end_page(offset);
+                let value = text(value);
+                let mut parts = value.rsplitn(2, ' ');
+                let ordinal = parts.next().and_then(|s| s.parse().ok());
+                let label = parts.next().map(|s| s.trim().to_owned());
+                doc.page_list.push(Page {
+                    label: label.unwrap_or_default(),
+                    ordinal: ordinal.unwrap_or(doc.page_list.len() as u32 + 1),
+                    range: offset..data.len(),
+                });
+            } else if line.starts_with(b"%%Trailer") {
+                doc.end_page(offset);
+                doc.trailer = Some(offset);
+            } else if let Some(value) = line.strip_prefix(b"%%Title:") {
+                doc.title = doc.title.or_else(|| Some(text(value)));
+            } else if let Some(value) = line.strip_prefix(b"%%Creator:") {
+                doc.creator = doc.creator.or_else(|| Some(text(value)));
+            } else if let Some(value) = line.strip_prefix(b"%%CreationDate:") {
+                doc.creation_date = doc.creation_date.or_else(|| Some(text(value)));
+            } else if let Some(value) = line.strip_prefix(b"%%Pages:") {
+                let value = text(value);
+                let value = value.split_whitespace().next().and_then(|s| s.parse().ok());
+                doc.pages = doc.pages.or(value);
+            } else if let Some(value) = line.strip_prefix(b"%%HiResBoundingBox:") {
+                hires = hires.or_else(|| BoundingBox::parse(value));
+            } else if let Some(value) = line.strip_prefix(b"%%BoundingBox:") {
+                doc.bounding_box = doc.bounding_box.or_else(|| BoundingBox::parse(value));
+            }
+        }
+        doc.bounding_box = hires.or(doc.bounding_box);
+        doc
+    }
+
+    /// Parse DSC comments of a file.
+    ///
+    /// See [`parse`][Document::parse] for details.
+    ///
+    /// # Errors
+    /// [`IoError`][crate::Error::IoError] if the file cannot be read.
+    pub fn from_file<P>(path: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        Ok(Self::parse(&fs::read(path)?))
+    }
+
+    /// Byte range of the header, i.e. everything before the first page.
+    ///
+    /// This is `None` if the document has no `%%Page` comments.
+    pub fn header(&self) -> Option<Range<usize>> {
+        self.page_list.first().map(|page| 0..page.range.start)
+    }
+
+    /// End the last page at `offset`, if it is still open.
+    fn end_page(&mut self, offset: usize) {
+        if self.trailer.is_none() {
+            if let Some(page) = self.page_list.last_mut() {
+                page.range.end = offset;
+            }
+        }
+    }
+}
+
+/// Concatenate PostScript documents into a single multi-page document.
+///
+/// Every document is run in its own VM save level, so definitions of one
+/// document do not affect the others. The DSC comments of the documents are
+/// removed, except for `%%Page` comments which are renumbered, and new
+/// document-level comments are written. A document without `%%Page` comments,
+/// such as an EPS file, becomes a single page.
+///
+/// # Examples
+/// ```no_run
+/// use std::fs::{self, File};
+///
+/// let first = fs::read("first.ps")?;
+/// let second = fs::read("second.eps")?;
+/// pstoedit::dsc::concat(&[first, second], File::create("merged.ps")?)?;
+/// # Ok::<(), pstoedit::Error>(())
+/// ```
+///
+/// # Errors
+/// [`IoError`][crate::Error::IoError] if writing the output fails.
+pub fn concat<I, W>(inputs: I, mut output: W) -> Result<()>
+where
+    I: IntoIterator,
+    I::Item: AsRef<[u8]>,
+    W: Write,
+{
+    let inputs: Vec<_> = inputs.into_iter().collect();
+    let docs: Vec<_> = inputs.iter().map(|i| Document::parse(i.as_ref())).collect();
+    let pages: usize = docs.iter().map(|doc| doc.page_list.len().max(1)).sum();
+    let bbox = docs
+        .iter()
+        .map(|doc| doc.bounding_box)
+        .reduce(|a, b| Some(a?.union(&b?)))
+        .flatten();
+
+    writeln!(output, "%!PS-Adobe-3.0")?;
+    writeln!(output, "%%Pages: {}", pages)?;
+    if let Some(b) = bbox {
+        let (llx, lly) = (b.llx.floor(), b.lly.floor());
+        let (urx, ury) = (b.urx.ceil(), b.ury.ceil());
+        writeln!(output, "%%BoundingBox: {} {} {} {}", llx, lly, urx, ury)?;
+        writeln!(
+            output,
+            "%%HiResBoundingBox: {} {} {} {}",
+            b.llx, b.lly, b.urx, b.ury
+        )?;
+    }
+    writeln!(output, "%%EndComments")?;
+
+    let mut ordinal = 0;
+    for (input, doc) in inputs.iter().zip(&docs) {
+        let data = input.as_ref();
+        let eps = InputKind::detect(data).ok() == Some(InputKind::Eps);
+        // Pages as byte ranges; the entire document if there are none
+        let ranges: Vec<_> = if doc.page_list.is_empty() {
+            std::iter::once(0..data.len()).collect()
+        } else {
+            doc.page_list.iter().map(|p| p.range.clone()).collect()
+        };
+        for (i, range) in ranges.iter().enumerate() {
+            ordinal += 1;
+            writeln!(output, "%%Page: {} {}", ordinal, ordinal)?;
+            if i == 0 {
+                writeln!(output, "userdict /pstoedit_rs_state save put")?;
+                if eps {
+                    // Pages are emitted explicitly after restoring
+                    writeln!(output, "/showpage {{}} def")?;
+                }
+                if let Some(header) = doc.header() {
+                    write_stripped(&mut output, &data[header])?;
+                }
+            }
+            write_stripped(&mut output, &data[range.clone()])?;
+        }
+        if let Some(trailer) = doc.trailer {
+            write_stripped(&mut output, &data[trailer..])?;
+        }
+        writeln!(output)?;
+        writeln!(output, "userdict /pstoedit_rs_state get restore")?;
+        if eps {
+            writeln!(output, "showpage")?;
+        }
+    }
+    writeln!(output, "%%Trailer")?;
+    writeln!(output, "%%EOF")?;
+    Ok(())
+}
+
+/// Concatenate PostScript files into a single multi-page file.
+///
+/// See [`concat()`] for details.
+///
+/// # Errors
+/// [`IoError`][crate::Error::IoError] if reading or writing a file fails.
+pub fn concat_files<P, Q>(inputs: &[P], output: Q) -> Result<()>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    let inputs = inputs
+        .iter()
+        .map(fs::read)
+        .collect::<std::io::Result<Vec<_>>>()?;
+    let mut output = BufWriter::new(File::create(output)?);
+    concat(&inputs, &mut output)?;
+    output.flush()?;
+    Ok(())
+}
+
+/// Write data without top-level DSC comments and header lines.
+///
+/// Embedded documents are written unchanged. Line endings are preserved, as
+/// they may be part of binary data.
+fn write_stripped<W: Write>(output: &mut W, data: &[u8]) -> Result<()> {
+    let offsets: Vec<_> = lines(data).map(|(offset, _)| offset).collect();
+    let mut depth = 0usize;
+    for (i, &start) in offsets.iter().enumerate() {
+        let end = offsets.get(i + 1).copied().unwrap_or(data.len());
+        let line = &data[start..end];
+        if line.starts_with(b"%%BeginDocument") {
+            depth += 1;
+        } else if line.starts_with(b"%%EndDocument") {
+            depth = depth.saturating_sub(1);
+        } else if depth == 0 && (line.starts_with(b"%%") || line.starts_with(b"%!")) {
+            continue;
+        }
+        output.write_all(line)?;
+    }
+    Ok(())
+}
+
+/// Iterator over lines and their byte offsets, accepting any line ending.
+pub(crate) fn lines(data: &[u8]) -> impl Iterator<Item = (usize, &[u8])> {
+    let mut offset = 0;
+    std::iter::from_fn(move || {
+        if offset >= data.len() {
+            return None;
+        }
+        let start = offset;
+        let rest = &data[start..];
+        let len = rest
+            .iter()
+            .position(|&b| b == b'\n' || b == b'\r')
+            .unwrap_or(rest.len());
+        offset += len;
+        if rest[len..].starts_with(b"\r\n") {
+            offset += 2;
+        } else if len < rest.len() {
+            offset += 1;
+        }
+        Some((start, &rest[..len]))
+    })
+}
+
+/// Text value of a comment, without surrounding whitespace or parentheses.
+fn text(value: &[u8]) -> String {
+    let value = String::from_utf8_lossy(value);
+    let value = value.trim();
+    value
+        .strip_prefix('(')
+        .and_then(|v| v.strip_suffix(')'))
+        .unwrap_or(value)
+        .to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOC: &[u8] = b"%!PS-Adobe-3.0\r\n\
+        %%Creator: test\r\n\
+        %%Pages: (atend)\r\n\
+        %%BoundingBox: 0 0 100 100\r\n\
+        %%EndComments\r\n\
+        %%Page: i 1\r\n\
+        %%BeginDocument: nested.eps\r\n\
+        %%Page: 1 1\r\n\
+        %%EndDocument\r\n\
+        showpage\r\n\
+        %%Page: ii 2\r\n\
+        showpage\r\n\
+        %%Trailer\r\n\
+        %%Pages: 2\r\n\
+        %%EOF\r\n";
+
+    #[test]
+    fn parse() {
+        let doc = Document::parse(DOC);
+        assert_eq!(doc.title, None);
+        assert_eq!(doc.creator.as_deref(), Some("test"));
+        assert_eq!(doc.pages, Some(2));
+        assert_eq!(doc.bounding_box.unwrap().width(), 100.0);
+        assert_eq!(doc.page_list.len(), 2);
+        let first = &doc.page_list[0];
+        assert_eq!((first.label.as_str(), first.ordinal), ("i", 1));
+        assert!(DOC[first.range.clone()].starts_with(b"%%Page: i 1"));
+        assert!(DOC[first.range.clone()].ends_with(b"showpage\r\n"));
+        let second = &doc.page_list[1];
+        assert_eq!(second.range.end, doc.trailer.unwrap());
+        assert_eq!(doc.header(), Some(0..first.range.start));
+    }
+
+    #[test]
+    fn concat_documents() {
+        let eps = b"%!PS-Adobe-3.0 EPSF-3.0\n%%BoundingBox: 50 50 200 150\nnewpath\n";
+        let mut merged = Vec::new();
+        concat(&[DOC, &eps[..]], &mut merged).unwrap();
+        let doc = Document::parse(&merged);
+        assert_eq!(doc.pages, Some(3));
+        assert_eq!(doc.page_list.len(), 3);
+        let ordinals: Vec<_> = doc.page_list.iter().map(|p| p.ordinal).collect();
+        assert_eq!(ordinals, [1, 2, 3]);
+        let bbox = doc.bounding_box.unwrap();
+        assert_eq!(
+            [bbox.llx, bbox.lly, bbox.urx, bbox.ury],
+            [0.0, 0.0, 200.0, 150.0]
+        );
+        assert_eq!(doc.creator, None);
+        // Embedded document and line endings are preserved
+        let merged = String::from_utf8(merged).unwrap();
+        assert!(merged.contains("%%BeginDocument: nested.eps\r\n%%Page: 1 1\r\n"));
+        assert_eq!(merged.matches("showpage").count(), 4);
+    }
+
+    #[test]
+    fn lines_offsets() {
+        let lines: Vec<_> = lines(b"a\nb\r\nc\rd").collect();
+        assert_eq!(
+            lines,
+            [
+                (0, &b"a"[..]),
+                (2, &b"b"[..]),
+                (5, &b"c"[..]),
+                (7, &b"d"[..])
+            ]
+        );
+    }
+}
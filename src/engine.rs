@@ -0,0 +1,649 @@
+//! Execution of commands.
+//!
+//! An [`Engine`] runs a [`Command`]. Code that is generic over the engine can
+//! be tested without running pstoedit, e.g. using `MockEngine` from the
+//! `test_support` module, which requires the feature of the same name.
+//!
+//! # Examples
+//! ```no_run
+//! use pstoedit::engine::{Engine, InProcess};
+//! use pstoedit::Command;
+//!
+//! fn convert<E: Engine>(engine: &E, input: &str) -> pstoedit::Result<()> {
+//!     let mut cmd = Command::new();
+//!     cmd.format("svg")?.input(input)?.output("output.svg")?;
+//!     engine.run(&cmd)
+//! }
+//!
+//! pstoedit::init()?;
+//! convert(&InProcess, "input.ps")?;
+//! # Ok::<(), pstoedit::Error>(())
+//! ```
+//!
+//! The [`Subprocess`] engine runs the pstoedit executable instead, which
+//! isolates crashes and allows [`Limits`] on the resources used by the
+//! interpreter, as is advisable for untrusted documents.
+//!
+//! ```no_run
+//! use pstoedit::engine::{Engine, Limits, Subprocess};
+//! use pstoedit::Command;
+//! use std::time::Duration;
+//!
+//! let limits = Limits::new()
+//!     .cpu_time(Duration::from_secs(30))
+//!     .memory(512 << 20);
+//! let engine = Subprocess::new().limits(limits);
+//! let mut cmd = Command::new();
+//! cmd.format("svg")?.input("untrusted.ps")?.output("output.svg")?;
+//! engine.run(&cmd)?;
+//! # Ok::<(), pstoedit::Error>(())
+//! ```
+
+use crate::conversion::ConversionHandle;
+use crate::process::Abort;
+use crate::scratch::ScratchDir;
+use crate::{Command, Error, GsErrorKind, Result, RunReport, Warning};
+use std::ffi::{CStr, OsStr};
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{self, Stdio};
+use std::time::Duration;
+
+#[cfg(all(feature = "sandbox", target_os = "linux"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "sandbox", target_os = "linux"))))]
+pub use crate::sandbox::Sandbox;
+
+/// Strategy to run commands.
+///
+/// Besides the engines of this crate, applications can implement this to run
+/// commands elsewhere, e.g. on a remote worker. Commands are run using an
+/// engine with [`Engine::run`] or [`Command::run_with`].
+pub trait Engine {
+    /// Run a command.
+    fn run(&self, command: &Command) -> Result<()>;
+}
+
+/// Engine that runs pstoedit in-process through its C API.
+///
+/// This is equivalent to [`Command::run`].
+#[derive(Copy, Clone, Debug, Default)]
+pub struct InProcess;
+
+impl Engine for InProcess {
+    fn run(&self, command: &Command) -> Result<()> {
+        command.run()
+    }
+}
+
+/// Engine that runs the pstoedit executable as a child process.
+///
+/// Everything configured on the command applies as with [`Command::run`],
+/// except that pstoedit itself is not run through its C API, so
+/// [`init`][crate::init] is not required and runs are not serialized. The
+/// ghostscript executable set using [`Command::gs`] is passed using `-gs`.
+///
+/// Processes started by pstoedit, such as ghostscript, are terminated
+/// together with it when a limit is hit or the conversion is cancelled. On
+/// Windows, they are run in a job object to this end.
+///
+/// # Examples
+/// See [module-level documentation][self].
+#[derive(Clone, Debug)]
+pub struct Subprocess {
+    program: PathBuf,
+    limits: Limits,
+    scratch_dir: bool,
+    #[cfg(all(feature = "sandbox", target_os = "linux"))]
+    sandbox: Option<Sandbox>,
+}
+
+impl Subprocess {
+    /// Create engine running `pstoedit` from `PATH`, without limits.
+    pub fn new() -> Self {
+        Self {
+            program: PathBuf::from("pstoedit"),
+            limits: Limits::new(),
+            scratch_dir: false,
+            #[cfg(all(feature = "sandbox", target_os = "linux"))]
+            sandbox: None,
+        }
+    }
+
+    /// Set the pstoedit executable.
+    pub fn program<P>(mut self, program: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.program = program.into();
+        self
+    }
+
+    /// Set limits on the resources used by pstoedit and the interpreter.
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Run pstoedit in a scratch directory, created for every run.
+    ///
+    /// The input file is copied into the directory, which becomes the working
+    /// directory of pstoedit and holds its temporary files, so that relative
+    /// file names in the document refer to the directory. After a successful
+    /// run, the output file and any other files written next to it are moved
+    /// to the directory of the output file. Relative paths in other arguments
+    /// are resolved in the scratch directory too.
+    ///
+    /// This only redirects the files named in the command, it does not
+    /// isolate pstoedit: the document and the interpreter can still read and
+    /// write any absolute path the process has access to. Restrict access
+    /// with a sandbox for that, where available.
+    pub fn scratch_dir(mut self, scratch_dir: bool) -> Self {
+        self.scratch_dir = scratch_dir;
+        self
+    }
+
+    /// Restrict the file system and network access of pstoedit and the
+    /// interpreter.
+    #[cfg(all(feature = "sandbox", target_os = "linux"))]
+    #[cfg_attr(docsrs, doc(cfg(all(feature = "sandbox", target_os = "linux"))))]
+    pub fn sandbox(mut self, sandbox: Sandbox) -> Self {
+        self.sandbox = Some(sandbox);
+        self
+    }
+
+    /// Run a command on a new thread.
+    ///
+    /// Unlike [`Command::spawn`], the returned handle can
+    /// [cancel][ConversionHandle::cancel] the conversion, which terminates
+    /// pstoedit together with the interpreter it started.
+    pub fn spawn(&self, command: &Command) -> ConversionHandle {
+        let engine = self.clone();
+        let command = command.clone();
+        let abort = Abort::new();
+        let thread_abort = abort.clone();
+        ConversionHandle::spawn_with(
+            move || command.run_via(&|command| engine.exec(command, Some(&thread_abort))),
+            abort,
+        )
+    }
+
+    /// Run a command and report on the run.
+    ///
+    /// This behaves like [`run`][Engine::run], but additionally reports like
+    /// [`Command::run_report`], including the warnings written to standard
+    /// error by pstoedit and the interpreter.
+    ///
+    /// # Errors
+    /// See [`Engine::run`].
+    pub fn run_report(&self, command: &Command) -> Result<RunReport> {
+        command.run_report_via(&|command| self.exec(command, None))
+    }
+
+    /// Run a command, cancelling it if the process receives `SIGINT` (e.g.
+    /// from Ctrl-C) or `SIGTERM`.
+    ///
+    /// pstoedit and the processes it started are terminated, and the partial
+    /// output file is removed. While the command runs, the signals do not
+    /// terminate the current process; the caller is expected to exit after
+    /// this returns [`Cancelled`][Error::Cancelled]. Afterwards, signals that
+    /// had their default disposition terminate the process again.
+    ///
+    /// # Errors
+    /// - Errors of [`Engine::run`].
+    /// - [`Cancelled`][Error::Cancelled] if a signal was received before the
+    ///   conversion finished.
+    /// - [`IoError`][Error::IoError] if the signal handlers cannot be
+    ///   registered.
+    #[cfg(feature = "signal-hook")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "signal-hook")))]
+    pub fn run_interruptible(&self, command: &Command) -> Result<()> {
+        use signal_hook::consts::{SIGINT, SIGTERM};
+        use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+        use std::sync::{Arc, Mutex, PoisonError};
+
+        const SIGNALS: [i32; 2] = [SIGINT, SIGTERM];
+
+        /// Number of runs currently handling the signals.
+        static RUNNING: AtomicUsize = AtomicUsize::new(0);
+        /// Whether the fallback to the default action is registered.
+        static DEFAULTS: Mutex<bool> = Mutex::new(false);
+
+        /// Signal handlers, unregistered when dropped.
+        struct Handlers(Vec<signal_hook::SigId>);
+
+        impl Drop for Handlers {
+            fn drop(&mut self) {
+                for &id in &self.0 {
+                    signal_hook::low_level::unregister(id);
+                }
+                RUNNING.fetch_sub(1, Ordering::SeqCst);
+            }
+        }
+
+        /// Whether the signal has its default disposition.
+        #[cfg(unix)]
+        fn is_default(signal: i32) -> io::Result<bool> {
+            // Safety: the action is only queried, not changed
+            unsafe {
+                let mut action = std::mem::zeroed::<libc::sigaction>();
+                if libc::sigaction(signal, std::ptr::null(), &mut action) != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                Ok(action.sa_sigaction == libc::SIG_DFL)
+            }
+        }
+
+        #[cfg(not(unix))]
+        fn is_default(_signal: i32) -> io::Result<bool> {
+            Ok(true)
+        }
+
+        {
+            // signal-hook keeps its handler installed once the actions are
+            // unregistered, which would leave the signals ignored after
+            // running; run the default action instead outside of runs, if
+            // that was the original disposition
+            let mut defaults = DEFAULTS.lock().unwrap_or_else(PoisonError::into_inner);
+            if !*defaults {
+                for &signal in &SIGNALS {
+                    if is_default(signal)? {
+                        let action = move || {
+                            if RUNNING.load(Ordering::SeqCst) == 0 {
+                                let _ = signal_hook::low_level::emulate_default_handler(signal);
+                            }
+                        };
+                        // Safety: the action only uses async-signal-safe
+                        // operations
+                        unsafe { signal_hook::low_level::register(signal, action)? };
+                    }
+                }
+                *defaults = true;
+            }
+        }
+
+        let interrupted = Arc::new(AtomicBool::new(false));
+        RUNNING.fetch_add(1, Ordering::SeqCst);
+        let mut handlers = Handlers(Vec::new());
+        for &signal in &SIGNALS {
+            let id = signal_hook::flag::register(signal, Arc::clone(&interrupted))?;
+            handlers.0.push(id);
+        }
+        let handle = self.spawn(command);
+        while !handle.state().is_finished() {
+            if interrupted.load(Ordering::SeqCst) {
+                handle.cancel();
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        handle.wait()
+    }
+
+    /// Execute pstoedit for a prepared command.
+    fn exec(&self, command: &Command, abort: Option<&Abort>) -> Result<Vec<Warning>> {
+        let scratch = if self.scratch_dir {
+            Some(ScratchDir::new(command)?)
+        } else {
+            None
+        };
+        let command = scratch
+            .as_ref()
+            .map_or(command, |(_, redirected)| redirected);
+        let mut process = process::Command::new(&self.program);
+        if let Some((scratch, _)) = &scratch {
+            process.current_dir(scratch.path());
+        }
+        if let Some(gs) = command.gs_path() {
+            process.arg("-gs").arg(cstr_os(gs)?);
+        }
+        for arg in command.argv().iter().skip(1) {
+            process.arg(cstr_os(arg)?);
+        }
+        let temp_dir = command
+            .temp_dir_path()
+            .map(Path::to_owned)
+            .or_else(crate::configured_temp_dir);
+        if let Some(dir) = &temp_dir {
+            for var in &crate::TEMP_VARS {
+                process.env(var, dir);
+            }
+        }
+        // Capture diagnostics to classify failures through a pipe rather than
+        // a file, which would be subject to the output size limit
+        let (mut reader, writer) = io::pipe()?;
+        process.stdin(Stdio::null()).stderr(writer);
+        let capture = std::thread::spawn(move || {
+            let mut stderr = Vec::new();
+            let _ = reader.read_to_end(&mut stderr);
+            stderr
+        });
+        let output = command.output_path().and_then(crate::cstr_path);
+        #[cfg(all(feature = "sandbox", target_os = "linux"))]
+        let _private_temp_dir = match &self.sandbox {
+            Some(sandbox) => {
+                // Sandboxed processes get a directory of their own rather
+                // than access to the shared temporary directory
+                let private = match temp_dir {
+                    Some(_) => None,
+                    None => Some(crate::temp::TempDir::new()?),
+                };
+                let temp_dir = match &private {
+                    Some(private) => {
+                        for var in &crate::TEMP_VARS {
+                            process.env(var, private.path());
+                        }
+                        private.path()
+                    }
+                    None => temp_dir.as_deref().unwrap(),
+                };
+                let input = command.input_path().and_then(crate::cstr_path);
+                sandbox.apply(&mut process, input, output, temp_dir)?;
+                private
+            }
+            None => None,
+        };
+        let result = crate::process::run(&mut process, &self.limits, output, abort);
+        // The command holds on to the write end of the pipe
+        drop(process);
+        let stderr = capture.join().unwrap_or_default();
+        let _ = io::stderr().write_all(&stderr);
+        let status = match result {
+            Err(Error::Cancelled) => {
+                // Remove partial output, unless it is discarded with the
+                // scratch directory
+                if let (Some(output), None) = (output, &scratch) {
+                    let _ = fs::remove_file(output);
+                }
+                return Err(Error::Cancelled);
+            }
+            result => result?,
+        };
+        if !status.success() {
+            return Err(match GsErrorKind::from_output(&stderr) {
+                Some(kind) => Error::InterpreterError(kind),
+                None => Error::SubprocessError(status),
+            });
+        }
+        if let Some((scratch, _)) = &scratch {
+            scratch.collect()?;
+        }
+        Ok(Warning::from_output(&stderr))
+    }
+}
+
+impl Default for Subprocess {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Engine for Subprocess {
+    fn run(&self, command: &Command) -> Result<()> {
+        command.run_via(&|command| self.exec(command, None))
+    }
+}
+
+/// Interpret C string as OS string.
+///
+/// Strings are only required to be valid UTF-8 on platforms other than Unix.
+fn cstr_os(s: &CStr) -> Result<&OsStr> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        Ok(OsStr::from_bytes(s.to_bytes()))
+    }
+    #[cfg(not(unix))]
+    {
+        Ok(OsStr::new(s.to_str()?))
+    }
+}
+
+/// Limits on the resources used by a child process and its descendants.
+///
+/// Limits are enforced using resource limits (`setrlimit`) on Unix and job
+/// objects on Windows. No limits are set by default.
+///
+/// The scheduling [`Priority`] can be lowered as well, so that bulk
+/// conversions in the background do not slow down interactive work.
+///
+/// # Examples
+/// See [module-level documentation][self].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Limits {
+    pub(crate) cpu_time: Option<Duration>,
+    pub(crate) memory: Option<u64>,
+    pub(crate) output_size: Option<u64>,
+    pub(crate) priority: Option<Priority>,
+}
+
+impl Limits {
+    /// Create limits that do not restrict anything.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limit the CPU time of every process.
+    ///
+    /// On Unix, the limit is rounded up to whole seconds.
+    pub fn cpu_time(mut self, limit: Duration) -> Self {
+        self.cpu_time = Some(limit);
+        self
+    }
+
+    /// Limit the memory of every process in bytes.
+    ///
+    /// On Unix, this limits the address space, which is larger than the
+    /// memory actually used.
+    pub fn memory(mut self, bytes: u64) -> Self {
+        self.memory = Some(bytes);
+        self
+    }
+
+    /// Limit the size of every file written in bytes.
+    ///
+    /// This includes temporary files. Only supported on Unix.
+    pub fn output_size(mut self, bytes: u64) -> Self {
+        self.output_size = Some(bytes);
+        self
+    }
+
+    /// Set the scheduling priority of every process.
+    ///
+    /// By default, processes inherit the priority of the current process. On
+    /// Unix, the niceness of processes is only ever raised, so a process
+    /// that is already nicer than the priority keeps its niceness.
+    pub fn priority(mut self, priority: Priority) -> Self {
+        self.priority = Some(priority);
+        self
+    }
+}
+
+/// Scheduling priority of child processes, see [`Limits::priority`].
+///
+/// # Examples
+/// ```no_run
+/// use pstoedit::engine::{Engine, Limits, Priority, Subprocess};
+/// use pstoedit::Command;
+///
+/// let engine = Subprocess::new().limits(Limits::new().priority(Priority::Idle));
+/// let mut cmd = Command::new();
+/// cmd.format("svg")?.input("input.ps")?.output("output.svg")?;
+/// engine.run(&cmd)?;
+/// # Ok::<(), pstoedit::Error>(())
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Priority {
+    /// Lower priority than normal processes: niceness 10 on Unix,
+    /// `BELOW_NORMAL_PRIORITY_CLASS` on Windows.
+    BelowNormal,
+    /// Run only when the system is otherwise idle: niceness 19 on Unix,
+    /// `IDLE_PRIORITY_CLASS` on Windows.
+    Idle,
+}
+
+impl Priority {
+    /// Niceness corresponding to the priority.
+    #[cfg(unix)]
+    pub(crate) fn niceness(self) -> libc::c_int {
+        match self {
+            Priority::BelowNormal => 10,
+            Priority::Idle => 19,
+        }
+    }
+}
+
+/// Resource of which the limit was exceeded, see [`Limits`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Resource {
+    /// CPU time.
+    CpuTime,
+    /// Memory.
+    Memory,
+    /// Size of a written file.
+    OutputSize,
+}
+
+impl std::fmt::Display for Resource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Resource::CpuTime => "CPU time",
+            Resource::Memory => "memory",
+            Resource::OutputSize => "output size",
+        })
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subprocess() {
+        let mut cmd = Command::new();
+        cmd.args_slice(&["-c", "test \"$0\" = -c"]).unwrap();
+        Subprocess::new().program("sh").run(&cmd).unwrap_err();
+        let mut cmd = Command::new();
+        cmd.args_slice(&["-c", "exit 0"]).unwrap();
+        Subprocess::new().program("sh").run(&cmd).unwrap();
+        let mut cmd = Command::new();
+        cmd.args_slice(&["-c", "exit 2"]).unwrap();
+        let result = Subprocess::new().program("sh").run(&cmd);
+        assert!(matches!(result, Err(Error::SubprocessError(_))));
+    }
+
+    #[test]
+    #[cfg(feature = "signal-hook")]
+    fn run_interruptible() {
+        let dir = crate::temp::TempDir::new().unwrap();
+        let output = dir.path().join("output.ps");
+        let mut cmd = Command::new();
+        // The format avoids looking up the driver by extension
+        let script = "echo partial > \"$2\" && sleep 10";
+        cmd.args_slice(&["-c", script, "-f", "psf"]).unwrap();
+        cmd.output(crate::path_bytes(&output).unwrap()).unwrap();
+        let thread_output = output.clone();
+        let signal = std::thread::spawn(move || {
+            while !thread_output.exists() {
+                std::thread::yield_now();
+            }
+            // Safety: the signal is handled while the command runs
+            unsafe { libc::kill(libc::getpid(), libc::SIGTERM) };
+        });
+        let engine = Subprocess::new().program("sh");
+        let result = engine.run_interruptible(&cmd);
+        signal.join().unwrap();
+        assert!(matches!(result, Err(Error::Cancelled)));
+        assert!(!output.exists());
+        for &signal in &[libc::SIGINT, libc::SIGTERM] {
+            // Safety: the child only raises the signal and exits
+            let status = unsafe {
+                match libc::fork() {
+                    0 => {
+                        libc::raise(signal);
+                        libc::_exit(0)
+                    }
+                    pid => {
+                        let mut status = 0;
+                        libc::waitpid(pid, &mut status, 0);
+                        status
+                    }
+                }
+            };
+            assert!(libc::WIFSIGNALED(status));
+            assert_eq!(libc::WTERMSIG(status), signal);
+        }
+    }
+
+    #[test]
+    fn interpreter_error() {
+        let mut cmd = Command::new();
+        let script = "echo 'Error: /undefined in fancyshow' >&2; exit 1";
+        cmd.args_slice(&["-c", script]).unwrap();
+        let result = Subprocess::new().program("sh").run(&cmd);
+        assert!(matches!(
+            result,
+            Err(Error::InterpreterError(GsErrorKind::UndefinedOperator(op))) if op == "fancyshow"
+        ));
+    }
+
+    #[test]
+    fn warnings() {
+        let mut cmd = Command::new();
+        let script = "echo 'Substituting font Courier for Foo.' >&2";
+        cmd.args_slice(&["-c", script]).unwrap();
+        let report = Subprocess::new().program("sh").run_report(&cmd).unwrap();
+        assert_eq!(
+            report.warnings(),
+            [Warning::FontSubstituted {
+                font: "Foo".to_owned(),
+                substitute: "Courier".to_owned(),
+            }]
+        );
+    }
+
+    #[test]
+    fn temp_dir() {
+        let dir = crate::temp::TempDir::new().unwrap();
+        let mut cmd = Command::new();
+        let script = "test \"$TMPDIR\" = \"$0\" && test \"$TEMP\" = \"$0\"";
+        cmd.args_slice(&["-c", script]).unwrap();
+        cmd.arg(crate::path_bytes(dir.path()).unwrap()).unwrap();
+        cmd.temp_dir(dir.path());
+        Subprocess::new().program("sh").run(&cmd).unwrap();
+    }
+
+    #[test]
+    fn priority() {
+        let mut cmd = Command::new();
+        cmd.args_slice(&["-c", "test \"$(nice)\" -ge 19"]).unwrap();
+        let limits = Limits::new().priority(Priority::Idle);
+        Subprocess::new()
+            .program("sh")
+            .limits(limits)
+            .run(&cmd)
+            .unwrap();
+    }
+
+    #[test]
+    fn subprocess_limits() {
+        let mut cmd = Command::new();
+        cmd.args_slice(&["-c", "while :; do :; done"]).unwrap();
+        let limits = Limits::new().cpu_time(Duration::from_millis(500));
+        let result = Subprocess::new().program("sh").limits(limits).run(&cmd);
+        assert!(matches!(
+            result,
+            Err(Error::LimitExceeded(Resource::CpuTime))
+        ));
+        // Diagnostics do not count towards the output size
+        let mut cmd = Command::new();
+        cmd.args_slice(&["-c", "yes | head -c 4096 >&2"]).unwrap();
+        let limits = Limits::new().output_size(1024);
+        Subprocess::new()
+            .program("sh")
+            .limits(limits)
+            .run(&cmd)
+            .unwrap();
+    }
+}
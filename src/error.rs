@@ -1,10 +1,16 @@
+use crate::engine::Resource;
+use crate::GsErrorKind;
 use std::ffi::NulError;
+use std::io;
 use std::os::raw::c_int;
+use std::path::PathBuf;
+use std::process::ExitStatus;
 use std::str::Utf8Error;
 use std::{error, fmt, result};
 
 /// Enumerations of possible errors during interaction with pstoedit.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Error {
     /// The connection to pstoedit was not initialized, i.e.
     /// [`init`][crate::init] was not called first.
@@ -15,12 +21,99 @@ pub enum Error {
     /// crate, or the incorrect feature flags were used to specify the pstoedit
     /// version, see [the top-level documentation][crate#compatibility].
     IncompatibleVersion,
+    /// Data structures returned by pstoedit do not match the layout this crate
+    /// was compiled for.
+    ///
+    /// This typically indicates that the feature flags do not match the
+    /// version of pstoedit, which was not caught by the version check, see
+    /// [the top-level documentation][crate#compatibility].
+    AbiMismatch,
     /// Internal pstoedit (or ghostscript) error.
     PstoeditError(c_int),
+    /// pstoedit reported success, but the output file is missing or empty,
+    /// see [`Command::verify_output`][crate::Command::verify_output].
+    EmptyOutput {
+        /// Path of the output file.
+        path: PathBuf,
+        /// Whether the file does not exist at all.
+        missing: bool,
+    },
     /// A UTF-8 string to be passed to pstoedit contained a nul byte.
     NulError(NulError),
     /// A string from pstoedit was invalid UTF-8.
     Utf8Error(Utf8Error),
+    /// Reading or writing a file failed.
+    IoError(io::Error),
+    /// Streamed input exceeds the limit in bytes, see
+    /// [`Pipeline::spool_limit`][crate::pipeline::Pipeline::spool_limit].
+    InputTooLarge(u64),
+    /// Input is not a PostScript, EPS, or PDF document.
+    UnsupportedInput,
+    /// Ghostscript, run directly by this crate, exited unsuccessfully.
+    GhostscriptError(ExitStatus),
+    /// Ghostscript reported an error while interpreting the input for
+    /// pstoedit.
+    InterpreterError(GsErrorKind),
+    /// The ghostscript executable, required to interpret the input, was not
+    /// found or is not executable.
+    MissingGhostscript,
+    /// pstoedit, run as a child process by
+    /// [`Subprocess`][crate::engine::Subprocess], exited unsuccessfully.
+    SubprocessError(ExitStatus),
+    /// A child process exceeded a limit set using
+    /// [`Limits`][crate::engine::Limits].
+    LimitExceeded(Resource),
+    /// The conversion was cancelled, see
+    /// [`ConversionHandle::cancel`][crate::conversion::ConversionHandle::cancel]
+    /// and `Subprocess::run_interruptible`.
+    Cancelled,
+    /// No driver with this name is available.
+    UnknownFormat(String),
+    /// Multiple drivers, given by name, use the file name extension.
+    AmbiguousExtension(String, Vec<String>),
+    /// Arguments contain conflicting options, see
+    /// [`Command::normalize`][crate::Command::normalize].
+    ConflictingArgs(String, String),
+    /// An [`OutputTemplate`][crate::OutputTemplate] could not be parsed.
+    InvalidTemplate(String),
+    /// A driver name or option of a
+    /// [`DriverSpec`][crate::formats::DriverSpec] cannot be passed to
+    /// pstoedit.
+    InvalidDriverOption(String),
+    /// A [`Color`][crate::Color] could not be parsed.
+    InvalidColor(String),
+    /// A font is not listed in a fontmap, see
+    /// [`Command::default_font_in`][crate::Command::default_font_in].
+    UnknownFont(String),
+    /// Generated output files would overwrite each other or an existing file.
+    OutputCollision(PathBuf),
+    /// The output file name is to be derived, but no input or format is set,
+    /// see [`Command::output_dir`][crate::Command::output_dir].
+    MissingOutputName,
+    /// The worker thread of a [`Queue`][crate::queue::Queue] stopped before
+    /// running a command.
+    QueueStopped,
+    /// A bitmap could not be decoded.
+    #[cfg(feature = "image")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "image")))]
+    ImageError(image::ImageError),
+    /// SVG output could not be parsed.
+    #[cfg(feature = "usvg")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "usvg")))]
+    SvgError(usvg::Error),
+    /// A [`Sandbox`][crate::engine::Sandbox] could not be set up.
+    #[cfg(all(feature = "sandbox", target_os = "linux"))]
+    #[cfg_attr(docsrs, doc(cfg(all(feature = "sandbox", target_os = "linux"))))]
+    SandboxError(landlock::RulesetError),
+    /// A [job file][crate::jobs] could not be parsed or does not describe
+    /// valid conversions.
+    #[cfg(feature = "jobs")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "jobs")))]
+    JobFileError(toml::de::Error),
+    /// A directory could not be [watched][crate::watch].
+    #[cfg(feature = "notify")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "notify")))]
+    WatchError(notify::Error),
 }
 
 impl error::Error for Error {
@@ -28,9 +121,40 @@ impl error::Error for Error {
         match self {
             Error::NotInitialized => None,
             Error::IncompatibleVersion => None,
+            Error::AbiMismatch => None,
             Error::PstoeditError(_) => None,
+            Error::EmptyOutput { .. } => None,
             Error::NulError(err) => Some(err),
             Error::Utf8Error(err) => Some(err),
+            Error::IoError(err) => Some(err),
+            Error::InputTooLarge(_) => None,
+            Error::UnsupportedInput => None,
+            Error::GhostscriptError(_) => None,
+            Error::InterpreterError(_) => None,
+            Error::MissingGhostscript => None,
+            Error::SubprocessError(_) => None,
+            Error::LimitExceeded(_) => None,
+            Error::Cancelled => None,
+            Error::UnknownFormat(_) => None,
+            Error::AmbiguousExtension(..) => None,
+            Error::ConflictingArgs(..) => None,
+            Error::InvalidTemplate(_) => None,
+            Error::InvalidDriverOption(_) => None,
+            Error::InvalidColor(_) => None,
+            Error::UnknownFont(_) => None,
+            Error::OutputCollision(_) => None,
+            Error::MissingOutputName => None,
+            Error::QueueStopped => None,
+            #[cfg(feature = "image")]
+            Error::ImageError(err) => Some(err),
+            #[cfg(feature = "usvg")]
+            Error::SvgError(err) => Some(err),
+            #[cfg(all(feature = "sandbox", target_os = "linux"))]
+            Error::SandboxError(err) => Some(err),
+            #[cfg(feature = "jobs")]
+            Error::JobFileError(err) => Some(err),
+            #[cfg(feature = "notify")]
+            Error::WatchError(err) => Some(err),
         }
     }
 }
@@ -40,9 +164,61 @@ impl fmt::Display for Error {
         match self {
             Error::NotInitialized => write!(f, "pstoedit was not initialized"),
             Error::IncompatibleVersion => write!(f, "incompatible pstoedit version"),
+            Error::AbiMismatch => write!(f, "pstoedit data layout does not match compiled crate"),
             Error::PstoeditError(err) => write!(f, "internal pstoedit error code {}", err),
+            Error::EmptyOutput { path, missing } => {
+                let state = if *missing { "missing" } else { "empty" };
+                write!(f, "output file {} is {}", path.display(), state)
+            }
             Error::NulError(err) => err.fmt(f),
             Error::Utf8Error(err) => err.fmt(f),
+            Error::IoError(err) => err.fmt(f),
+            Error::InputTooLarge(limit) => write!(f, "input exceeds limit of {} bytes", limit),
+            Error::UnsupportedInput => write!(f, "input is not PostScript, EPS, or PDF"),
+            Error::GhostscriptError(status) => write!(f, "ghostscript failed: {}", status),
+            Error::InterpreterError(kind) => write!(f, "ghostscript error: {}", kind),
+            Error::MissingGhostscript => write!(f, "ghostscript executable not found"),
+            Error::SubprocessError(status) => write!(f, "pstoedit failed: {}", status),
+            Error::LimitExceeded(resource) => write!(f, "{} limit exceeded", resource),
+            Error::Cancelled => write!(f, "conversion was cancelled"),
+            Error::UnknownFormat(format) => write!(f, "unknown format `{}`", format),
+            Error::AmbiguousExtension(extension, drivers) => write!(
+                f,
+                "extension `{}` is used by drivers {}",
+                extension,
+                drivers.join(", ")
+            ),
+            Error::ConflictingArgs(first, second) => {
+                write!(f, "conflicting arguments `{}` and `{}`", first, second)
+            }
+            Error::InvalidTemplate(template) => {
+                write!(f, "invalid output template `{}`", template)
+            }
+            Error::InvalidDriverOption(option) => {
+                write!(f, "invalid driver name or option `{}`", option)
+            }
+            Error::InvalidColor(color) => write!(f, "invalid color `{}`", color),
+            Error::UnknownFont(font) => write!(f, "font `{}` not found in fontmap", font),
+            Error::OutputCollision(path) => {
+                write!(f, "output file {} collides", path.display())
+            }
+            Error::MissingOutputName => {
+                write!(
+                    f,
+                    "input and format are required to derive output file name"
+                )
+            }
+            Error::QueueStopped => write!(f, "queue stopped before running command"),
+            #[cfg(feature = "image")]
+            Error::ImageError(err) => err.fmt(f),
+            #[cfg(feature = "usvg")]
+            Error::SvgError(err) => err.fmt(f),
+            #[cfg(all(feature = "sandbox", target_os = "linux"))]
+            Error::SandboxError(err) => err.fmt(f),
+            #[cfg(feature = "jobs")]
+            Error::JobFileError(err) => write!(f, "invalid job file: {}", err),
+            #[cfg(feature = "notify")]
+            Error::WatchError(err) => err.fmt(f),
         }
     }
 }
@@ -59,5 +235,46 @@ impl From<Utf8Error> for Error {
     }
 }
 
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Self::IoError(err)
+    }
+}
+
+#[cfg(feature = "image")]
+impl From<image::ImageError> for Error {
+    fn from(err: image::ImageError) -> Self {
+        Self::ImageError(err)
+    }
+}
+
+#[cfg(feature = "usvg")]
+impl From<usvg::Error> for Error {
+    fn from(err: usvg::Error) -> Self {
+        Self::SvgError(err)
+    }
+}
+
 /// Type of the result returned by many methods.
 pub type Result<T> = result::Result<T, Error>;
+
+#[cfg(all(feature = "sandbox", target_os = "linux"))]
+impl From<landlock::RulesetError> for Error {
+    fn from(err: landlock::RulesetError) -> Self {
+        Self::SandboxError(err)
+    }
+}
+
+#[cfg(feature = "jobs")]
+impl From<toml::de::Error> for Error {
+    fn from(err: toml::de::Error) -> Self {
+        Self::JobFileError(err)
+    }
+}
+
+#[cfg(feature = "notify")]
+impl From<notify::Error> for Error {
+    fn from(err: notify::Error) -> Self {
+        Self::WatchError(err)
+    }
+}
@@ -0,0 +1,186 @@
+//! Typed helpers for the options of specific drivers.
+//!
+//! Driver-specific options are passed to pstoedit as part of the format, as in
+//! `-f "fig:-metric -depth 8"`. The types in the submodules build such formats
+//! in a typed way, and can be passed to [`Command::format`] directly. Formats
+//! of other drivers can be built using [`DriverSpec`].
+//!
+//! # Examples
+//! ```no_run
+//! use pstoedit::formats::svg;
+//! use pstoedit::{Command, DriverInfo};
+//!
+//! pstoedit::init()?;
+//! let svg = svg::best_available(&DriverInfo::get()?).expect("no SVG driver");
+//! Command::new()
+//!     .format(svg)?
+//!     .input("input.ps")?
+//!     .output("output.svg")?
+//!     .run()?;
+//! # Ok::<(), pstoedit::Error>(())
+//! ```
+//!
+//! [`Command::format`]: crate::Command::format
+
+pub mod cgm;
+pub mod emf;
+pub mod fig;
+pub mod gnuplot;
+pub mod hpgl;
+pub mod mpost;
+#[cfg(feature = "pstoedit_4_00")]
+#[cfg_attr(docsrs, doc(cfg(feature = "pstoedit_4_00")))]
+pub mod pptx;
+pub mod psf;
+pub mod svg;
+pub mod text;
+
+use crate::{Error, Result};
+use std::fmt;
+
+/// Driver name with driver-specific options, formatted as the argument of
+/// `-f`.
+///
+/// This builds formats for drivers without typed helper, quoting options that
+/// are empty or contain whitespace, as pstoedit splits the options at
+/// whitespace outside double quotes. Pass it to
+/// [`Command::format_with_options`][crate::Command::format_with_options],
+/// which also rejects options that cannot be quoted.
+///
+/// # Examples
+/// ```
+/// use pstoedit::formats::DriverSpec;
+///
+/// let mut spec = DriverSpec::new("svg");
+/// spec.flag("-nfw").option("-title", "Annual report");
+/// assert_eq!(spec.to_string(), r#"svg:-nfw -title "Annual report""#);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DriverSpec {
+    driver: String,
+    options: Vec<String>,
+}
+
+impl DriverSpec {
+    /// Create specification of driver without options.
+    pub fn new<S>(driver: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            driver: driver.into(),
+            options: Vec::new(),
+        }
+    }
+
+    /// Add flag followed by its value.
+    pub fn option<V>(&mut self, flag: &str, value: V) -> &mut Self
+    where
+        V: fmt::Display,
+    {
+        self.options.push(flag.into());
+        self.options.push(value.to_string());
+        self
+    }
+
+    /// Add flag without value.
+    pub fn flag(&mut self, flag: &str) -> &mut Self {
+        self.options.push(flag.into());
+        self
+    }
+
+    /// Add flag if `enable` is true.
+    pub fn flag_if(&mut self, flag: &str, enable: bool) -> &mut Self {
+        if enable {
+            self.flag(flag);
+        }
+        self
+    }
+
+    /// Check that the driver name and options can be passed to pstoedit.
+    ///
+    /// # Errors
+    /// [`InvalidDriverOption`][Error::InvalidDriverOption] if the driver name
+    /// is empty or contains a colon or whitespace, or an option contains a
+    /// double quote.
+    pub(crate) fn check(&self) -> Result<()> {
+        let invalid_driver = |c: char| c == ':' || c.is_whitespace();
+        if self.driver.is_empty() || self.driver.contains(invalid_driver) {
+            return Err(Error::InvalidDriverOption(self.driver.clone()));
+        }
+        match self.options.iter().find(|option| option.contains('"')) {
+            Some(option) => Err(Error::InvalidDriverOption(option.clone())),
+            None => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for DriverSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.driver)?;
+        for (i, option) in self.options.iter().enumerate() {
+            f.write_str(if i == 0 { ":" } else { " " })?;
+            if option.is_empty() || option.contains(char::is_whitespace) {
+                write!(f, "\"{}\"", option)?;
+            } else {
+                f.write_str(option)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Implement [`Display`][fmt::Display] and conversion to the argument of `-f`
+/// for a type with a `spec` method returning a [`DriverSpec`].
+macro_rules! driver_format {
+    ($ty:ty) => {
+        impl std::fmt::Display for $ty {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                std::fmt::Display::fmt(&self.spec(), f)
+            }
+        }
+
+        impl From<$ty> for Vec<u8> {
+            fn from(format: $ty) -> Self {
+                format.to_string().into()
+            }
+        }
+
+        impl From<&$ty> for Vec<u8> {
+            fn from(format: &$ty) -> Self {
+                format.to_string().into()
+            }
+        }
+    };
+}
+
+use driver_format;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn driver_spec() {
+        assert_eq!(DriverSpec::new("svg").to_string(), "svg");
+        let mut spec = DriverSpec::new("fig");
+        spec.flag_if("-metric", true)
+            .flag_if("-unused", false)
+            .option("-depth", 8);
+        assert_eq!(spec.to_string(), "fig:-metric -depth 8");
+    }
+
+    #[test]
+    fn driver_spec_quoting() {
+        let mut spec = DriverSpec::new("svg");
+        spec.option("-title", "a\tb").option("-empty", "");
+        assert_eq!(spec.to_string(), "svg:-title \"a\tb\" -empty \"\"");
+        spec.check().unwrap();
+        spec.option("-title", "\"quoted\"");
+        assert!(matches!(spec.check(), Err(Error::InvalidDriverOption(_))));
+        for driver in ["", "svg:", "s vg"].iter() {
+            let result = DriverSpec::new(*driver).check();
+            assert!(matches!(result, Err(Error::InvalidDriverOption(_))));
+        }
+    }
+}
@@ -0,0 +1,108 @@
+//! CGM drivers.
+//!
+//! Computer Graphics Metafiles come in a compact binary encoding and a
+//! clear-text encoding, written by the `cgm` and `cgmt` drivers respectively.
+//! Installations without these drivers may offer binary output through the
+//! `plot-cgm` driver based on GNU libplot; use [`best_available`] to pick a
+//! driver that is present.
+//!
+//! # Examples
+//! ```no_run
+//! use pstoedit::formats::cgm::{self, Encoding};
+//! use pstoedit::{Command, DriverInfo};
+//!
+//! pstoedit::init()?;
+//! let cgm = cgm::best_available(&DriverInfo::get()?, Encoding::ClearText)
+//!     .expect("no clear-text CGM driver");
+//! Command::new()
+//!     .format(cgm)?
+//!     .input("drawing.ps")?
+//!     .output("drawing.cgm")?
+//!     .run()?;
+//! # Ok::<(), pstoedit::Error>(())
+//! ```
+
+use super::{driver_format, DriverSpec};
+use crate::DriverInfo;
+
+/// Encoding of a CGM file.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    /// Binary encoding, supported by most applications.
+    Binary,
+    /// Human-readable clear-text encoding.
+    ClearText,
+}
+
+impl Encoding {
+    /// Drivers writing this encoding in order of preference.
+    fn drivers(self) -> &'static [&'static str] {
+        match self {
+            Encoding::Binary => &["cgm", "plot-cgm"],
+            Encoding::ClearText => &["cgmt"],
+        }
+    }
+}
+
+/// CGM output through a specific driver.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cgm {
+    driver: &'static str,
+}
+
+impl Cgm {
+    /// Create CGM output with the given encoding using the native driver.
+    pub fn new(encoding: Encoding) -> Self {
+        Self {
+            driver: encoding.drivers()[0],
+        }
+    }
+
+    /// Name of the selected driver.
+    pub fn driver(&self) -> &str {
+        self.driver
+    }
+
+    fn spec(&self) -> DriverSpec {
+        DriverSpec::new(self.driver)
+    }
+}
+
+driver_format!(Cgm);
+
+/// Select a driver present in `info` writing the given encoding.
+///
+/// Returns `None` if no such driver is available.
+pub fn best_available(info: &DriverInfo, encoding: Encoding) -> Option<Cgm> {
+    let names: Vec<_> = info
+        .iter()
+        .filter_map(|driver| driver.symbolic_name().ok())
+        .collect();
+    encoding
+        .drivers()
+        .iter()
+        .find(|driver| names.contains(driver))
+        .map(|&driver| Cgm { driver })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format() {
+        assert_eq!(Cgm::new(Encoding::Binary).to_string(), "cgm");
+        assert_eq!(Cgm::new(Encoding::ClearText).to_string(), "cgmt");
+    }
+
+    #[test]
+    fn available() {
+        crate::init().unwrap();
+        let info = DriverInfo::get().unwrap();
+        for &encoding in &[Encoding::Binary, Encoding::ClearText] {
+            if let Some(cgm) = best_available(&info, encoding) {
+                assert!(encoding.drivers().contains(&cgm.driver()));
+            }
+        }
+    }
+}
@@ -0,0 +1,139 @@
+//! EMF and WMF drivers.
+//!
+//! The `emf` and `wmf` drivers write Windows metafiles, e.g. for embedding in
+//! Office documents. They are only available when pstoedit runs on Windows.
+//!
+//! # Examples
+//! ```no_run
+//! use pstoedit::formats::emf::Emf;
+//! use pstoedit::Command;
+//!
+//! pstoedit::init()?;
+//! Command::new()
+//!     .format(Emf::new().map_to_arial(true).openoffice(true))?
+//!     .input("input.ps")?
+//!     .output("output.emf")?
+//!     .run()?;
+//! # Ok::<(), pstoedit::Error>(())
+//! ```
+
+use super::{driver_format, DriverSpec};
+
+/// EMF or WMF output with driver-specific options.
+///
+/// All options are disabled by default, except for
+/// [`font_widths`][Emf::font_widths].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Emf {
+    driver: &'static str,
+    map_to_arial: bool,
+    emulate_narrow_fonts: bool,
+    font_widths: bool,
+    draw_bounding_box: bool,
+    windows_bounding_box: bool,
+    prune_line_ends: bool,
+    openoffice: bool,
+}
+
+impl Emf {
+    /// Create EMF output.
+    pub fn new() -> Self {
+        Self {
+            driver: "emf",
+            map_to_arial: false,
+            emulate_narrow_fonts: false,
+            font_widths: true,
+            draw_bounding_box: false,
+            windows_bounding_box: false,
+            prune_line_ends: false,
+            openoffice: false,
+        }
+    }
+
+    /// Create WMF output.
+    pub fn wmf() -> Self {
+        Self {
+            driver: "wmf",
+            ..Self::new()
+        }
+    }
+
+    /// Map fonts that are not available to Arial instead of the default
+    /// font.
+    pub fn map_to_arial(mut self, enable: bool) -> Self {
+        self.map_to_arial = enable;
+        self
+    }
+
+    /// Emulate narrow fonts by reducing the font width.
+    pub fn emulate_narrow_fonts(mut self, enable: bool) -> Self {
+        self.emulate_narrow_fonts = enable;
+        self
+    }
+
+    /// Write font widths. Disabling this helps programs that do not support
+    /// them, but newer versions of Windows reject such files containing text.
+    pub fn font_widths(mut self, enable: bool) -> Self {
+        self.font_widths = enable;
+        self
+    }
+
+    /// Draw the bounding box of the page.
+    pub fn draw_bounding_box(mut self, enable: bool) -> Self {
+        self.draw_bounding_box = enable;
+        self
+    }
+
+    /// Let the Windows API calculate the bounding box.
+    pub fn windows_bounding_box(mut self, enable: bool) -> Self {
+        self.windows_bounding_box = enable;
+        self
+    }
+
+    /// Remove line ends that extend beyond the path.
+    pub fn prune_line_ends(mut self, enable: bool) -> Self {
+        self.prune_line_ends = enable;
+        self
+    }
+
+    /// Write files compatible with OpenOffice and LibreOffice.
+    pub fn openoffice(mut self, enable: bool) -> Self {
+        self.openoffice = enable;
+        self
+    }
+
+    fn spec(&self) -> DriverSpec {
+        let mut spec = DriverSpec::new(self.driver);
+        spec.flag_if("-m", self.map_to_arial)
+            .flag_if("-nf", self.emulate_narrow_fonts)
+            .flag_if("-nfw", !self.font_widths)
+            .flag_if("-drawbb", self.draw_bounding_box)
+            .flag_if("-winbb", self.windows_bounding_box)
+            .flag_if("-p", self.prune_line_ends)
+            .flag_if("-OO", self.openoffice);
+        spec
+    }
+}
+
+impl Default for Emf {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+driver_format!(Emf);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format() {
+        assert_eq!(Emf::new().to_string(), "emf");
+        let wmf = Emf::wmf()
+            .map_to_arial(true)
+            .font_widths(false)
+            .openoffice(true);
+        assert_eq!(wmf.to_string(), "wmf:-m -nfw -OO");
+    }
+}
@@ -0,0 +1,88 @@
+//! Xfig driver.
+//!
+//! The `fig` driver writes files for the Xfig drawing program. Objects are
+//! assigned decreasing depths in drawing order, starting at the start depth,
+//! so later objects are drawn on top.
+//!
+//! # Examples
+//! ```no_run
+//! use pstoedit::formats::fig::Fig;
+//! use pstoedit::Command;
+//!
+//! pstoedit::init()?;
+//! Command::new()
+//!     .format(Fig::new().metric(true).start_depth(500))?
+//!     .input("drawing.ps")?
+//!     .output("drawing.fig")?
+//!     .run()?;
+//! # Ok::<(), pstoedit::Error>(())
+//! ```
+
+use super::{driver_format, DriverSpec};
+
+/// Xfig output with driver-specific options.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Fig {
+    start_depth: Option<u32>,
+    metric: bool,
+    correct_font_size: bool,
+    page_depth: Option<f64>,
+}
+
+impl Fig {
+    /// Create Xfig output with the default options of the driver.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the depth of the first object, 999 by default.
+    pub fn start_depth(mut self, depth: u32) -> Self {
+        self.start_depth = Some(depth);
+        self
+    }
+
+    /// Use centimeters instead of inches as unit.
+    pub fn metric(mut self, enable: bool) -> Self {
+        self.metric = enable;
+        self
+    }
+
+    /// Write font sizes as is, instead of scaling them for Xfig.
+    pub fn correct_font_size(mut self, enable: bool) -> Self {
+        self.correct_font_size = enable;
+        self
+    }
+
+    /// Set the height of the page in inches, 11 by default.
+    pub fn page_depth(mut self, inches: f64) -> Self {
+        self.page_depth = Some(inches);
+        self
+    }
+
+    fn spec(&self) -> DriverSpec {
+        let mut spec = DriverSpec::new("fig");
+        if let Some(depth) = self.start_depth {
+            spec.option("-startdepth", depth);
+        }
+        spec.flag_if("-metric", self.metric)
+            .flag_if("-use_correct_font_size", self.correct_font_size);
+        if let Some(inches) = self.page_depth {
+            spec.option("-depth", inches);
+        }
+        spec
+    }
+}
+
+driver_format!(Fig);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format() {
+        assert_eq!(Fig::new().to_string(), "fig");
+        let fig = Fig::new().start_depth(100).metric(true).page_depth(8.5);
+        assert_eq!(fig.to_string(), "fig:-startdepth 100 -metric -depth 8.5");
+    }
+}
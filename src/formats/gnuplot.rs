@@ -0,0 +1,43 @@
+//! gnuplot driver.
+//!
+//! The `gnuplot` driver writes the paths of a document as data for gnuplot,
+//! with curves approximated by line segments and text omitted. The driver has
+//! no driver-specific options, so this only provides a typed name.
+//!
+//! # Examples
+//! ```no_run
+//! use pstoedit::formats::gnuplot::Gnuplot;
+//! use pstoedit::Command;
+//!
+//! pstoedit::init()?;
+//! Command::new()
+//!     .format(Gnuplot)?
+//!     .input("plot.ps")?
+//!     .output("plot.gnuplot")?
+//!     .run()?;
+//! # Ok::<(), pstoedit::Error>(())
+//! ```
+
+use super::{driver_format, DriverSpec};
+
+/// gnuplot output.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Gnuplot;
+
+impl Gnuplot {
+    fn spec(&self) -> DriverSpec {
+        DriverSpec::new("gnuplot")
+    }
+}
+
+driver_format!(Gnuplot);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format() {
+        assert_eq!(Vec::from(Gnuplot), b"gnuplot");
+    }
+}
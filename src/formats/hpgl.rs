@@ -0,0 +1,134 @@
+//! HP-GL driver.
+//!
+//! The `hpgl` driver writes HP-GL or HP-GL/2 for plotters and cutters. Colors
+//! are mapped to pen numbers, either on the fly or using the pen colors listed
+//! in the `drvhpgl.pencolors` file in the data directory of pstoedit. Plotter
+//! units are fixed by the format, so use the `-scale` option of pstoedit
+//! itself to scale the output.
+//!
+//! # Examples
+//! ```no_run
+//! use pstoedit::formats::hpgl::Hpgl;
+//! use pstoedit::Command;
+//!
+//! pstoedit::init()?;
+//! Command::new()
+//!     .format(Hpgl::new().pen_plotter(true).pen_colors(8))?
+//!     .option("-scale", 0.5)?
+//!     .input("drawing.ps")?
+//!     .output("drawing.hpgl")?
+//!     .run()?;
+//! # Ok::<(), pstoedit::Error>(())
+//! ```
+
+use super::{driver_format, DriverSpec};
+
+/// Rotation of the output.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Rotation {
+    /// Rotate by 90 degrees.
+    Deg90,
+    /// Rotate by 180 degrees.
+    Deg180,
+    /// Rotate by 270 degrees.
+    Deg270,
+}
+
+/// HP-GL output with driver-specific options.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Hpgl {
+    pen_plotter: bool,
+    pen_colors: Option<u32>,
+    pen_colors_from_file: bool,
+    fill_type: Option<String>,
+    hpgl2: bool,
+    rotation: Option<Rotation>,
+}
+
+impl Hpgl {
+    /// Create HP-GL output with the default options of the driver.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Target a pen plotter, which does not support specific line widths.
+    pub fn pen_plotter(mut self, enable: bool) -> Self {
+        self.pen_plotter = enable;
+        self
+    }
+
+    /// Set the maximum number of pens that colors are mapped to.
+    pub fn pen_colors(mut self, pens: u32) -> Self {
+        self.pen_colors = Some(pens);
+        self
+    }
+
+    /// Map colors to pens using the `drvhpgl.pencolors` file.
+    pub fn pen_colors_from_file(mut self, enable: bool) -> Self {
+        self.pen_colors_from_file = enable;
+        self
+    }
+
+    /// Set the HP-GL fill type instruction, e.g. `"FT 1"`.
+    pub fn fill_type<S>(mut self, fill_type: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.fill_type = Some(fill_type.into());
+        self
+    }
+
+    /// Write HP-GL/2 instead of HP-GL/1.
+    pub fn hpgl2(mut self, enable: bool) -> Self {
+        self.hpgl2 = enable;
+        self
+    }
+
+    /// Rotate the output.
+    pub fn rotation(mut self, rotation: Rotation) -> Self {
+        self.rotation = Some(rotation);
+        self
+    }
+
+    fn spec(&self) -> DriverSpec {
+        let mut spec = DriverSpec::new("hpgl");
+        spec.flag_if("-penplotter", self.pen_plotter);
+        if let Some(pens) = self.pen_colors {
+            spec.option("-pencolors", pens);
+        }
+        spec.flag_if("-pencolorsfromfile", self.pen_colors_from_file);
+        if let Some(fill_type) = &self.fill_type {
+            spec.option("-filltype", fill_type);
+        }
+        spec.flag_if("-hpgl2", self.hpgl2);
+        if let Some(rotation) = self.rotation {
+            spec.flag(match rotation {
+                Rotation::Deg90 => "-rot90",
+                Rotation::Deg180 => "-rot180",
+                Rotation::Deg270 => "-rot270",
+            });
+        }
+        spec
+    }
+}
+
+driver_format!(Hpgl);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format() {
+        assert_eq!(Hpgl::new().to_string(), "hpgl");
+        let hpgl = Hpgl::new()
+            .pen_plotter(true)
+            .pen_colors(4)
+            .hpgl2(true)
+            .rotation(Rotation::Deg90);
+        assert_eq!(
+            hpgl.to_string(),
+            "hpgl:-penplotter -pencolors 4 -hpgl2 -rot90"
+        );
+    }
+}
@@ -0,0 +1,44 @@
+//! MetaPost driver.
+//!
+//! The `mpost` driver writes a MetaPost source file with one figure per page,
+//! which can be processed by `mpost` and included in TeX documents. Text is
+//! written using `infont`, so the fonts must be available to TeX. The driver
+//! has no driver-specific options, so this only provides a typed name.
+//!
+//! # Examples
+//! ```no_run
+//! use pstoedit::formats::mpost::MetaPost;
+//! use pstoedit::Command;
+//!
+//! pstoedit::init()?;
+//! Command::new()
+//!     .format(MetaPost)?
+//!     .input("figure.ps")?
+//!     .output("figure.mp")?
+//!     .run()?;
+//! # Ok::<(), pstoedit::Error>(())
+//! ```
+
+use super::{driver_format, DriverSpec};
+
+/// MetaPost output.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct MetaPost;
+
+impl MetaPost {
+    fn spec(&self) -> DriverSpec {
+        DriverSpec::new("mpost")
+    }
+}
+
+driver_format!(MetaPost);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format() {
+        assert_eq!(Vec::from(MetaPost), b"mpost");
+    }
+}
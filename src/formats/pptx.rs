@@ -0,0 +1,126 @@
+//! PowerPoint driver.
+//!
+//! The `pptx` driver writes each page as a slide of a PowerPoint
+//! presentation. The slide size follows the bounding box of the pages.
+//!
+//! # Examples
+//! ```no_run
+//! use pstoedit::formats::pptx::{Colors, Fonts, Pptx};
+//! use pstoedit::Command;
+//!
+//! pstoedit::init()?;
+//! Command::new()
+//!     .format(Pptx::new().colors(Colors::Theme).fonts(Fonts::Native))?
+//!     .input("plot.ps")?
+//!     .output("plot.pptx")?
+//!     .run()?;
+//! # Ok::<(), pstoedit::Error>(())
+//! ```
+
+use super::{driver_format, DriverSpec};
+
+/// Mapping of colors to the presentation.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Colors {
+    /// Keep the colors of the input.
+    Original,
+    /// Map colors to the closest theme colors.
+    Theme,
+    /// Map colors to the closest theme colors, adjusting luminance.
+    ThemeLum,
+}
+
+/// Selection of fonts for text in the presentation.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Fonts {
+    /// Use the PostScript font names of the input.
+    UsePs,
+    /// Map PostScript fonts to similar Windows fonts.
+    Windows,
+    /// Use the native name of the font, when known.
+    Native,
+    /// Use the fonts of the theme.
+    Theme,
+}
+
+/// PowerPoint output with driver-specific options.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Pptx {
+    colors: Option<Colors>,
+    fonts: Option<Fonts>,
+    embed: Vec<String>,
+}
+
+impl Pptx {
+    /// Create PowerPoint output with the default options of the driver.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set how colors are mapped.
+    pub fn colors(mut self, colors: Colors) -> Self {
+        self.colors = Some(colors);
+        self
+    }
+
+    /// Set how fonts are selected.
+    pub fn fonts(mut self, fonts: Fonts) -> Self {
+        self.fonts = Some(fonts);
+        self
+    }
+
+    /// Embed a font, given as the path of an EOT font file.
+    pub fn embed<S>(mut self, font: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.embed.push(font.into());
+        self
+    }
+
+    fn spec(&self) -> DriverSpec {
+        let mut spec = DriverSpec::new("pptx");
+        if let Some(colors) = self.colors {
+            let colors = match colors {
+                Colors::Original => "original",
+                Colors::Theme => "theme",
+                Colors::ThemeLum => "theme-lum",
+            };
+            spec.option("-colors", colors);
+        }
+        if let Some(fonts) = self.fonts {
+            let fonts = match fonts {
+                Fonts::UsePs => "use-PS",
+                Fonts::Windows => "windows",
+                Fonts::Native => "native",
+                Fonts::Theme => "theme",
+            };
+            spec.option("-fonts", fonts);
+        }
+        if !self.embed.is_empty() {
+            spec.option("-embed", self.embed.join(","));
+        }
+        spec
+    }
+}
+
+driver_format!(Pptx);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format() {
+        assert_eq!(Pptx::new().to_string(), "pptx");
+        let pptx = Pptx::new()
+            .colors(Colors::ThemeLum)
+            .fonts(Fonts::UsePs)
+            .embed("a.eot")
+            .embed("b.eot");
+        assert_eq!(
+            pptx.to_string(),
+            "pptx:-colors theme-lum -fonts use-PS -embed a.eot,b.eot"
+        );
+    }
+}
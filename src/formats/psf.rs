@@ -0,0 +1,121 @@
+//! Flattened PostScript driver.
+//!
+//! The `psf` driver writes PostScript in which curves are approximated by
+//! line segments. Such flattened PostScript is a useful canonical form for
+//! diffing conversions and debugging drivers, as it shows the input as seen
+//! by pstoedit. [`Flattened`] converts a document to this form and back, or to
+//! another format, keeping the intermediate file available for inspection.
+//!
+//! # Examples
+//! ```no_run
+//! use pstoedit::formats::psf::Flattened;
+//!
+//! pstoedit::init()?;
+//! let flattened = Flattened::new("input.ps")?;
+//! println!("flattened to {}", flattened.path().display());
+//! flattened.convert("svg", "output.svg")?;
+//! # Ok::<(), pstoedit::Error>(())
+//! ```
+
+use super::{driver_format, DriverSpec};
+use crate::temp::TempDir;
+use crate::{Command, Result};
+use std::path::{Path, PathBuf};
+
+/// Flattened PostScript output.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Psf;
+
+impl Psf {
+    fn spec(&self) -> DriverSpec {
+        DriverSpec::new("psf")
+    }
+}
+
+driver_format!(Psf);
+
+/// Document converted to flattened PostScript in a temporary file.
+///
+/// The file is removed when this is dropped.
+#[derive(Debug)]
+pub struct Flattened {
+    path: PathBuf,
+    _dir: TempDir,
+}
+
+impl Flattened {
+    /// Convert a document to flattened PostScript.
+    ///
+    /// # Errors
+    /// - Errors from running pstoedit, see [`Command::run`].
+    /// - [`IoError`][crate::Error::IoError] if the temporary file cannot be
+    ///   created.
+    pub fn new<P>(input: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let dir = TempDir::new()?;
+        let path = dir.path().join("flattened.ps");
+        convert(input.as_ref(), Psf, &path)?;
+        Ok(Self { path, _dir: dir })
+    }
+
+    /// Path of the flattened PostScript file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Path of the flattened PostScript file, if it is valid UTF-8.
+    #[cfg(feature = "camino")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "camino")))]
+    pub fn utf8_path(&self) -> Option<&camino::Utf8Path> {
+        camino::Utf8Path::from_path(&self.path)
+    }
+
+    /// Convert the flattened PostScript to a format, e.g. [`Psf`] for the
+    /// round trip.
+    ///
+    /// # Errors
+    /// Errors from running pstoedit, see [`Command::run`].
+    pub fn convert<S, P>(&self, format: S, output: P) -> Result<()>
+    where
+        S: Into<Vec<u8>>,
+        P: AsRef<Path>,
+    {
+        convert(&self.path, format, output.as_ref())
+    }
+}
+
+/// Convert `input` to `format`, writing `output`.
+fn convert<S>(input: &Path, format: S, output: &Path) -> Result<()>
+where
+    S: Into<Vec<u8>>,
+{
+    Command::new()
+        .format(format)?
+        .input(crate::path_bytes(input)?)?
+        .output(crate::path_bytes(output)?)?
+        .run()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn round_trip() {
+        crate::init().unwrap();
+        let dir = TempDir::new().unwrap();
+        let input = dir.path().join("input.ps");
+        let output = dir.path().join("output.ps");
+        fs::write(&input, "%!PS\n0 0 moveto 10 10 lineto stroke showpage\n").unwrap();
+        let flattened = Flattened::new(&input).unwrap();
+        let path = flattened.path().to_owned();
+        assert!(path.is_file());
+        flattened.convert(Psf, &output).unwrap();
+        assert!(output.is_file());
+        drop(flattened);
+        assert!(!path.exists());
+    }
+}
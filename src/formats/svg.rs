@@ -0,0 +1,75 @@
+//! SVG drivers.
+//!
+//! Depending on the installation, pstoedit offers SVG output through the
+//! `svg` driver of the pstoedit plugins, which preserves text and gradients,
+//! or through the `plot-svg` driver based on GNU libplot. Use
+//! [`best_available`] to pick the best one that is present.
+
+use super::{driver_format, DriverSpec};
+use crate::DriverInfo;
+
+/// SVG drivers in order of decreasing quality.
+const DRIVERS: &[&str] = &["svg", "plot-svg"];
+
+/// SVG output through a specific driver.
+///
+/// # Examples
+/// See [`formats`][crate::formats#examples].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Svg {
+    driver: &'static str,
+}
+
+impl Svg {
+    /// Name of the selected driver.
+    pub fn driver(&self) -> &str {
+        self.driver
+    }
+
+    fn spec(&self) -> DriverSpec {
+        DriverSpec::new(self.driver)
+    }
+}
+
+driver_format!(Svg);
+
+/// Select the highest-quality SVG driver present in `info`.
+///
+/// Returns `None` if no SVG driver is available.
+pub fn best_available(info: &DriverInfo) -> Option<Svg> {
+    best(info.iter().filter_map(|driver| driver.symbolic_name().ok()))
+}
+
+/// Select the highest-quality SVG driver among the driver names.
+fn best<'a, I>(names: I) -> Option<Svg>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let names: Vec<_> = names.into_iter().collect();
+    DRIVERS
+        .iter()
+        .find(|driver| names.contains(driver))
+        .map(|&driver| Svg { driver })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn preference() {
+        let driver = |names: &[&'static str]| best(names.iter().copied()).map(|svg| svg.driver);
+        assert_eq!(driver(&["ps", "plot-svg", "svg"]), Some("svg"));
+        assert_eq!(driver(&["plot-svg", "fig"]), Some("plot-svg"));
+        assert_eq!(driver(&["ps"]), None);
+    }
+
+    #[test]
+    fn available() {
+        crate::init().unwrap();
+        let info = DriverInfo::get().unwrap();
+        if let Some(svg) = best_available(&info) {
+            assert_eq!(Vec::from(&svg), svg.driver().as_bytes());
+        }
+    }
+}
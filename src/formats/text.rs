@@ -0,0 +1,154 @@
+//! Text driver.
+//!
+//! The `text` driver writes the text of a document, either laid out on a grid
+//! of characters per page or as a dump of the individual text pieces. Use
+//! [`extract_text_file`] to obtain the text directly, e.g. for indexing.
+//!
+//! # Examples
+//! ```no_run
+//! use pstoedit::formats::text::{self, Encoding, Text};
+//!
+//! pstoedit::init()?;
+//! let text = text::extract_text_file("input.ps")?;
+//! let pieces = text::extract_text_file_with("input.ps", &Text::new().dump(true), Encoding::Latin1)?;
+//! # Ok::<(), pstoedit::Error>(())
+//! ```
+
+use super::{driver_format, DriverSpec};
+use crate::temp::TempDir;
+use crate::{Command, Result};
+use std::fs;
+use std::path::Path;
+
+/// Text output with driver-specific options.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Text {
+    dump: bool,
+    width: Option<u32>,
+    height: Option<u32>,
+}
+
+impl Text {
+    /// Create text output with the default options of the driver.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Dump the individual text pieces instead of laying out pages.
+    pub fn dump(mut self, enable: bool) -> Self {
+        self.dump = enable;
+        self
+    }
+
+    /// Set the width of a page in characters, 200 by default.
+    pub fn width(mut self, width: u32) -> Self {
+        self.width = Some(width);
+        self
+    }
+
+    /// Set the height of a page in lines, 120 by default.
+    pub fn height(mut self, height: u32) -> Self {
+        self.height = Some(height);
+        self
+    }
+
+    fn spec(&self) -> DriverSpec {
+        let mut spec = DriverSpec::new("text");
+        spec.flag_if("-dump", self.dump);
+        if let Some(width) = self.width {
+            spec.option("-width", width);
+        }
+        if let Some(height) = self.height {
+            spec.option("-height", height);
+        }
+        spec
+    }
+}
+
+driver_format!(Text);
+
+/// Encoding used to decode the extracted text.
+///
+/// pstoedit writes the character codes of the fonts in the document, so the
+/// encoding depends on the document.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    /// UTF-8, failing on invalid sequences.
+    Utf8,
+    /// UTF-8, replacing invalid sequences by U+FFFD.
+    Utf8Lossy,
+    /// ISO 8859-1, which never fails.
+    Latin1,
+}
+
+impl Encoding {
+    fn decode(self, data: Vec<u8>) -> Result<String> {
+        match self {
+            Encoding::Utf8 => String::from_utf8(data).map_err(|err| err.utf8_error().into()),
+            Encoding::Utf8Lossy => Ok(String::from_utf8_lossy(&data).into_owned()),
+            Encoding::Latin1 => Ok(data.into_iter().map(char::from).collect()),
+        }
+    }
+}
+
+/// Extract the text of a document, laid out per page.
+///
+/// Invalid UTF-8 is replaced, see [`extract_text_file_with`] for more
+/// control.
+///
+/// # Errors
+/// - Errors from running pstoedit, see [`Command::run`].
+/// - [`IoError`][crate::Error::IoError] if the intermediate file cannot be
+///   created or read.
+pub fn extract_text_file<P>(input: P) -> Result<String>
+where
+    P: AsRef<Path>,
+{
+    extract_text_file_with(input, &Text::new(), Encoding::Utf8Lossy)
+}
+
+/// Extract the text of a document with driver options and encoding.
+///
+/// # Errors
+/// See [`extract_text_file`], and
+/// [`Utf8Error`][crate::Error::Utf8Error] if the text is not valid UTF-8 and
+/// `encoding` is [`Utf8`][Encoding::Utf8].
+pub fn extract_text_file_with<P>(input: P, text: &Text, encoding: Encoding) -> Result<String>
+where
+    P: AsRef<Path>,
+{
+    let dir = TempDir::new()?;
+    let output = dir.path().join("output.txt");
+    Command::new()
+        .format(text)?
+        .input(crate::path_bytes(input.as_ref())?)?
+        .output(crate::path_bytes(&output)?)?
+        .run()?;
+    encoding.decode(fs::read(output)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format() {
+        assert_eq!(Text::new().to_string(), "text");
+        let text = Text::new().dump(true).width(80);
+        assert_eq!(text.to_string(), "text:-dump -width 80");
+    }
+
+    #[test]
+    fn decode() {
+        let data = || b"caf\xe9".to_vec();
+        assert!(Encoding::Utf8.decode(data()).is_err());
+        assert_eq!(Encoding::Utf8Lossy.decode(data()).unwrap(), "caf\u{fffd}");
+        assert_eq!(Encoding::Latin1.decode(data()).unwrap(), "caf\u{e9}");
+    }
+
+    #[test]
+    fn missing_input() {
+        crate::init().unwrap();
+        assert!(extract_text_file("does-not-exist.ps").is_err());
+    }
+}
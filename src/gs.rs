@@ -0,0 +1,702 @@
+//! Discovery and direct invocation of ghostscript.
+
+use crate::engine::Limits;
+use crate::{Error, Result};
+use std::cmp::Reverse;
+use std::collections::HashSet;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::{env, fmt, fs, io};
+
+/// Default ghostscript executable.
+#[cfg(windows)]
+pub(crate) const DEFAULT_GS: &str = "gswin64c";
+#[cfg(not(windows))]
+pub(crate) const DEFAULT_GS: &str = "gs";
+
+/// File names of the ghostscript executable.
+#[cfg(windows)]
+const EXECUTABLES: &[&str] = &["gswin64c.exe", "gswin32c.exe"];
+#[cfg(not(windows))]
+const EXECUTABLES: &[&str] = &["gs"];
+
+/// Directories into which ghostscript is commonly installed, besides those in
+/// `PATH`.
+#[cfg(not(windows))]
+const INSTALL_DIRS: &[&str] = &[
+    "/usr/bin",
+    "/usr/local/bin",
+    "/opt/homebrew/bin",
+    "/opt/local/bin",
+];
+
+/// Installation of ghostscript, found by [`Ghostscript::find_all`].
+///
+/// The executable can be passed to [`Command::gs`][crate::Command::gs] to
+/// make pstoedit use this installation.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Ghostscript {
+    path: PathBuf,
+    version: GsVersion,
+}
+
+impl Ghostscript {
+    /// Find all installations of ghostscript, ordered from newest to oldest
+    /// version.
+    ///
+    /// The directories in `PATH` are searched, followed by the directories
+    /// into which ghostscript is commonly installed, and on Windows the
+    /// installations recorded in the registry. The version of every
+    /// executable found is queried by running it. Executables that are found
+    /// multiple times, e.g. through symbolic links, are listed once. Among
+    /// installations with the same version, the order is that of the path of
+    /// the executable, so the result does not depend on the order of `PATH`.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use pstoedit::{Command, Ghostscript, GsVersion};
+    ///
+    /// pstoedit::init()?;
+    /// let pinned = GsVersion::new(10, 2, 1);
+    /// let gs = Ghostscript::find_all()
+    ///     .into_iter()
+    ///     .find(|gs| gs.version() == pinned)
+    ///     .ok_or(pstoedit::Error::MissingGhostscript)?;
+    /// Command::new()
+    ///     .gs(gs.path().to_str().unwrap())?
+    ///     .args_slice(&["-f", "svg", "input.ps", "output.svg"])?
+    ///     .run()?;
+    /// # Ok::<(), pstoedit::Error>(())
+    /// ```
+    pub fn find_all() -> Vec<Self> {
+        Self::collect(candidates(), query_version)
+    }
+
+    /// Find the installation of ghostscript with the newest version.
+    ///
+    /// This is the first installation returned by
+    /// [`find_all`][Ghostscript::find_all].
+    pub fn newest() -> Option<Self> {
+        Self::find_all().into_iter().next()
+    }
+
+    /// Installation of ghostscript with executable `path`, querying its
+    /// version.
+    ///
+    /// # Errors
+    /// [`MissingGhostscript`][Error::MissingGhostscript] if the executable
+    /// cannot be run or does not report a version.
+    pub fn from_path<P>(path: P) -> Result<Self>
+    where
+        P: Into<PathBuf>,
+    {
+        let path = path.into();
+        let version = query_version(&path).ok_or(Error::MissingGhostscript)?;
+        Ok(Self { path, version })
+    }
+
+    /// Path of the executable.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Version of the installation.
+    pub fn version(&self) -> GsVersion {
+        self.version
+    }
+
+    /// Installations among the candidate executables, with their versions
+    /// determined by `version`.
+    fn collect<I, F>(candidates: I, version: F) -> Vec<Self>
+    where
+        I: IntoIterator<Item = PathBuf>,
+        F: Fn(&Path) -> Option<GsVersion>,
+    {
+        let mut seen = HashSet::new();
+        let mut found: Vec<_> = candidates
+            .into_iter()
+            .filter(|path| path.is_file())
+            .filter(|path| seen.insert(fs::canonicalize(path).unwrap_or_else(|_| path.clone())))
+            .filter_map(|path| {
+                let version = version(&path)?;
+                Some(Self { path, version })
+            })
+            .collect();
+        found.sort_by(|a, b| (Reverse(a.version), &a.path).cmp(&(Reverse(b.version), &b.path)));
+        found
+    }
+}
+
+/// Version of ghostscript, e.g. 10.02.1.
+///
+/// Versions are ordered numerically, so 10.0.0 is newer than 9.56.1.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct GsVersion {
+    major: u32,
+    minor: u32,
+    patch: u32,
+}
+
+impl GsVersion {
+    /// Create version `major.minor.patch`.
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// Major version, e.g. 10 for 10.02.1.
+    pub fn major(&self) -> u32 {
+        self.major
+    }
+
+    /// Minor version, e.g. 2 for 10.02.1.
+    pub fn minor(&self) -> u32 {
+        self.minor
+    }
+
+    /// Patch version, e.g. 1 for 10.02.1, and 0 for versions without one.
+    pub fn patch(&self) -> u32 {
+        self.patch
+    }
+
+    /// Parse version as printed by `gs --version`.
+    fn parse(version: &str) -> Option<Self> {
+        let mut parts = version.trim().split('.').map(str::parse);
+        let major = parts.next()?.ok()?;
+        let minor = parts.next()?.ok()?;
+        let patch = match parts.next() {
+            Some(patch) => patch.ok()?,
+            None => 0,
+        };
+        if parts.next().is_some() {
+            return None;
+        }
+        Some(Self::new(major, minor, patch))
+    }
+}
+
+impl fmt::Display for GsVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{:02}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// Version of ghostscript executable `gs`, or `None` if it cannot be run or
+/// does not report a version.
+fn query_version(gs: &Path) -> Option<GsVersion> {
+    let output = Command::new(gs)
+        .arg("--version")
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    GsVersion::parse(std::str::from_utf8(&output.stdout).ok()?)
+}
+
+/// Paths at which a ghostscript executable may be installed.
+fn candidates() -> Vec<PathBuf> {
+    let mut dirs: Vec<_> = env::var_os("PATH")
+        .map(|path| env::split_paths(&path).collect())
+        .unwrap_or_default();
+    dirs.extend(install_dirs());
+    let candidates = dirs
+        .iter()
+        .flat_map(|dir| EXECUTABLES.iter().map(move |name| dir.join(name)));
+    #[cfg(windows)]
+    let candidates = candidates.chain(registry::installations());
+    candidates.collect()
+}
+
+#[cfg(not(windows))]
+fn install_dirs() -> Vec<PathBuf> {
+    INSTALL_DIRS.iter().map(PathBuf::from).collect()
+}
+
+/// The `bin` directories of the installations in `gs` in the program files
+/// directories, such as `C:\Program Files\gs\gs10.02.1\bin`.
+#[cfg(windows)]
+fn install_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    for var in ["ProgramFiles", "ProgramFiles(x86)", "ProgramW6432"] {
+        let root = match env::var_os(var) {
+            Some(root) => PathBuf::from(root).join("gs"),
+            None => continue,
+        };
+        if let Ok(entries) = fs::read_dir(root) {
+            dirs.extend(entries.flatten().map(|entry| entry.path().join("bin")));
+        }
+    }
+    dirs
+}
+
+/// Installations of ghostscript recorded in the registry.
+#[cfg(windows)]
+mod registry {
+    use super::EXECUTABLES;
+    use std::ffi::{OsStr, OsString};
+    use std::os::windows::ffi::{OsStrExt, OsStringExt};
+    use std::path::PathBuf;
+    use std::{iter, ptr};
+    use windows_sys::Win32::Foundation::ERROR_SUCCESS;
+    use windows_sys::Win32::System::Registry::{
+        RegCloseKey, RegEnumKeyExW, RegGetValueW, RegOpenKeyExW, HKEY, HKEY_CURRENT_USER,
+        HKEY_LOCAL_MACHINE, KEY_READ, RRF_RT_REG_SZ,
+    };
+
+    /// Registry keys under which ghostscript distributions record their
+    /// installations, with a subkey per version.
+    const KEYS: &[&str] = &[
+        r"SOFTWARE\GPL Ghostscript",
+        r"SOFTWARE\Artifex Ghostscript",
+        r"SOFTWARE\AFPL Ghostscript",
+    ];
+
+    /// Paths of the executables next to the ghostscript DLLs recorded in the
+    /// registry.
+    pub(super) fn installations() -> Vec<PathBuf> {
+        let mut installations = Vec::new();
+        for root in [HKEY_LOCAL_MACHINE, HKEY_CURRENT_USER] {
+            for key in KEYS {
+                let key = match Key::open(root, key) {
+                    Some(key) => key,
+                    None => continue,
+                };
+                for version in key.subkeys() {
+                    let dll = match key.value(&version, "GS_DLL") {
+                        Some(dll) => PathBuf::from(dll),
+                        None => continue,
+                    };
+                    if let Some(dir) = dll.parent() {
+                        installations.extend(EXECUTABLES.iter().map(|name| dir.join(name)));
+                    }
+                }
+            }
+        }
+        installations
+    }
+
+    /// Open registry key, closed when dropped.
+    struct Key(HKEY);
+
+    impl Key {
+        fn open(root: HKEY, path: &str) -> Option<Self> {
+            let path = wide(path.as_ref());
+            let mut key = ptr::null_mut();
+            // Safety: the path is nul-terminated and the key is written to a
+            // valid location
+            let result = unsafe { RegOpenKeyExW(root, path.as_ptr(), 0, KEY_READ, &mut key) };
+            (result == ERROR_SUCCESS).then(|| Self(key))
+        }
+
+        /// Names of the subkeys.
+        fn subkeys(&self) -> Vec<OsString> {
+            let mut subkeys = Vec::new();
+            // Key names are at most 255 characters
+            let mut name = [0u16; 256];
+            for index in 0.. {
+                let mut len = name.len() as u32;
+                // Safety: the buffer has the given length, and the optional
+                // arguments are null
+                let result = unsafe {
+                    RegEnumKeyExW(
+                        self.0,
+                        index,
+                        name.as_mut_ptr(),
+                        &mut len,
+                        ptr::null(),
+                        ptr::null_mut(),
+                        ptr::null_mut(),
+                        ptr::null_mut(),
+                    )
+                };
+                if result != ERROR_SUCCESS {
+                    break;
+                }
+                subkeys.push(OsString::from_wide(&name[..len as usize]));
+            }
+            subkeys
+        }
+
+        /// String value `name` of subkey `subkey`.
+        fn value(&self, subkey: &OsStr, name: &str) -> Option<OsString> {
+            let subkey = wide(subkey);
+            let name = wide(name.as_ref());
+            let mut len = 0;
+            // Safety: the strings are nul-terminated, and querying the size
+            // does not write data
+            let result = unsafe {
+                RegGetValueW(
+                    self.0,
+                    subkey.as_ptr(),
+                    name.as_ptr(),
+                    RRF_RT_REG_SZ,
+                    ptr::null_mut(),
+                    ptr::null_mut(),
+                    &mut len,
+                )
+            };
+            if result != ERROR_SUCCESS {
+                return None;
+            }
+            let mut data = vec![0u16; (len as usize).div_ceil(2)];
+            // Safety: the buffer holds `len` bytes
+            let result = unsafe {
+                RegGetValueW(
+                    self.0,
+                    subkey.as_ptr(),
+                    name.as_ptr(),
+                    RRF_RT_REG_SZ,
+                    ptr::null_mut(),
+                    data.as_mut_ptr().cast(),
+                    &mut len,
+                )
+            };
+            if result != ERROR_SUCCESS {
+                return None;
+            }
+            // The value is nul-terminated
+            let end = data.iter().position(|&c| c == 0).unwrap_or(data.len());
+            Some(OsString::from_wide(&data[..end]))
+        }
+    }
+
+    impl Drop for Key {
+        fn drop(&mut self) {
+            // Safety: the key was opened and is not used afterwards
+            unsafe { RegCloseKey(self.0) };
+        }
+    }
+
+    /// Nul-terminated UTF-16 string.
+    fn wide(s: &OsStr) -> Vec<u16> {
+        s.encode_wide().chain(iter::once(0)).collect()
+    }
+}
+
+/// Run ghostscript in batch mode with the given arguments, subject to
+/// `limits`.
+///
+/// If `gs` is `None`, the default executable is searched in `PATH`.
+///
+/// # Errors
+/// - [`MissingGhostscript`][Error::MissingGhostscript] if the executable
+///   does not exist or cannot be executed.
+/// - [`GhostscriptError`][Error::GhostscriptError] if ghostscript fails.
+pub(crate) fn run<I, S>(gs: Option<&Path>, args: I, limits: &Limits) -> Result<()>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    let gs = gs.unwrap_or_else(|| Path::new(DEFAULT_GS));
+    if is_missing(gs) {
+        return Err(Error::MissingGhostscript);
+    }
+    let mut command = Command::new(gs);
+    command
+        .args(["-q", "-dSAFER", "-dBATCH", "-dNOPAUSE"])
+        .args(args)
+        .stdin(Stdio::null());
+    let status = match crate::process::run(&mut command, limits, None, None) {
+        Err(Error::IoError(err))
+            if matches!(
+                err.kind(),
+                io::ErrorKind::NotFound | io::ErrorKind::PermissionDenied
+            ) =>
+        {
+            return Err(Error::MissingGhostscript)
+        }
+        result => result?,
+    };
+    if status.success() {
+        Ok(())
+    } else {
+        Err(Error::GhostscriptError(status))
+    }
+}
+
+/// Whether `gs` is a path to a file that does not exist or is not executable.
+///
+/// Names without a directory are searched in `PATH` by pstoedit and the
+/// operating system, so they are not considered missing here.
+pub(crate) fn is_missing(gs: &Path) -> bool {
+    if gs.parent().is_none_or(|dir| dir.as_os_str().is_empty()) {
+        return false;
+    }
+    match gs.metadata() {
+        #[cfg(unix)]
+        Ok(metadata) if metadata.is_file() => {
+            use std::os::unix::fs::PermissionsExt;
+            metadata.permissions().mode() & 0o111 == 0
+        }
+        Ok(metadata) => !metadata.is_file(),
+        Err(_) => true,
+    }
+}
+
+/// Whether the diagnostic output of a failed pstoedit run shows that
+/// ghostscript could not be started.
+///
+/// Returns `None` if the output only reports that the interpreter failed,
+/// which is also the case when ghostscript is started, but fails on the
+/// input.
+pub(crate) fn missing_from_output(output: &[u8]) -> Option<bool> {
+    let output = String::from_utf8_lossy(output).to_lowercase();
+    let mut interpreter_failed = false;
+    for line in output.lines() {
+        let names_gs = line
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .any(|word| {
+                matches!(word, "gs" | "gswin32c" | "gswin64c")
+                    || word.starts_with("ghostscript")
+                    || word.starts_with("interpreter")
+            });
+        if !names_gs {
+            continue;
+        }
+        let missing = [
+            "not found",
+            "no such file",
+            "permission denied",
+            "could not find",
+            "cannot execute",
+            "can't execute",
+        ];
+        if missing.iter().any(|message| line.contains(message)) {
+            return Some(true);
+        }
+        interpreter_failed |= line.contains("interpreter");
+    }
+    if interpreter_failed {
+        None
+    } else {
+        Some(false)
+    }
+}
+
+/// Kind of error reported by ghostscript, see
+/// [`InterpreterError`][Error::InterpreterError].
+///
+/// Errors are classified from the diagnostic output of pstoedit and
+/// ghostscript, which is not a stable interface, so classification is best
+/// effort.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum GsErrorKind {
+    /// An operator, given by name, is not defined (`/undefined`), e.g.
+    /// because the document depends on a procedure set that is missing.
+    UndefinedOperator(String),
+    /// Access to a file was denied (`/invalidfileaccess`), typically because
+    /// the document reads files outside the permitted paths.
+    InvalidFileAccess,
+    /// A font, given by name, could not be found or loaded.
+    FontNotFound(String),
+    /// The ghostscript version does not support delayed binding
+    /// (`-dDELAYBIND`), which older versions of pstoedit rely on.
+    DelayBind,
+    /// Another error, given by its ghostscript name, e.g. `typecheck`.
+    Other(String),
+}
+
+impl GsErrorKind {
+    /// Classify the first error in the output of pstoedit or ghostscript.
+    ///
+    /// Returns `None` if the output does not contain an error reported by
+    /// ghostscript.
+    ///
+    /// # Examples
+    /// ```
+    /// use pstoedit::GsErrorKind;
+    ///
+    /// let output = b"Error: /undefined in fancyshow\nOperand stack:\n";
+    /// assert_eq!(
+    ///     GsErrorKind::from_output(output),
+    ///     Some(GsErrorKind::UndefinedOperator("fancyshow".to_owned()))
+    /// );
+    /// ```
+    pub fn from_output(output: &[u8]) -> Option<Self> {
+        let output = String::from_utf8_lossy(output);
+        let lines: Vec<_> = output.lines().map(str::trim).collect();
+        let (index, name, operator) = lines.iter().enumerate().find_map(|(i, line)| {
+            let error = line.strip_prefix("Error: /")?;
+            Some(match error.split_once(" in ") {
+                Some((name, operator)) => (i, name.trim(), operator.trim()),
+                None => (i, error.trim(), ""),
+            })
+        })?;
+        // Only the lines right next to the error describe it; earlier notices
+        // may be unrelated and the command line echoes the options
+        let context = lines[index.saturating_sub(1)..lines.len().min(index + 2)]
+            .iter()
+            .filter(|line| !is_command_line(line));
+        let delay_bind = operator.contains("delaybind")
+            || operator.contains(".bindnow")
+            || context.clone().any(|line| line.contains("DELAYBIND"));
+        let font = context.clone().find_map(|line| {
+            let font = line.split("font file ").nth(1)?;
+            Some(font.trim().trim_end_matches('.'))
+        });
+        Some(match (name, font) {
+            _ if delay_bind => GsErrorKind::DelayBind,
+            ("invalidfileaccess", _) => GsErrorKind::InvalidFileAccess,
+            ("invalidfont", font) => GsErrorKind::FontNotFound(font.unwrap_or(operator).to_owned()),
+            (_, Some(font)) if operator.contains("font") => {
+                GsErrorKind::FontNotFound(font.to_owned())
+            }
+            ("undefined", _) => GsErrorKind::UndefinedOperator(operator.to_owned()),
+            (name, _) => GsErrorKind::Other(name.to_owned()),
+        })
+    }
+}
+
+/// Whether a line of output echoes the arguments of ghostscript.
+fn is_command_line(line: &str) -> bool {
+    let options = line
+        .split_whitespace()
+        .filter(|word| word.starts_with("-d") || word.starts_with("-s"))
+        .count();
+    options > 1
+}
+
+impl fmt::Display for GsErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GsErrorKind::UndefinedOperator(operator) => {
+                write!(f, "undefined operator `{}`", operator)
+            }
+            GsErrorKind::InvalidFileAccess => write!(f, "file access denied"),
+            GsErrorKind::FontNotFound(font) => write!(f, "font `{}` not found", font),
+            GsErrorKind::DelayBind => write!(f, "delayed binding is not supported"),
+            GsErrorKind::Other(name) => write!(f, "/{}", name),
+        }
+    }
+}
+
+/// Argument setting the output file of ghostscript.
+pub(crate) fn output_arg(path: &Path) -> std::ffi::OsString {
+    let mut arg = std::ffi::OsString::from("-sOutputFile=");
+    arg.push(path);
+    arg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing() {
+        assert!(!is_missing(Path::new("gs")));
+        assert!(is_missing(Path::new("/nonexistent/gs")));
+        assert!(is_missing(&std::env::temp_dir()));
+        #[cfg(unix)]
+        assert!(!is_missing(Path::new("/bin/sh")));
+        let result = run(Some(Path::new("pstoedit-rs-no-gs")), ["-v"], &Limits::new());
+        assert!(matches!(result, Err(Error::MissingGhostscript)));
+    }
+
+    #[test]
+    fn missing_output() {
+        let missing = |output: &str| missing_from_output(output.as_bytes());
+        assert_eq!(missing("sh: 1: gs: not found\n"), Some(true));
+        assert_eq!(
+            missing("Could not find Ghostscript interpreter\n"),
+            Some(true)
+        );
+        assert_eq!(
+            missing("The interpreter seems to have failed, cannot proceed !\n"),
+            None
+        );
+        assert_eq!(missing("Unsupported output format foo\n"), Some(false));
+        assert_eq!(
+            missing("Can't find (or can't open) font file Foo.\n"),
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn classify() {
+        let kind = |output: &str| GsErrorKind::from_output(output.as_bytes());
+        assert_eq!(kind("Loading font Times\n"), None);
+        assert_eq!(
+            kind("GPL Ghostscript\nError: /invalidfileaccess in --file--\n"),
+            Some(GsErrorKind::InvalidFileAccess)
+        );
+        assert_eq!(
+            kind("Can't find (or can't open) font file %rom%Resource/Font/Foo.\nError: /invalidfont in findfont\n"),
+            Some(GsErrorKind::FontNotFound("%rom%Resource/Font/Foo".to_owned()))
+        );
+        assert_eq!(
+            kind("Error: /undefined in .setdelaybind\n-dDELAYBIND is not supported\n"),
+            Some(GsErrorKind::DelayBind)
+        );
+        assert_eq!(
+            kind("Error: /typecheck in --add--\n"),
+            Some(GsErrorKind::Other("typecheck".to_owned()))
+        );
+        assert_eq!(
+            kind("gs -q -dDELAYBIND -dNOPAUSE -sDEVICE=nullpage input.ps\nError: /typecheck in --add--\n"),
+            Some(GsErrorKind::Other("typecheck".to_owned()))
+        );
+        assert_eq!(
+            kind("Substituting font Courier for Foo.\nLoading font file /usr/share/fonts/n022003l.pfb.\nPage 1\nError: /typecheck in --add--\n"),
+            Some(GsErrorKind::Other("typecheck".to_owned()))
+        );
+    }
+
+    #[test]
+    fn version() {
+        assert_eq!(
+            GsVersion::parse("10.02.1\n"),
+            Some(GsVersion::new(10, 2, 1))
+        );
+        assert_eq!(GsVersion::parse("9.27"), Some(GsVersion::new(9, 27, 0)));
+        assert_eq!(GsVersion::parse("10"), None);
+        assert_eq!(GsVersion::parse("10.02.1.0"), None);
+        assert_eq!(GsVersion::parse("GPL Ghostscript"), None);
+        assert!(GsVersion::new(10, 0, 0) > GsVersion::new(9, 56, 1));
+        assert_eq!(GsVersion::new(10, 2, 1).to_string(), "10.02.1");
+    }
+
+    #[test]
+    fn collect() {
+        let dir = crate::temp::TempDir::new().unwrap();
+        let path = |name: &str| dir.path().join(name);
+        for name in ["gs-9", "gs-10a", "gs-10b", "gs-broken"] {
+            fs::write(path(name), "").unwrap();
+        }
+        let version = |gs: &Path| match gs.file_name()?.to_str()? {
+            "gs-9" | "gs-link" => Some(GsVersion::new(9, 56, 1)),
+            "gs-10a" | "gs-10b" => Some(GsVersion::new(10, 2, 1)),
+            _ => None,
+        };
+        let mut candidates = vec![
+            path("gs-9"),
+            path("gs-missing"),
+            path("gs-10b"),
+            path("gs-broken"),
+            path("gs-10a"),
+            path("gs-10b"),
+        ];
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(path("gs-9"), path("gs-link")).unwrap();
+            candidates.push(path("gs-link"));
+        }
+        let found = Ghostscript::collect(candidates, version);
+        let paths: Vec<_> = found.iter().map(Ghostscript::path).collect();
+        assert_eq!(paths, [path("gs-10a"), path("gs-10b"), path("gs-9")]);
+        assert_eq!(found[2].version(), GsVersion::new(9, 56, 1));
+    }
+
+    #[test]
+    fn from_path() {
+        let result = Ghostscript::from_path("pstoedit-rs-no-gs");
+        assert!(matches!(result, Err(Error::MissingGhostscript)));
+    }
+}
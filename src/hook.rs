@@ -0,0 +1,49 @@
+use crate::Result;
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Hook receiving the output file after a successful run, see
+/// [`Command::post_process`][crate::Command::post_process].
+pub(crate) type PostProcess = dyn Fn(&Path) -> Result<()> + Send + Sync;
+
+/// Hook rewriting the input file to a new file before a run, see
+/// [`Command::pre_process`][crate::Command::pre_process].
+pub(crate) type PreProcess = dyn Fn(&Path, &Path) -> Result<()> + Send + Sync;
+
+/// Hooks registered on a command, run in order of registration.
+///
+/// Hooks are shared between clones of a command.
+pub(crate) struct Hooks<F: ?Sized>(Vec<Arc<F>>);
+
+impl<F: ?Sized> Hooks<F> {
+    pub(crate) fn push(&mut self, hook: Arc<F>) {
+        self.0.push(hook);
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &F> {
+        self.0.iter().map(|hook| &**hook)
+    }
+}
+
+impl<F: ?Sized> Clone for Hooks<F> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<F: ?Sized> Default for Hooks<F> {
+    fn default() -> Self {
+        Self(Vec::new())
+    }
+}
+
+impl<F: ?Sized> fmt::Debug for Hooks<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Hooks").field(&self.0.len()).finish()
+    }
+}
@@ -0,0 +1,408 @@
+//! Inspection of input documents.
+//!
+//! These functions help to validate and inspect documents before converting
+//! them. Apart from [`page_count`], they do not require pstoedit.
+
+use crate::dsc::Document;
+use crate::temp::TempDir;
+use crate::{Command, Error, Result};
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+
+/// Number of bytes inspected to detect the kind of input.
+const SNIFF_LEN: usize = 1024;
+
+/// Kind of input document accepted by pstoedit.
+///
+/// # Examples
+/// ```
+/// use pstoedit::input::InputKind;
+///
+/// assert_eq!(InputKind::detect(b"%PDF-1.7\n")?, InputKind::Pdf);
+/// assert!(InputKind::detect(b"\xff\xd8\xff\xe0").is_err());
+/// # Ok::<(), pstoedit::Error>(())
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum InputKind {
+    /// PostScript document.
+    PostScript,
+    /// Encapsulated PostScript, including DOS EPS files with binary header.
+    Eps,
+    /// PDF document.
+    Pdf,
+}
+
+impl InputKind {
+    /// Detect kind of input from the start of a document.
+    ///
+    /// Only the first kilobyte of `data` is inspected.
+    ///
+    /// # Errors
+    /// [`UnsupportedInput`][Error::UnsupportedInput] if the data is not
+    /// recognized as PostScript, EPS or PDF.
+    pub fn detect(data: &[u8]) -> Result<Self> {
+        let data = &data[..data.len().min(SNIFF_LEN)];
+        // Leading Ctrl-D is sometimes added by printer drivers
+        let start = data.strip_prefix(b"\x04").unwrap_or(data);
+        if start.starts_with(b"\xc5\xd0\xd3\xc6") {
+            Ok(Self::Eps)
+        } else if start.starts_with(b"%!") {
+            let line = start.split(|&b| b == b'\n' || b == b'\r').next();
+            match line {
+                Some(line) if line.windows(4).any(|w| w == b"EPSF") => Ok(Self::Eps),
+                _ => Ok(Self::PostScript),
+            }
+        } else if data.windows(5).any(|w| w == b"%PDF-") {
+            // PDF readers accept a header that does not start at the beginning
+            Ok(Self::Pdf)
+        } else {
+            Err(Error::UnsupportedInput)
+        }
+    }
+
+    /// Detect kind of input from the start of a file.
+    ///
+    /// # Errors
+    /// [`IoError`][Error::IoError] if the file cannot be read, or see
+    /// [`detect`][InputKind::detect].
+    pub fn detect_file<P>(path: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let mut data = Vec::with_capacity(SNIFF_LEN);
+        File::open(path)?
+            .take(SNIFF_LEN as u64)
+            .read_to_end(&mut data)?;
+        Self::detect(&data)
+    }
+}
+
+/// Memory-mapped input file.
+///
+/// Large documents can be inspected without reading them into memory, by
+/// passing a mapped file to functions taking bytes, such as
+/// [`InputKind::detect`], [`Document::parse`], and
+/// [`concat`][crate::dsc::concat].
+///
+/// # Examples
+/// ```no_run
+/// use pstoedit::dsc::Document;
+/// use pstoedit::input::{InputKind, MappedFile};
+///
+/// // Safety: the file is not modified while mapped
+/// let file = unsafe { MappedFile::open("large.ps")? };
+/// assert_eq!(InputKind::detect(&file)?, InputKind::PostScript);
+/// println!("{} pages", Document::parse(&file).page_list.len());
+/// # Ok::<(), pstoedit::Error>(())
+/// ```
+#[cfg(feature = "memmap2")]
+#[cfg_attr(docsrs, doc(cfg(feature = "memmap2")))]
+#[derive(Debug)]
+pub struct MappedFile(memmap2::Mmap);
+
+#[cfg(feature = "memmap2")]
+impl MappedFile {
+    /// Map a file into memory.
+    ///
+    /// # Safety
+    /// The file must not be modified or truncated while it is mapped, which
+    /// is undefined behavior.
+    ///
+    /// # Errors
+    /// [`IoError`][Error::IoError] if the file cannot be opened or mapped.
+    pub unsafe fn open<P>(path: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let file = File::open(path)?;
+        Ok(Self(memmap2::Mmap::map(&file)?))
+    }
+}
+
+#[cfg(feature = "memmap2")]
+impl std::ops::Deref for MappedFile {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+#[cfg(feature = "memmap2")]
+impl AsRef<[u8]> for MappedFile {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+/// Bounding box of a document in PostScript points.
+///
+/// Obtained from the `%%HiResBoundingBox` or `%%BoundingBox` comment of a
+/// PostScript or EPS document.
+///
+/// # Examples
+/// ```
+/// use pstoedit::input::BoundingBox;
+///
+/// let eps = b"%!PS-Adobe-3.0 EPSF-3.0\n%%BoundingBox: 10 20 110 70\n";
+/// let bbox = BoundingBox::from_bytes(eps).unwrap();
+/// assert_eq!((bbox.width(), bbox.height()), (100.0, 50.0));
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct BoundingBox {
+    /// Horizontal coordinate of lower left corner.
+    pub llx: f64,
+    /// Vertical coordinate of lower left corner.
+    pub lly: f64,
+    /// Horizontal coordinate of upper right corner.
+    pub urx: f64,
+    /// Vertical coordinate of upper right corner.
+    pub ury: f64,
+}
+
+impl BoundingBox {
+    /// Width of the bounding box.
+    pub fn width(&self) -> f64 {
+        self.urx - self.llx
+    }
+
+    /// Height of the bounding box.
+    pub fn height(&self) -> f64 {
+        self.ury - self.lly
+    }
+
+    /// Smallest bounding box containing both bounding boxes.
+    pub fn union(&self, other: &Self) -> Self {
+        Self {
+            llx: self.llx.min(other.llx),
+            lly: self.lly.min(other.lly),
+            urx: self.urx.max(other.urx),
+            ury: self.ury.max(other.ury),
+        }
+    }
+
+    /// Extract bounding box from document.
+    ///
+    /// The high-resolution bounding box is preferred if present. Bounding
+    /// boxes deferred to the trailer using `(atend)` are supported, while
+    /// those of embedded documents are ignored. Returns `None` if the
+    /// document has no valid bounding box comment.
+    pub fn from_bytes(data: &[u8]) -> Option<Self> {
+        Document::parse(data).bounding_box
+    }
+
+    /// Extract bounding box from file.
+    ///
+    /// See [`from_bytes`][BoundingBox::from_bytes] for details.
+    ///
+    /// # Errors
+    /// [`IoError`][Error::IoError] if the file cannot be read.
+    pub fn from_file<P>(path: P) -> Result<Option<Self>>
+    where
+        P: AsRef<Path>,
+    {
+        Ok(Self::from_bytes(&fs::read(path)?))
+    }
+
+    /// Parse the four coordinates of a bounding box comment.
+    pub(crate) fn parse(value: &[u8]) -> Option<Self> {
+        let value = std::str::from_utf8(value).ok()?;
+        let mut coords = value.split_whitespace().map(str::parse::<f64>);
+        let mut next = || coords.next()?.ok();
+        let bbox = Self {
+            llx: next()?,
+            lly: next()?,
+            urx: next()?,
+            ury: next()?,
+        };
+        match next() {
+            None => Some(bbox),
+            Some(_) => None,
+        }
+    }
+}
+
+/// Count the pages of a PostScript or PDF document.
+///
+/// The document is converted by pstoedit to flattened PostScript, whose
+/// `%%Page` comments are counted. This requires a ghostscript run, but no
+/// output driver besides the native `psf` driver.
+///
+/// # Examples
+/// ```no_run
+/// pstoedit::init()?;
+/// let pages = pstoedit::page_count("input.pdf")?;
+/// println!("input.pdf has {} pages", pages);
+/// # Ok::<(), pstoedit::Error>(())
+/// ```
+///
+/// # Errors
+/// Errors from running pstoedit, see [`Command::run`], or an
+/// [`IoError`][crate::Error::IoError] if the intermediate file cannot be
+/// created or read.
+pub fn page_count<P>(input: P) -> Result<u32>
+where
+    P: AsRef<Path>,
+{
+    let dir = TempDir::new()?;
+    let output = dir.path().join("pages.fps");
+    Command::new()
+        .format("psf")?
+        .input(crate::path_bytes(input.as_ref())?)?
+        .output(crate::path_bytes(&output)?)?
+        .run()?;
+    Ok(count_pages(&fs::read(output)?))
+}
+
+/// Number of pages declared by the DSC comments of a PostScript or EPS file,
+/// or otherwise the number of its `%%Page` comments.
+///
+/// The file is read line by line, and only up to the end of the header
+/// comments if they declare the number of pages. Returns `None` for other
+/// input, or if the file cannot be read or declares no pages.
+pub(crate) fn declared_pages(path: &Path) -> Option<u32> {
+    match InputKind::detect_file(path).ok()? {
+        InputKind::PostScript | InputKind::Eps => {}
+        InputKind::Pdf => return None,
+    }
+    let mut reader = BufReader::new(File::open(path).ok()?);
+    let mut line = Vec::new();
+    let mut header = true;
+    let mut atend = false;
+    let mut declared = None;
+    let mut listed = 0;
+    let mut depth = 0usize;
+    // The first line holds the `%!` header
+    reader.read_until(b'\n', &mut line).ok()?;
+    loop {
+        line.clear();
+        if reader.read_until(b'\n', &mut line).ok()? == 0 {
+            break;
+        }
+        let line = line.strip_suffix(b"\n").unwrap_or(&line);
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        if header && (line.starts_with(b"%%EndComments") || !line.starts_with(b"%%")) {
+            header = false;
+            if declared.is_some() {
+                break;
+            }
+        }
+        if line.starts_with(b"%%BeginDocument") {
+            depth += 1;
+        } else if line.starts_with(b"%%EndDocument") {
+            depth = depth.saturating_sub(1);
+        } else if depth > 0 {
+            continue;
+        } else if line.starts_with(b"%%Page:") {
+            listed += 1;
+        } else if let Some(value) = line.strip_prefix(b"%%Pages:") {
+            let value = String::from_utf8_lossy(value);
+            let value = value.split_whitespace().next().unwrap_or_default();
+            if header && value == "(atend)" {
+                atend = true;
+            } else if header || atend {
+                declared = value.parse().ok().or(declared);
+            }
+        }
+    }
+    declared.or_else(|| Some(listed).filter(|&pages| pages > 0))
+}
+
+/// Count `%%Page` comments in PostScript document.
+fn count_pages(data: &[u8]) -> u32 {
+    data.split(|&b| b == b'\n')
+        .filter(|line| line.starts_with(b"%%Page:"))
+        .count() as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn declared() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("input.ps");
+        fs::write(&path, "%!PS-Adobe-3.0\n%%Pages: 3\n%%EndComments\n").unwrap();
+        assert_eq!(declared_pages(&path), Some(3));
+        fs::write(
+            &path,
+            "%!PS\n%%Page: 1 1\nshowpage\n%%Page: 2 2\nshowpage\n",
+        )
+        .unwrap();
+        assert_eq!(declared_pages(&path), Some(2));
+        fs::write(
+            &path,
+            "%!PS-Adobe-3.0\n%%Pages: (atend)\n%%EndComments\n\
+             %%Page: 1 1\n%%BeginDocument: a.eps\n%%Pages: 5\n%%EndDocument\n\
+             %%Trailer\n%%Pages: 4\n",
+        )
+        .unwrap();
+        assert_eq!(declared_pages(&path), Some(4));
+        fs::write(&path, "%!PS\nshowpage\n").unwrap();
+        assert_eq!(declared_pages(&path), None);
+        fs::write(&path, "%PDF-1.7\n%%Pages: 3\n").unwrap();
+        assert_eq!(declared_pages(&path), None);
+    }
+
+    #[test]
+    fn detect() {
+        let detect = |data: &[u8]| InputKind::detect(data).ok();
+        assert_eq!(detect(b"%!PS-Adobe-3.0\n"), Some(InputKind::PostScript));
+        assert_eq!(detect(b"\x04%!PS\r\n"), Some(InputKind::PostScript));
+        assert_eq!(detect(b"%!PS-Adobe-3.0 EPSF-3.0\n"), Some(InputKind::Eps));
+        assert_eq!(detect(b"\xc5\xd0\xd3\xc6\x1e\0"), Some(InputKind::Eps));
+        assert_eq!(detect(b"%PDF-1.4\n"), Some(InputKind::Pdf));
+        assert_eq!(detect(b"\n\n%PDF-1.4\n"), Some(InputKind::Pdf));
+        assert_eq!(detect(b"\x89PNG\r\n"), None);
+        assert_eq!(detect(b""), None);
+    }
+
+    #[cfg(feature = "memmap2")]
+    #[test]
+    fn mapped_file() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("input.eps");
+        fs::write(
+            &path,
+            b"%!PS-Adobe-3.0 EPSF-3.0\n%%BoundingBox: 0 0 10 20\n",
+        )
+        .unwrap();
+        let file = unsafe { MappedFile::open(&path) }.unwrap();
+        assert_eq!(InputKind::detect(&file).unwrap(), InputKind::Eps);
+        let bbox = Document::parse(&file).bounding_box.unwrap();
+        assert_eq!((bbox.width(), bbox.height()), (10.0, 20.0));
+    }
+
+    #[test]
+    fn bounding_box() {
+        let bbox =
+            |data: &[u8]| BoundingBox::from_bytes(data).map(|b| [b.llx, b.lly, b.urx, b.ury]);
+        assert_eq!(
+            bbox(b"%%BoundingBox: 0 0 10 20\n"),
+            Some([0.0, 0.0, 10.0, 20.0])
+        );
+        assert_eq!(
+            bbox(b"%%BoundingBox: 0 0 10 20\r%%HiResBoundingBox: 0 0 9.5 19.5\r"),
+            Some([0.0, 0.0, 9.5, 19.5])
+        );
+        assert_eq!(
+            bbox(b"%%BoundingBox: (atend)\n%%Trailer\n%%BoundingBox: 1 2 3 4\n"),
+            Some([1.0, 2.0, 3.0, 4.0])
+        );
+        assert_eq!(
+            bbox(b"%%BeginDocument: a.eps\n%%BoundingBox: 1 2 3 4\n%%EndDocument\n"),
+            None
+        );
+        assert_eq!(bbox(b"%%BoundingBox: 1 2 3\n"), None);
+        assert_eq!(bbox(b"%!PS\n"), None);
+    }
+
+    #[test]
+    fn count() {
+        let doc = b"%!PS-Adobe-3.0\n%%Pages: 2\n%%Page: 1 1\nshowpage\n%%Page: 2 2\nshowpage\n";
+        assert_eq!(count_pages(doc), 2);
+        assert_eq!(count_pages(b"%!PS\nshowpage\n"), 0);
+    }
+}
@@ -0,0 +1,400 @@
+//! Conversions described in job files.
+//!
+//! A job file lists conversions in [TOML](https://toml.io), so that they can
+//! be kept under version control next to the documents they convert. Every
+//! `[[job]]` table converts one or more inputs, and the `[defaults]` table
+//! holds options shared by all jobs.
+//!
+//! ```toml
+//! [defaults]
+//! format = "svg"
+//! output_dir = "build"
+//! args = ["-dt"]
+//!
+//! # Written to build/plot.svg and build/diagram.svg
+//! [[job]]
+//! inputs = ["figures/plot.eps", "figures/diagram.eps"]
+//!
+//! [[job]]
+//! input = "logo.ps"
+//! output = "logo.pdf"
+//! format = "pdf"
+//! verify_output = true
+//! ```
+//!
+//! Every job takes an `input` or a list of `inputs`, and optionally a single
+//! `output` file. Otherwise, output files are named after the inputs and
+//! placed in `output_dir`, see [`Command::output_dir`]. The options `format`,
+//! `output_dir`, `gs`, `verify_output`, `deterministic` and `incremental`
+//! correspond to the methods of [`Command`] of the same name, and can be set
+//! on both jobs and the defaults, where the job takes precedence. The `args`
+//! of a job follow those of the defaults.
+//!
+//! Relative paths are resolved against the directory of the job file, except
+//! for a `gs` executable given by name only.
+//!
+//! # Examples
+//! ```no_run
+//! use pstoedit::jobs::JobFile;
+//!
+//! pstoedit::init()?;
+//! let jobs = JobFile::load("figures.toml")?;
+//! for outcome in jobs.run()? {
+//!     if let Err(err) = outcome.result() {
+//!         eprintln!("{}: {}", outcome.input().display(), err);
+//!     }
+//! }
+//! # Ok::<(), pstoedit::Error>(())
+//! ```
+//!
+//! The outcomes can be serialized as a report for other tools, e.g. as JSON
+//! with `serde_json`:
+//!
+//! ```no_run
+//! # use pstoedit::jobs::JobFile;
+//! # pstoedit::init()?;
+//! let outcomes = JobFile::load("figures.toml")?.run()?;
+//! let report = std::fs::File::create("report.json")?;
+//! serde_json::to_writer_pretty(report, &outcomes).expect("report is serializable");
+//! # Ok::<(), pstoedit::Error>(())
+//! ```
+
+use crate::{Command, Error, Result, RunReport};
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Serialize, Serializer};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Parsed job file, see [module-level documentation][self].
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JobFile {
+    #[serde(default)]
+    defaults: Defaults,
+    #[serde(default, rename = "job")]
+    jobs: Vec<Job>,
+    #[serde(skip)]
+    base: Option<PathBuf>,
+}
+
+/// Options shared by all jobs.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Defaults {
+    format: Option<String>,
+    #[serde(default)]
+    args: Vec<String>,
+    output_dir: Option<PathBuf>,
+    gs: Option<PathBuf>,
+    verify_output: Option<bool>,
+    deterministic: Option<bool>,
+    incremental: Option<bool>,
+}
+
+/// Conversion of one or more inputs.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Job {
+    input: Option<PathBuf>,
+    #[serde(default)]
+    inputs: Vec<PathBuf>,
+    output: Option<PathBuf>,
+    format: Option<String>,
+    #[serde(default)]
+    args: Vec<String>,
+    output_dir: Option<PathBuf>,
+    gs: Option<PathBuf>,
+    verify_output: Option<bool>,
+    deterministic: Option<bool>,
+    incremental: Option<bool>,
+}
+
+impl JobFile {
+    /// Parse a job file from TOML.
+    ///
+    /// Relative paths are resolved against the current directory when the
+    /// jobs are run.
+    ///
+    /// # Errors
+    /// [`JobFileError`][Error::JobFileError] if the job file is invalid.
+    pub fn parse(toml: &str) -> Result<Self> {
+        Ok(toml::from_str(toml)?)
+    }
+
+    /// Read and parse a job file.
+    ///
+    /// Relative paths are resolved against the directory of the file.
+    ///
+    /// # Errors
+    /// - [`IoError`][Error::IoError] if the file cannot be read.
+    /// - [`JobFileError`][Error::JobFileError] if the job file is invalid.
+    pub fn load<P>(path: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let mut jobs = Self::parse(&fs::read_to_string(path)?)?;
+        jobs.base = path.parent().map(Path::to_owned);
+        Ok(jobs)
+    }
+
+    /// Commands for all conversions, one per input, in order.
+    ///
+    /// # Errors
+    /// - [`JobFileError`][Error::JobFileError] if a job has no input, or has
+    ///   a single output file for multiple inputs.
+    /// - [`NulError`][Error::NulError] if an argument contains a nul byte.
+    pub fn commands(&self) -> Result<Vec<Command>> {
+        let mut commands = Vec::new();
+        for (i, job) in self.jobs.iter().enumerate() {
+            let inputs: Vec<_> = job.input.iter().chain(&job.inputs).collect();
+            if inputs.is_empty() {
+                return Err(invalid(format!("job {} has no input", i + 1)));
+            }
+            if job.output.is_some() && inputs.len() > 1 {
+                let msg = format!("job {} has one output for multiple inputs", i + 1);
+                return Err(invalid(msg));
+            }
+            for input in inputs {
+                commands.push(self.command(job, input)?);
+            }
+        }
+        Ok(commands)
+    }
+
+    /// Run all conversions in order, continuing after failed ones.
+    ///
+    /// # Errors
+    /// See [`commands`][JobFile::commands]. Errors of individual conversions
+    /// are reported in their [`Outcome`].
+    pub fn run(&self) -> Result<Vec<Outcome>> {
+        let commands = self.commands()?;
+        let outcomes = commands.iter().map(|command| {
+            let input = command.input_path().and_then(crate::cstr_path);
+            let output = command.resolved().ok().and_then(|resolved| {
+                let output = resolved.output_path().and_then(crate::cstr_path);
+                output.map(Path::to_owned)
+            });
+            Outcome {
+                input: input.map(Path::to_owned).unwrap_or_default(),
+                output,
+                result: command.run_report(),
+            }
+        });
+        Ok(outcomes.collect())
+    }
+
+    /// Command converting `input` according to `job` and the defaults.
+    fn command(&self, job: &Job, input: &Path) -> Result<Command> {
+        let defaults = &self.defaults;
+        let mut command = Command::new();
+        command.args(defaults.args.iter().chain(&job.args).map(String::as_str))?;
+        if let Some(format) = job.format.as_ref().or(defaults.format.as_ref()) {
+            command.format(format.as_str())?;
+        }
+        command.input(crate::path_bytes(&self.resolve(input))?)?;
+        if let Some(output) = &job.output {
+            command.output(crate::path_bytes(&self.resolve(output))?)?;
+        }
+        if let Some(dir) = job.output_dir.as_ref().or(defaults.output_dir.as_ref()) {
+            command.output_dir(self.resolve(dir));
+        }
+        if let Some(gs) = job.gs.as_ref().or(defaults.gs.as_ref()) {
+            // Names without a directory are searched in `PATH`
+            let gs = match gs.parent() {
+                Some(dir) if !dir.as_os_str().is_empty() => self.resolve(gs),
+                _ => gs.clone(),
+            };
+            command.gs(crate::path_bytes(&gs)?)?;
+        }
+        command
+            .verify_output(job.verify_output.or(defaults.verify_output) == Some(true))
+            .deterministic(job.deterministic.or(defaults.deterministic) == Some(true))
+            .incremental(job.incremental.or(defaults.incremental) == Some(true));
+        Ok(command)
+    }
+
+    /// Resolve a path from the job file against its directory.
+    fn resolve(&self, path: &Path) -> PathBuf {
+        match &self.base {
+            Some(base) => base.join(path),
+            None => path.to_owned(),
+        }
+    }
+}
+
+/// Outcome of a single conversion of a [`JobFile`].
+///
+/// Outcomes serialize to a structure with the fields
+/// - `input`: path of the input file.
+/// - `output`: path of the output file, or none if unknown.
+/// - `elapsed`: seconds spent in pstoedit, or none if the conversion failed.
+/// - `output_size`: size of the output file in bytes, or none if the
+///   conversion failed or the output file could not be inspected.
+/// - `warnings`: list of [warnings][crate::Warning].
+/// - `error`: message of the error, or none if the conversion succeeded.
+#[derive(Debug)]
+pub struct Outcome {
+    input: PathBuf,
+    output: Option<PathBuf>,
+    result: Result<RunReport>,
+}
+
+impl Outcome {
+    /// Input file of the conversion.
+    pub fn input(&self) -> &Path {
+        &self.input
+    }
+
+    /// Output file of the conversion, if known.
+    ///
+    /// This is `None` if the job did not set an output file or directory, or
+    /// if the name of the output file could not be derived.
+    pub fn output(&self) -> Option<&Path> {
+        self.output.as_deref()
+    }
+
+    /// Report on the conversion if it succeeded, or its error.
+    pub fn result(&self) -> &Result<RunReport> {
+        &self.result
+    }
+}
+
+impl Serialize for Outcome {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let report = self.result.as_ref().ok();
+        let mut outcome = serializer.serialize_struct("Outcome", 6)?;
+        outcome.serialize_field("input", &self.input)?;
+        outcome.serialize_field("output", &self.output)?;
+        let elapsed = report.map(|report| report.elapsed().as_secs_f64());
+        outcome.serialize_field("elapsed", &elapsed)?;
+        let output_size = report.and_then(RunReport::output_size);
+        outcome.serialize_field("output_size", &output_size)?;
+        let warnings = report.map_or(&[][..], RunReport::warnings);
+        outcome.serialize_field("warnings", warnings)?;
+        let error = self.result.as_ref().err().map(ToString::to_string);
+        outcome.serialize_field("error", &error)?;
+        outcome.end()
+    }
+}
+
+/// Error for a job file that is valid TOML, but does not describe
+/// conversions.
+fn invalid(msg: String) -> Error {
+    <toml::de::Error as serde::de::Error>::custom(msg).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp::TempDir;
+
+    #[test]
+    fn commands() {
+        let jobs = JobFile::parse(
+            r#"
+            [defaults]
+            format = "svg"
+            args = ["-dt"]
+
+            [[job]]
+            input = "a.ps"
+            output = "a.svg"
+            args = ["-ssp"]
+
+            [[job]]
+            inputs = ["b.ps", "c.ps"]
+            format = "pdf"
+            output_dir = "out"
+            "#,
+        )
+        .unwrap();
+        let commands = jobs.commands().unwrap();
+        let argv = |i: usize| {
+            let argv = commands[i].argv();
+            argv.iter()
+                .skip(1)
+                .map(|arg| arg.to_str().unwrap().to_owned())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(argv(0), ["-dt", "-ssp", "-f", "svg", "a.ps", "a.svg"]);
+        assert_eq!(argv(1), ["-dt", "-f", "pdf", "b.ps"]);
+        assert_eq!(argv(2), ["-dt", "-f", "pdf", "c.ps"]);
+    }
+
+    #[test]
+    fn invalid_jobs() {
+        let error = |toml| matches!(JobFile::parse(toml), Err(Error::JobFileError(_)));
+        assert!(error("[[job]]\ninput = 1\n"));
+        assert!(error("[[job]]\ninput = \"a.ps\"\nunknown = true\n"));
+        let commands = |toml| JobFile::parse(toml).unwrap().commands();
+        assert!(matches!(
+            commands("[[job]]\noutput = \"a.svg\"\n"),
+            Err(Error::JobFileError(_))
+        ));
+        assert!(matches!(
+            commands("[[job]]\ninputs = [\"a.ps\", \"b.ps\"]\noutput = \"a.svg\"\n"),
+            Err(Error::JobFileError(_))
+        ));
+    }
+
+    #[test]
+    fn load() {
+        crate::init().unwrap();
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("jobs.toml");
+        let toml = "[[job]]\ninput = \"input.ps\"\noutput = \"output.svg\"\nformat = \"svg\"\nargs = [\"-gstest\"]\n";
+        fs::write(&path, toml).unwrap();
+        let outcomes = JobFile::load(&path).unwrap().run().unwrap();
+        assert_eq!(outcomes.len(), 1);
+        assert_eq!(outcomes[0].input(), dir.path().join("input.ps"));
+        assert_eq!(outcomes[0].output(), Some(&*dir.path().join("output.svg")));
+        assert!(outcomes[0].result().is_ok());
+    }
+
+    #[test]
+    fn report() {
+        let outcomes = [
+            Outcome {
+                input: PathBuf::from("a.ps"),
+                output: Some(PathBuf::from("a.svg")),
+                result: Ok(RunReport {
+                    elapsed: std::time::Duration::from_millis(1500),
+                    output_size: Some(42),
+                    pages: Some(1),
+                    warnings: vec![crate::Warning::FontNotFound("Foo".to_owned())],
+                    cached: false,
+                }),
+            },
+            Outcome {
+                input: PathBuf::from("b.ps"),
+                output: None,
+                result: Err(Error::MissingGhostscript),
+            },
+        ];
+        assert_eq!(
+            serde_json::to_value(&outcomes).unwrap(),
+            serde_json::json!([
+                {
+                    "input": "a.ps",
+                    "output": "a.svg",
+                    "elapsed": 1.5,
+                    "output_size": 42,
+                    "warnings": [{"font_not_found": "Foo"}],
+                    "error": null,
+                },
+                {
+                    "input": "b.ps",
+                    "output": null,
+                    "elapsed": null,
+                    "output_size": null,
+                    "warnings": [],
+                    "error": "ghostscript executable not found",
+                },
+            ])
+        );
+    }
+}
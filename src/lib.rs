@@ -7,6 +7,8 @@
 //! Multiple versions of pstoedit are supported, but the appropriate feature
 //! starting with `pstoedit_` has to be enabled.
 //!
+//! - `pstoedit_4_01`: compatible with pstoedit version 4.01, and likely with
+//!   future 4.xx releases.
 //! - `pstoedit_4_00`: compatible with pstoedit version 4.00&ndash;4.01, and likely
 //!   with future 4.xx releases.
 //! - No feature starting with `pstoedit_`: compatible with pstoedit version
@@ -36,21 +38,89 @@
 
 #![cfg_attr(docsrs, feature(doc_cfg))]
 
+mod builder;
+mod cache;
 mod command;
+mod command_ref;
+pub mod conversion;
+pub mod deterministic;
 pub mod driver_info;
+pub mod dsc;
+pub mod engine;
 mod error;
+pub mod formats;
+mod gs;
+mod hook;
+pub mod input;
+#[cfg(feature = "jobs")]
+#[cfg_attr(docsrs, doc(cfg(feature = "jobs")))]
+pub mod jobs;
+mod normalize;
+mod options;
+mod output;
+pub mod pipeline;
+mod process;
+pub mod queue;
+#[cfg(feature = "image")]
+mod raster;
+mod report;
+mod retry;
+#[cfg(all(feature = "sandbox", target_os = "linux"))]
+mod sandbox;
+mod scratch;
+#[cfg(feature = "usvg")]
+mod svg_tree;
+mod temp;
+#[cfg(feature = "test_support")]
+#[cfg_attr(docsrs, doc(cfg(feature = "test_support")))]
+pub mod test_support;
+mod warning;
+#[cfg(feature = "notify")]
+#[cfg_attr(docsrs, doc(cfg(feature = "notify")))]
+pub mod watch;
 
 use pstoedit_sys as ffi;
+use std::env;
 use std::ffi::CStr;
-use std::os::raw::{c_char, c_int};
+use std::ffi::OsStr;
+use std::io::Write;
+use std::os::raw::{c_char, c_int, c_ulong, c_void};
+use std::path::{Path, PathBuf};
 use std::ptr;
+use std::sync::{Mutex, PoisonError};
+use std::thread::{self, ThreadId};
 
+pub use builder::CommandBuilder;
 pub use command::Command;
+pub use command_ref::CommandRef;
 pub use driver_info::DriverInfo;
 pub use error::{Error, Result};
+pub use gs::{Ghostscript, GsErrorKind, GsVersion};
+pub use input::page_count;
+pub use normalize::Normalize;
+pub use options::{Color, ColorModel, TextMode};
+pub use output::OutputTemplate;
+#[cfg(feature = "image")]
+pub use raster::rasterize;
+pub use report::RunReport;
+pub use retry::RetryPolicy;
+#[cfg(feature = "usvg")]
+pub use svg_tree::convert_to_svg_tree;
+pub use warning::Warning;
+
+/// Inline capacity of small vectors, set by the `smallvec_` features.
+#[cfg(feature = "smallvec_16")]
+const INLINE_CAPACITY: usize = 16;
+#[cfg(all(feature = "smallvec_8", not(feature = "smallvec_16")))]
+const INLINE_CAPACITY: usize = 8;
+#[cfg(all(
+    feature = "smallvec",
+    not(any(feature = "smallvec_8", feature = "smallvec_16"))
+))]
+const INLINE_CAPACITY: usize = 5;
 
 #[cfg(feature = "smallvec")]
-type SmallVec<T> = smallvec::SmallVec<[T; 5]>;
+type SmallVec<T> = smallvec::SmallVec<[T; INLINE_CAPACITY]>;
 #[cfg(feature = "smallvec")]
 use smallvec::smallvec;
 #[cfg(not(feature = "smallvec"))]
@@ -67,44 +137,252 @@ use vec as smallvec;
 /// # Errors
 /// [`IncompatibleVersion`][Error::IncompatibleVersion] if the version of
 /// pstoedit is not compatible with this crate.
+///
+/// [`AbiMismatch`][Error::AbiMismatch] if the driver information returned by
+/// pstoedit does not match the layout this crate was compiled for.
 pub fn init() -> Result<()> {
-    if unsafe { ffi::pstoedit_checkversion(ffi::pstoeditdllversion) } != 0 {
-        Ok(())
+    if unsafe { ffi::pstoedit_checkversion(ffi::pstoeditdllversion) } == 0 {
+        return Err(Error::IncompatibleVersion);
+    }
+    DriverInfo::get_native()?.check_layout()
+}
+
+/// Lock serializing calls to pstoedit, as it is not reentrant.
+static FFI_LOCK: Mutex<()> = Mutex::new(());
+
+/// Directory set by [`set_temp_dir`].
+static TEMP_DIR: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// Set directory for temporary files of all conversions.
+///
+/// pstoedit and ghostscript create intermediate files in the temporary
+/// directory of the system. This points them, as well as temporary files
+/// created by this crate, to `dir` instead, e.g. storage that is faster or
+/// subject to a quota. Passing `None` restores the default. A directory set
+/// for a single command using [`Command::temp_dir`] takes precedence.
+///
+/// The directory is passed to pstoedit and ghostscript through the `TMPDIR`,
+/// `TEMP`, and `TMP` environment variables, which can only be set soundly for
+/// child processes. It therefore applies to commands run using
+/// [`Subprocess`][engine::Subprocess], but not to commands run in-process,
+/// e.g. using [`Command::run`]: changing the environment of the process while
+/// other threads, including ghostscript, may read it is undefined behavior.
+/// To redirect in-process runs, set these variables before starting any
+/// threads instead.
+///
+/// # Examples
+/// ```no_run
+/// pstoedit::set_temp_dir(Some("/scratch/pstoedit".into()));
+/// ```
+pub fn set_temp_dir(dir: Option<PathBuf>) {
+    *TEMP_DIR.lock().unwrap_or_else(PoisonError::into_inner) = dir;
+}
+
+/// Directory set by [`set_temp_dir`], if any.
+pub(crate) fn configured_temp_dir() -> Option<PathBuf> {
+    TEMP_DIR
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+}
+
+/// Directory for temporary files created by this crate.
+fn temp_dir() -> PathBuf {
+    configured_temp_dir().unwrap_or_else(env::temp_dir)
+}
+
+/// Thread currently running pstoedit, if any.
+static ACTIVE: Mutex<Option<ThreadId>> = Mutex::new(None);
+
+/// Whether pstoedit is currently running on the thread.
+fn is_active(thread: ThreadId) -> bool {
+    *ACTIVE.lock().unwrap_or_else(PoisonError::into_inner) == Some(thread)
+}
+
+/// Record the thread currently running pstoedit.
+fn set_active(thread: Option<ThreadId>) {
+    *ACTIVE.lock().unwrap_or_else(PoisonError::into_inner) = thread;
+}
+
+/// Threads waiting for other threads to finish running pstoedit.
+static WAITING: Mutex<Vec<ThreadId>> = Mutex::new(Vec::new());
+
+/// Whether the thread is waiting to run pstoedit.
+fn is_waiting(thread: ThreadId) -> bool {
+    WAITING
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .contains(&thread)
+}
+
+/// Record whether the current thread is waiting to run pstoedit.
+fn set_waiting(waiting: bool) {
+    let thread = thread::current().id();
+    let mut threads = WAITING.lock().unwrap_or_else(PoisonError::into_inner);
+    if waiting {
+        threads.push(thread);
     } else {
-        Err(Error::IncompatibleVersion)
+        threads.retain(|&waiting| waiting != thread);
     }
 }
 
-/// Thin safe wrapper to main pstoedit API.
+/// Load pstoedit plugins.
+///
+/// Plugins are searched relative to `progname`, as well as in the default
+/// plugin directories. Plugins that are already loaded are not loaded again.
+///
+/// # Examples
+/// ```no_run
+/// pstoedit::init()?;
+/// pstoedit::load_plugins("pstoedit", false)?;
+/// # Ok::<(), pstoedit::Error>(())
+/// ```
 ///
-/// Safety is ensured using the invariants of [`CStr`].
-fn pstoedit_cstr<S, T>(argv: &[S], gs: Option<T>) -> Result<()>
+/// # Errors
+/// [`NulError`][Error::NulError] if `progname` contains an internal nul byte.
+#[cfg(feature = "pstoedit_4_01")]
+#[cfg_attr(docsrs, doc(cfg(feature = "pstoedit_4_01")))]
+pub fn load_plugins<S>(progname: S, verbose: bool) -> Result<()>
 where
-    S: AsRef<CStr>,
-    T: AsRef<CStr>,
+    S: Into<Vec<u8>>,
 {
-    let argv: SmallVec<_> = argv.iter().map(|s| s.as_ref().as_ptr()).collect();
-    // First as_ref is required to prevent move and drop if T = CString
-    let gs = gs.as_ref().map_or(ptr::null(), |s| s.as_ref().as_ptr());
-    // Safety: due to CStr input arguments it is ensured they are valid C strings
-    unsafe { pstoedit_raw(&argv, gs) }
+    let progname = std::ffi::CString::new(progname)?;
+    let _guard = FFI_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+    unsafe { ffi::loadpstoeditplugins_plainC(progname.as_ptr(), verbose as c_int) };
+    Ok(())
+}
+
+/// Unload all pstoedit plugins.
+///
+/// Drivers supplied by plugins are no longer available afterwards, until
+/// plugins are loaded again. Existing [`DriverInfo`] instances are not
+/// affected.
+///
+/// # Examples
+/// ```no_run
+/// pstoedit::init()?;
+/// // ... use pstoedit ...
+/// pstoedit::unload_plugins();
+/// # Ok::<(), pstoedit::Error>(())
+/// ```
+#[cfg(feature = "pstoedit_4_00")]
+#[cfg_attr(docsrs, doc(cfg(feature = "pstoedit_4_00")))]
+pub fn unload_plugins() {
+    let _guard = FFI_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+    unsafe { ffi::unloadpstoeditplugins() };
+}
+
+/// Interpret C string as path.
+///
+/// Paths are only required to be valid UTF-8 on platforms other than Unix.
+fn cstr_path(s: &CStr) -> Option<&Path> {
+    #[cfg(unix)]
+    {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+        Some(Path::new(OsStr::from_bytes(s.to_bytes())))
+    }
+    #[cfg(not(unix))]
+    {
+        s.to_str().ok().map(Path::new)
+    }
+}
+
+/// Convert path to bytes that can be passed to pstoedit.
+///
+/// Paths are only required to be valid UTF-8 on platforms other than Unix.
+fn path_bytes(path: &Path) -> Result<Vec<u8>> {
+    os_str_bytes(path.as_os_str())
+}
+
+/// Convert OS string to bytes that can be passed to pstoedit.
+///
+/// OS strings are only required to be valid UTF-8 on platforms other than
+/// Unix.
+fn os_str_bytes(s: &OsStr) -> Result<Vec<u8>> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        Ok(s.as_bytes().to_vec())
+    }
+    #[cfg(not(unix))]
+    {
+        let err = || std::io::Error::new(std::io::ErrorKind::InvalidInput, "non-UTF-8 string");
+        Ok(s.to_str().ok_or_else(err)?.into())
+    }
 }
 
 /// Thin wrapper to main pstoedit API that sets `argc` and converts errors.
 ///
+/// Calls are serialized, as pstoedit is not reentrant.
+///
+/// The diagnostic output of pstoedit is passed on to standard error, and
+/// inspected to classify failures reported by ghostscript, or to collect the
+/// warnings of a successful run.
+///
+/// # Safety
+/// All pointers must be valid C strings; `gs` may be null.
+unsafe fn pstoedit_raw(argv: &[*const c_char], gs: *const c_char) -> Result<Vec<Warning>> {
+    let (result, output) = pstoedit_captured(argv, gs);
+    pstoedit_outcome(result, &output)
+}
+
+/// Run pstoedit like [`pstoedit_raw`], returning its result code and the
+/// captured diagnostic output.
+///
 /// # Safety
 /// All pointers must be valid C strings; `gs` may be null.
-unsafe fn pstoedit_raw(argv: &[*const c_char], gs: *const c_char) -> Result<()> {
+unsafe fn pstoedit_captured(argv: &[*const c_char], gs: *const c_char) -> (c_int, Vec<u8>) {
     debug_assert!(argv.len() <= c_int::MAX as usize);
     let argc = argv.len() as c_int;
-    pstoedit_result(ffi::pstoedit_plainC(argc, argv.as_ptr(), gs))
+    set_waiting(true);
+    let _guard = FFI_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+    // Marked active before no longer waiting, so that the state of the
+    // conversion does not briefly read as processing.
+    set_active(Some(thread::current().id()));
+    set_waiting(false);
+    let mut output = Vec::new();
+    ffi::setPstoeditOutputFunction(&mut output as *mut Vec<u8> as *mut c_void, Some(capture));
+    let result = ffi::pstoedit_plainC(argc, argv.as_ptr(), gs);
+    set_active(None);
+    ffi::setPstoeditOutputFunction(ptr::null_mut(), None);
+    (result, output)
 }
 
+/// Outcome of a pstoedit run, given its result code and diagnostic output.
+fn pstoedit_outcome(result: c_int, output: &[u8]) -> Result<Vec<Warning>> {
+    match pstoedit_result(result) {
+        Ok(()) => Ok(Warning::from_output(output)),
+        Err(Error::PstoeditError(code)) => match GsErrorKind::from_output(output) {
+            Some(kind) => Err(Error::InterpreterError(kind)),
+            None if gs::missing_from_output(output) == Some(true) => Err(Error::MissingGhostscript),
+            None => Err(Error::PstoeditError(code)),
+        },
+        Err(err) => Err(err),
+    }
+}
+
+/// Output function of pstoedit appending to the buffer given by `data`, and
+/// writing to standard error.
+///
+/// # Safety
+/// `data` must point to a `Vec<u8>`, and `text` to `length` bytes.
+unsafe extern "C" fn capture(data: *mut c_void, text: *const c_char, length: c_ulong) -> c_int {
+    let text = std::slice::from_raw_parts(text as *const u8, length as usize);
+    (*(data as *mut Vec<u8>)).extend_from_slice(text);
+    let _ = std::io::stderr().write_all(text);
+    length.min(c_int::MAX as c_ulong) as c_int
+}
+
+/// Environment variables used by pstoedit and ghostscript to locate the
+/// temporary directory.
+pub(crate) const TEMP_VARS: [&str; 3] = ["TMPDIR", "TEMP", "TMP"];
+
 /// Interpret pstoedit return value as result.
 fn pstoedit_result(error_code: c_int) -> Result<()> {
     match error_code {
-        0 => Ok(()),
-        -1 => Err(Error::NotInitialized),
+        ffi::PSTOEDIT_OK => Ok(()),
+        ffi::PSTOEDIT_NOT_INITIALIZED => Err(Error::NotInitialized),
         err => Err(Error::PstoeditError(err)),
     }
 }
@@ -0,0 +1,190 @@
+use crate::{Error, Result, SmallVec, TextMode};
+use std::ffi::{CStr, CString};
+
+/// Flags of pstoedit that take a value as the next argument.
+pub(crate) const VALUED_FLAGS: &[&str] = &[
+    "-f",
+    "-df",
+    "-scale",
+    "-xscale",
+    "-yscale",
+    "-xshift",
+    "-yshift",
+    "-flat",
+    "-include",
+    "-gs",
+    "-psarg",
+    "-fontmap",
+    "-gsregbase",
+    "-minlinewidth",
+    "-page",
+    "-pagenumberformat",
+    "-rotate",
+    "-simplifymethod",
+    "-pngimage",
+    "-unmappablecharstring",
+    "-pagesize",
+];
+
+/// Flags that may be given multiple times, accumulating their values.
+const REPEATABLE_FLAGS: &[&str] = &["-psarg"];
+
+/// Groups of mutually exclusive flags.
+const EXCLUSIVE_FLAGS: &[&[&str]] = &[&TextMode::FLAGS];
+
+/// How [`Command::normalize`][crate::Command::normalize] resolves conflicting
+/// options.
+///
+/// Options conflict if the same flag is given multiple times with different
+/// values, or if mutually exclusive flags are given. Repeated identical
+/// options are always merged.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Normalize {
+    /// Keep the last of conflicting options, as later layers of configuration
+    /// typically override earlier ones.
+    KeepLast,
+    /// Report conflicting options as
+    /// [`ConflictingArgs`][Error::ConflictingArgs].
+    Reject,
+}
+
+/// Option in an argument list, consisting of a flag and possibly its value.
+struct Opt<'a> {
+    /// Index of the flag in the argument list.
+    index: usize,
+    /// Number of arguments, including the value.
+    count: usize,
+    /// Flag identifying conflicting options.
+    key: &'a [u8],
+    /// Option as displayed in errors.
+    text: String,
+}
+
+/// Flag identifying options that conflict with `flag`.
+fn key(flag: &[u8]) -> &[u8] {
+    EXCLUSIVE_FLAGS
+        .iter()
+        .find(|group| group.iter().any(|f| f.as_bytes() == flag))
+        .map_or(flag, |group| group[0].as_bytes())
+}
+
+/// Remove duplicate and conflicting options from `args`, of which the first is
+/// the program name.
+///
+/// A `format` set separately is passed to pstoedit after `args`, so it takes
+/// part as the last `-f` option.
+pub(crate) fn normalize(
+    args: &mut SmallVec<CString>,
+    format: Option<&CStr>,
+    mode: Normalize,
+) -> Result<()> {
+    let text = |args: &[CString]| {
+        let args: Vec<_> = args.iter().map(|arg| arg.to_string_lossy()).collect();
+        args.join(" ")
+    };
+    let mut options = Vec::new();
+    let mut index = 1;
+    while index < args.len() {
+        let flag = args[index].as_bytes();
+        if flag.len() < 2 || flag[0] != b'-' {
+            index += 1;
+            continue;
+        }
+        let valued = VALUED_FLAGS.iter().any(|f| f.as_bytes() == flag);
+        let count = if valued && index + 1 < args.len() {
+            2
+        } else {
+            1
+        };
+        options.push(Opt {
+            index,
+            count,
+            key: key(flag),
+            text: text(&args[index..index + count]),
+        });
+        index += count;
+    }
+    if let Some(format) = format {
+        options.push(Opt {
+            index: args.len(),
+            count: 0,
+            key: b"-f",
+            text: format!("-f {}", format.to_string_lossy()),
+        });
+    }
+
+    let mut remove = vec![false; args.len()];
+    for (n, opt) in options.iter().enumerate() {
+        if REPEATABLE_FLAGS.iter().any(|f| f.as_bytes() == opt.key) {
+            continue;
+        }
+        if let Some(later) = options[n + 1..].iter().find(|o| o.key == opt.key) {
+            if mode == Normalize::Reject && later.text != opt.text {
+                return Err(Error::ConflictingArgs(opt.text.clone(), later.text.clone()));
+            }
+            for flag in &mut remove[opt.index..opt.index + opt.count] {
+                *flag = true;
+            }
+        }
+    }
+    let mut index = 0;
+    args.retain(|_| {
+        index += 1;
+        !remove[index - 1]
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(args: &[&str], format: Option<&str>, mode: Normalize) -> Result<Vec<String>> {
+        let mut args = args.iter().map(|&arg| CString::new(arg).unwrap()).collect();
+        let format = format.map(|format| CString::new(format).unwrap());
+        normalize(&mut args, format.as_deref(), mode)?;
+        Ok(args
+            .iter()
+            .map(|arg| arg.to_str().unwrap().into())
+            .collect())
+    }
+
+    #[test]
+    fn keep_last() {
+        let args = ["pstoedit", "-f", "svg", "-dt", "-f", "pdf", "-ndt", "in.ps"];
+        let normalized = run(&args, None, Normalize::KeepLast).unwrap();
+        assert_eq!(normalized, ["pstoedit", "-f", "pdf", "-ndt", "in.ps"]);
+    }
+
+    #[test]
+    fn reject() {
+        let args = ["pstoedit", "-scale", "2", "-scale", "3"];
+        match run(&args, None, Normalize::Reject) {
+            Err(Error::ConflictingArgs(first, second)) => {
+                assert_eq!((first.as_str(), second.as_str()), ("-scale 2", "-scale 3"));
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn merge_identical() {
+        let args = ["pstoedit", "-dt", "-psarg", "a", "-dt", "-psarg", "b"];
+        let normalized = run(&args, None, Normalize::Reject).unwrap();
+        assert_eq!(
+            normalized,
+            ["pstoedit", "-psarg", "a", "-dt", "-psarg", "b"]
+        );
+    }
+
+    #[test]
+    fn typed_format() {
+        let args = ["pstoedit", "-f", "svg"];
+        assert_eq!(
+            run(&args, Some("pdf"), Normalize::KeepLast).unwrap(),
+            ["pstoedit"]
+        );
+        assert!(run(&args, Some("pdf"), Normalize::Reject).is_err());
+        assert!(run(&args, Some("svg"), Normalize::Reject).is_ok());
+    }
+}
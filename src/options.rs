@@ -0,0 +1,372 @@
+use crate::{Error, Result};
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+/// Color model passed to drivers, see
+/// [`Command::color_model`][crate::Command::color_model].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ColorModel {
+    /// Colors in the color model of the document, without converting them.
+    /// Whether CMYK colors are preserved depends on the driver. This is the
+    /// default of pstoedit.
+    #[default]
+    AsDocument,
+    /// Colors converted to RGB before being passed to the driver (`-rgb`).
+    Rgb,
+}
+
+/// Whether text is passed to drivers as text or drawn as outlines, see
+/// [`Command::text_mode`][crate::Command::text_mode].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum TextMode {
+    /// Text is passed as text to drivers that support it, and drawn as
+    /// outlines otherwise. This is the default of pstoedit.
+    #[default]
+    Auto,
+    /// All text is drawn as outlines (`-dt`), so that the output looks the
+    /// same regardless of the fonts available where it is used.
+    Outline,
+    /// Only text in fonts that are not standard PostScript fonts is drawn as
+    /// outlines (`-adt`).
+    OutlineNonStandard,
+    /// Text is always passed through as text (`-ndt`), even if its font is
+    /// not available.
+    PassThrough,
+}
+
+impl TextMode {
+    /// Flags of all modes.
+    pub(crate) const FLAGS: [&'static str; 3] = ["-dt", "-adt", "-ndt"];
+
+    /// Flag selecting the mode, if it is not the default.
+    pub(crate) fn flag(self) -> Option<&'static str> {
+        match self {
+            TextMode::Auto => None,
+            TextMode::Outline => Some(Self::FLAGS[0]),
+            TextMode::OutlineNonStandard => Some(Self::FLAGS[1]),
+            TextMode::PassThrough => Some(Self::FLAGS[2]),
+        }
+    }
+}
+
+/// Opaque RGB color, see [`Command::background`][crate::Command::background].
+///
+/// Colors can be parsed from hexadecimal notation, as in `"#ffcc00"` or
+/// `"#fc0"`, or from one of the names `black`, `white`, `gray` (or `grey`),
+/// `red`, `green`, `blue`, `yellow`, `cyan` and `magenta`.
+///
+/// # Examples
+/// ```
+/// use pstoedit::Color;
+///
+/// let color: Color = "#fc0".parse()?;
+/// assert_eq!(color, Color::rgb(0xff, 0xcc, 0x00));
+/// assert_eq!("white".parse::<Color>()?, Color::WHITE);
+/// assert_eq!(color.to_string(), "#ffcc00");
+/// # Ok::<(), pstoedit::Error>(())
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Color {
+    red: u8,
+    green: u8,
+    blue: u8,
+}
+
+impl Color {
+    /// Black.
+    pub const BLACK: Self = Self::rgb(0, 0, 0);
+    /// White.
+    pub const WHITE: Self = Self::rgb(0xff, 0xff, 0xff);
+
+    /// Create color from its red, green and blue components.
+    pub const fn rgb(red: u8, green: u8, blue: u8) -> Self {
+        Self { red, green, blue }
+    }
+
+    /// Red component.
+    pub fn red(self) -> u8 {
+        self.red
+    }
+
+    /// Green component.
+    pub fn green(self) -> u8 {
+        self.green
+    }
+
+    /// Blue component.
+    pub fn blue(self) -> u8 {
+        self.blue
+    }
+
+    /// Components as PostScript operands of `setrgbcolor`.
+    fn operands(self) -> String {
+        let component = |c: u8| f64::from(c) / 255.0;
+        format!(
+            "{:.4} {:.4} {:.4}",
+            component(self.red),
+            component(self.green),
+            component(self.blue)
+        )
+    }
+}
+
+impl FromStr for Color {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || Error::InvalidColor(s.to_owned());
+        let named = match s.to_ascii_lowercase().as_str() {
+            "black" => Some(Self::BLACK),
+            "white" => Some(Self::WHITE),
+            "gray" | "grey" => Some(Self::rgb(0x80, 0x80, 0x80)),
+            "red" => Some(Self::rgb(0xff, 0, 0)),
+            "green" => Some(Self::rgb(0, 0x80, 0)),
+            "blue" => Some(Self::rgb(0, 0, 0xff)),
+            "yellow" => Some(Self::rgb(0xff, 0xff, 0)),
+            "cyan" => Some(Self::rgb(0, 0xff, 0xff)),
+            "magenta" => Some(Self::rgb(0xff, 0, 0xff)),
+            _ => None,
+        };
+        if let Some(color) = named {
+            return Ok(color);
+        }
+        let hex = s.strip_prefix('#').ok_or_else(invalid)?;
+        if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(invalid());
+        }
+        let component = |digits: &str| u8::from_str_radix(digits, 16).map_err(|_| invalid());
+        match hex.len() {
+            3 => {
+                let short = |i: usize| component(&hex[i..=i]).map(|c| c * 0x11);
+                Ok(Self::rgb(short(0)?, short(1)?, short(2)?))
+            }
+            6 => Ok(Self::rgb(
+                component(&hex[0..2])?,
+                component(&hex[2..4])?,
+                component(&hex[4..6])?,
+            )),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.red, self.green, self.blue)
+    }
+}
+
+/// Write PostScript `input` to `output`, with code painting every page in
+/// `color` before it is drawn.
+///
+/// The code is inserted at the end of the setup section, or else directly
+/// after the header comments, so that the DSC header stays intact. Any
+/// `BeginPage` procedure of the document still runs after painting. EPS
+/// files must not use `setpagedevice`, so their bounding box is painted once
+/// instead.
+///
+/// # Errors
+/// [`UnsupportedInput`][Error::UnsupportedInput] if the input is not a
+/// PostScript or EPS document in text form.
+pub(crate) fn write_with_background(input: &Path, output: &Path, color: Color) -> Result<()> {
+    let data = fs::read(input)?;
+    let start = usize::from(data.starts_with(b"\x04"));
+    if !data[start..].starts_with(b"%!") {
+        return Err(Error::UnsupportedInput);
+    }
+    let mut lines = dsc_lines(&data);
+    let eps = lines
+        .next()
+        .is_some_and(|(_, line)| line.windows(4).any(|w| w == b"EPSF"));
+    let mut header_end = None;
+    let mut setup_end = None;
+    let mut bbox = None;
+    for (offset, line) in lines {
+        if header_end.is_none() {
+            if let Some(rest) = line.strip_prefix(b"%%BoundingBox:") {
+                bbox = parse_bbox(rest);
+            }
+            if line.starts_with(b"%%EndComments") {
+                header_end = Some(offset + line.len());
+            } else if !line.starts_with(b"%%") || line.starts_with(b"%%Begin") {
+                header_end = Some(offset);
+            }
+        }
+        if line.starts_with(b"%%EndSetup") {
+            setup_end = Some(offset);
+            break;
+        }
+        if line.starts_with(b"%%Page:") {
+            break;
+        }
+    }
+    let at = setup_end.or(header_end).unwrap_or(data.len());
+    let paint = format!("gsave {} setrgbcolor", color.operands());
+    let code = if eps {
+        let area = match bbox {
+            Some([llx, lly, urx, ury]) => {
+                format!("{} {} {} {} rectfill", llx, lly, urx - llx, ury - lly)
+            }
+            None => "clippath fill".to_owned(),
+        };
+        format!("{} {} grestore\n", paint, area)
+    } else {
+        format!(
+            "userdict /PstoeditRsBeginPage currentpagedevice /BeginPage get put\n\
+             << /BeginPage {{ {} clippath fill grestore \
+             userdict /PstoeditRsBeginPage get exec }} >> setpagedevice\n",
+            paint
+        )
+    };
+    let mut file = BufWriter::new(File::create(output)?);
+    file.write_all(&data[..at])?;
+    if !matches!(data[..at].last(), Some(b'\n' | b'\r')) {
+        file.write_all(b"\n")?;
+    }
+    file.write_all(code.as_bytes())?;
+    file.write_all(&data[at..])?;
+    file.flush()?;
+    Ok(())
+}
+
+/// Lines of a document with their offsets, including line terminators.
+fn dsc_lines(data: &[u8]) -> impl Iterator<Item = (usize, &[u8])> {
+    let mut offset = 0;
+    std::iter::from_fn(move || {
+        if offset == data.len() {
+            return None;
+        }
+        let rest = &data[offset..];
+        let end = match rest.iter().position(|&b| b == b'\n' || b == b'\r') {
+            Some(i) if rest[i..].starts_with(b"\r\n") => i + 2,
+            Some(i) => i + 1,
+            None => rest.len(),
+        };
+        let line = (offset, &rest[..end]);
+        offset += end;
+        Some(line)
+    })
+}
+
+/// Coordinates of a `%%BoundingBox` comment, `None` if deferred with
+/// `(atend)` or invalid.
+fn parse_bbox(value: &[u8]) -> Option<[f64; 4]> {
+    let value = std::str::from_utf8(value).ok()?;
+    let mut coordinates = value.split_whitespace().map(str::parse);
+    let mut bbox = [0.0; 4];
+    for coordinate in &mut bbox {
+        *coordinate = coordinates.next()?.ok()?;
+    }
+    Some(bbox)
+}
+
+/// Whether the fontmap file at `path` maps `font` or maps a font to it.
+///
+/// Every line of a fontmap holds the name of a font followed by its
+/// replacement. Names containing spaces are quoted, and `%` starts a comment.
+pub(crate) fn fontmap_contains(path: &Path, font: &str) -> Result<bool> {
+    let fontmap = fs::read(path)?;
+    let fontmap = String::from_utf8_lossy(&fontmap);
+    let found = fontmap
+        .lines()
+        .flat_map(|line| fontmap_names(line.split('%').next().unwrap_or_default()))
+        .any(|name| name == font);
+    Ok(found)
+}
+
+/// Font names on a line of a fontmap, without comments.
+fn fontmap_names(line: &str) -> impl Iterator<Item = &str> {
+    let mut rest = line;
+    std::iter::from_fn(move || {
+        rest = rest.trim_start();
+        let (name, remainder) = match rest.strip_prefix('"') {
+            Some(quoted) => quoted.split_once('"').unwrap_or((quoted, "")),
+            None => rest.split_at(rest.find(char::is_whitespace).unwrap_or(rest.len())),
+        };
+        rest = remainder;
+        Some(name).filter(|name| !name.is_empty())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp::TempDir;
+
+    #[test]
+    fn parse_color() {
+        let parse = |s: &str| s.parse::<Color>();
+        assert_eq!(parse("#336699").unwrap(), Color::rgb(0x33, 0x66, 0x99));
+        assert_eq!(parse("#FFF").unwrap(), Color::WHITE);
+        assert_eq!(parse("Grey").unwrap(), Color::rgb(0x80, 0x80, 0x80));
+        for invalid in ["", "#", "336699", "#12345", "#ggg", "#+1+2+3", "navy"].iter() {
+            assert!(matches!(parse(invalid), Err(Error::InvalidColor(_))));
+        }
+        assert_eq!(Color::rgb(1, 2, 255).to_string(), "#0102ff");
+        assert_eq!(Color::WHITE.operands(), "1.0000 1.0000 1.0000");
+    }
+
+    #[test]
+    fn background() {
+        let dir = TempDir::new().unwrap();
+        let input = dir.path().join("input.ps");
+        let output = dir.path().join("output.ps");
+        let rewrite = |document: &str| {
+            fs::write(&input, document).unwrap();
+            write_with_background(&input, &output, Color::BLACK).unwrap();
+            fs::read_to_string(&output).unwrap()
+        };
+        let written = rewrite("%!PS-Adobe-3.0\nshowpage\n");
+        assert!(written.starts_with("%!PS-Adobe-3.0\nuserdict /PstoeditRsBeginPage"));
+        assert!(written.contains("0.0000 0.0000 0.0000 setrgbcolor"));
+        assert!(written.contains("/PstoeditRsBeginPage get exec"));
+        assert!(written.ends_with("setpagedevice\nshowpage\n"));
+        let header = "%!PS-Adobe-3.0\n%%Pages: 1\n%%EndComments\n";
+        let written = rewrite(&format!("{}%%Page: 1 1\nshowpage\n%%EOF\n", header));
+        assert!(written.starts_with(&format!("{}userdict", header)));
+        assert!(written.ends_with("setpagedevice\n%%Page: 1 1\nshowpage\n%%EOF\n"));
+        let written = rewrite(&format!(
+            "{}%%BeginSetup\n1 setlinewidth\n%%EndSetup\n%%Page: 1 1\n",
+            header
+        ));
+        assert!(written.contains("1 setlinewidth\nuserdict"));
+        assert!(written.contains("setpagedevice\n%%EndSetup\n"));
+        let written = rewrite(
+            "%!PS-Adobe-3.0 EPSF-3.0\r\n%%BoundingBox: 10 20 110 70\r\n%%EndComments\r\n\
+             0 0 moveto\r\n",
+        );
+        assert!(written.starts_with(
+            "%!PS-Adobe-3.0 EPSF-3.0\r\n%%BoundingBox: 10 20 110 70\r\n%%EndComments\r\ngsave"
+        ));
+        assert!(written.contains("setrgbcolor 10 20 100 50 rectfill grestore\n0 0 moveto"));
+        assert!(!written.contains("setpagedevice"));
+        fs::write(&input, "%PDF-1.7\n").unwrap();
+        let result = write_with_background(&input, &output, Color::BLACK);
+        assert!(matches!(result, Err(Error::UnsupportedInput)));
+    }
+
+    #[test]
+    fn fontmap() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("fonts.fmp");
+        fs::write(
+            &path,
+            "% Replacements\n\
+             Helvetica Arial\n\
+             Times-Roman \"Times New Roman\" % quoted\n",
+        )
+        .unwrap();
+        let contains = |font| fontmap_contains(&path, font).unwrap();
+        assert!(contains("Helvetica"));
+        assert!(contains("Arial"));
+        assert!(contains("Times New Roman"));
+        assert!(!contains("Times"));
+        assert!(!contains("Replacements"));
+        assert!(!contains("quoted"));
+    }
+}
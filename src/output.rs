@@ -0,0 +1,250 @@
+//! Naming of output files.
+
+use crate::{Error, Result};
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Name of the intermediate file of every page written by pstoedit, with `%d`
+/// replaced by the page number.
+pub(crate) const PAGE_PATTERN: &str = "page-%d";
+
+/// Template for the names of generated output files.
+///
+/// Templates are file names with placeholders in braces:
+///
+/// - `{page}`: the page number, starting at 1. A width can be given as in
+///   [`format!`], e.g. `{page:03}` to pad with zeros to three digits.
+/// - `{stem}`: the file name of the input without extension.
+///
+/// Literal braces are written as `{{` and `}}`.
+///
+/// Unlike the implicit numbering of pstoedit, names generated from a template
+/// are checked for collisions, both with each other and with existing files,
+/// before any file is written.
+///
+/// # Examples
+/// ```
+/// use pstoedit::OutputTemplate;
+///
+/// let template = OutputTemplate::new("{stem}-page-{page:03}.svg")?;
+/// assert_eq!(template.render("input", 7), "input-page-007.svg");
+/// # Ok::<(), pstoedit::Error>(())
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OutputTemplate {
+    template: String,
+    parts: Vec<Part>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Part {
+    Literal(String),
+    Page { width: usize, zero: bool },
+    Stem,
+}
+
+impl OutputTemplate {
+    /// Parse template.
+    ///
+    /// # Errors
+    /// [`InvalidTemplate`][Error::InvalidTemplate] if a placeholder is
+    /// unknown or not closed, or a brace is not escaped.
+    pub fn new(template: &str) -> Result<Self> {
+        let invalid = || Error::InvalidTemplate(template.to_owned());
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.as_str().starts_with('{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.as_str().starts_with('}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let rest = chars.as_str();
+                    let end = rest.find('}').ok_or_else(invalid)?;
+                    let part = match rest[..end].split_once(':') {
+                        None if &rest[..end] == "page" => Part::Page {
+                            width: 0,
+                            zero: false,
+                        },
+                        None if &rest[..end] == "stem" => Part::Stem,
+                        Some(("page", spec)) => Part::Page {
+                            width: spec.parse().map_err(|_| invalid())?,
+                            zero: spec.starts_with('0'),
+                        },
+                        _ => return Err(invalid()),
+                    };
+                    if !literal.is_empty() {
+                        parts.push(Part::Literal(literal.split_off(0)));
+                    }
+                    parts.push(part);
+                    chars = rest[end + 1..].chars();
+                }
+                '}' => return Err(invalid()),
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+        Ok(Self {
+            template: template.to_owned(),
+            parts,
+        })
+    }
+
+    /// Whether the template contains the `{page}` placeholder.
+    ///
+    /// Without it, only single-page documents can be split without
+    /// collisions.
+    pub fn has_page(&self) -> bool {
+        self.parts
+            .iter()
+            .any(|part| matches!(part, Part::Page { .. }))
+    }
+
+    /// Generate file name for a page of an input with file stem `stem`.
+    pub fn render(&self, stem: &str, page: u32) -> String {
+        let mut name = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Literal(s) => name.push_str(s),
+                Part::Page { width, zero: true } => {
+                    name.push_str(&format!("{:0width$}", page, width = width))
+                }
+                Part::Page { width, zero: false } => {
+                    name.push_str(&format!("{:width$}", page, width = width))
+                }
+                Part::Stem => name.push_str(stem),
+            }
+        }
+        name
+    }
+}
+
+impl FromStr for OutputTemplate {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::new(s)
+    }
+}
+
+impl fmt::Display for OutputTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.template)
+    }
+}
+
+/// Check that paths are distinct and do not exist yet.
+///
+/// # Errors
+/// [`OutputCollision`][Error::OutputCollision] with the first path that is
+/// repeated or exists.
+pub(crate) fn check_collisions<'a, I>(paths: I) -> Result<()>
+where
+    I: IntoIterator<Item = &'a Path>,
+{
+    let mut seen = HashSet::new();
+    for path in paths {
+        if !seen.insert(path) || path.exists() {
+            return Err(Error::OutputCollision(path.to_owned()));
+        }
+    }
+    Ok(())
+}
+
+/// Move pages written by pstoedit using [`PAGE_PATTERN`] from `src` to `dst`,
+/// named according to `template`.
+///
+/// Returns the new paths ordered by page number. Nothing is moved if any of
+/// the names collide.
+pub(crate) fn move_pages(
+    src: &Path,
+    dst: &Path,
+    template: &OutputTemplate,
+    stem: &str,
+) -> Result<Vec<PathBuf>> {
+    let prefix = &PAGE_PATTERN[..PAGE_PATTERN.len() - 2];
+    let mut pages = Vec::new();
+    for entry in fs::read_dir(src)? {
+        let path = entry?.path();
+        let page = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_prefix(prefix))
+            .and_then(|number| number.parse::<u32>().ok());
+        if let Some(page) = page {
+            pages.push((page, path));
+        }
+    }
+    pages.sort();
+    let targets: Vec<_> = pages
+        .iter()
+        .map(|(page, _)| dst.join(template.render(stem, *page)))
+        .collect();
+    check_collisions(targets.iter().map(PathBuf::as_path))?;
+    for ((_, from), to) in pages.iter().zip(&targets) {
+        // Renaming fails if the temporary directory is on another file system
+        if fs::rename(from, to).is_err() {
+            fs::copy(from, to)?;
+        }
+    }
+    Ok(targets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp::TempDir;
+
+    #[test]
+    fn render() {
+        let template = OutputTemplate::new("{stem}-{page}-{page:3}-{page:03}.svg").unwrap();
+        assert_eq!(template.render("doc", 12), "doc-12- 12-012.svg");
+        assert!(template.has_page());
+        let template: OutputTemplate = "{{{stem}}}.svg".parse().unwrap();
+        assert_eq!(template.render("doc", 1), "{doc}.svg");
+        assert!(!template.has_page());
+        assert_eq!(template.to_string(), "{{{stem}}}.svg");
+    }
+
+    #[test]
+    fn invalid() {
+        for template in &["{page", "page}", "{pages}", "{stem:3}", "{page:x}"] {
+            assert!(matches!(
+                OutputTemplate::new(template),
+                Err(Error::InvalidTemplate(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn move_pages_collision() {
+        let src = TempDir::new().unwrap();
+        let dst = TempDir::new().unwrap();
+        for page in &["page-2", "page-1", "other"] {
+            fs::write(src.path().join(page), page).unwrap();
+        }
+        let template = OutputTemplate::new("{stem}.svg").unwrap();
+        assert!(matches!(
+            move_pages(src.path(), dst.path(), &template, "doc"),
+            Err(Error::OutputCollision(_))
+        ));
+        let template = OutputTemplate::new("{stem}-{page:02}.svg").unwrap();
+        let paths = move_pages(src.path(), dst.path(), &template, "doc").unwrap();
+        assert_eq!(
+            paths,
+            [dst.path().join("doc-01.svg"), dst.path().join("doc-02.svg")]
+        );
+        assert_eq!(fs::read(&paths[1]).unwrap(), b"page-2");
+    }
+}
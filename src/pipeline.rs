@@ -0,0 +1,247 @@
+//! Conversions with additional processing stages.
+//!
+//! A [`Pipeline`] runs a [`Command`] on an input file, optionally preceded by
+//! stages that prepare the input. Intermediate files are stored in a
+//! temporary directory that is removed afterwards.
+//!
+//! Input can also be streamed from a [`Read`] and output to a [`Write`] using
+//! [`run_stream`][Pipeline::run_stream], which spools the data through
+//! temporary files in chunks, so documents are never held in memory.
+//!
+//! # Examples
+//! ```no_run
+//! use pstoedit::pipeline::Pipeline;
+//! use pstoedit::Command;
+//!
+//! pstoedit::init()?;
+//! let mut cmd = Command::new();
+//! cmd.format("fig")?;
+//! Pipeline::new(cmd).pdf_to_ps().run("input.pdf", "output.fig")?;
+//! # Ok::<(), pstoedit::Error>(())
+//! ```
+//!
+//! ```no_run
+//! use pstoedit::pipeline::Pipeline;
+//! use pstoedit::Command;
+//! use std::io;
+//!
+//! pstoedit::init()?;
+//! let mut cmd = Command::new();
+//! cmd.format("fig")?;
+//! Pipeline::new(cmd)
+//!     .pdf_to_ps()
+//!     .spool_limit(4 << 30)
+//!     .run_stream(io::stdin().lock(), io::stdout().lock())?;
+//! # Ok::<(), pstoedit::Error>(())
+//! ```
+
+use crate::engine::Limits;
+use crate::input::InputKind;
+use crate::temp::TempDir;
+use crate::{gs, Command, Error, Result};
+use std::ffi::OsStr;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Read, Write};
+use std::path::Path;
+
+/// Conversion with additional processing stages.
+///
+/// See [module-level documentation][self] for more details.
+#[derive(Clone, Debug)]
+pub struct Pipeline {
+    command: Command,
+    pdf_to_ps: bool,
+    limits: Limits,
+    spool_limit: Option<u64>,
+}
+
+impl Pipeline {
+    /// Create pipeline around a command.
+    ///
+    /// The input and output of the command are set by the pipeline, and should
+    /// not be set on `command`.
+    pub fn new(command: Command) -> Self {
+        Self {
+            command,
+            pdf_to_ps: false,
+            limits: Limits::new(),
+            spool_limit: None,
+        }
+    }
+
+    /// Convert PDF input to PostScript before running pstoedit.
+    ///
+    /// The conversion uses the `ps2write` device of ghostscript, using the
+    /// executable set by [`Command::gs`] or otherwise the one found in `PATH`.
+    /// Input that is not PDF is passed to pstoedit unchanged.
+    pub fn pdf_to_ps(&mut self) -> &mut Self {
+        self.pdf_to_ps = true;
+        self
+    }
+
+    /// Set limits on the resources used by ghostscript when converting PDF
+    /// to PostScript.
+    ///
+    /// To limit pstoedit itself, run the command using the
+    /// [`Subprocess`][crate::engine::Subprocess] engine.
+    pub fn limits(&mut self, limits: Limits) -> &mut Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Limit the size of input spooled by [`run_stream`][Pipeline::run_stream].
+    ///
+    /// This bounds the temporary disk space used for the input. By default,
+    /// input of any size is spooled.
+    pub fn spool_limit(&mut self, bytes: u64) -> &mut Self {
+        self.spool_limit = Some(bytes);
+        self
+    }
+
+    /// Register a hook to rewrite the input file before running pstoedit.
+    ///
+    /// Hooks run after [`pdf_to_ps`][Pipeline::pdf_to_ps], see
+    /// [`Command::pre_process`].
+    pub fn pre_process<F>(&mut self, hook: F) -> &mut Self
+    where
+        F: Fn(&Path, &Path) -> Result<()> + Send + Sync + 'static,
+    {
+        self.command.pre_process(hook);
+        self
+    }
+
+    /// Register a hook to process the output file after a successful run.
+    ///
+    /// See [`Command::post_process`].
+    pub fn post_process<F>(&mut self, hook: F) -> &mut Self
+    where
+        F: Fn(&Path) -> Result<()> + Send + Sync + 'static,
+    {
+        self.command.post_process(hook);
+        self
+    }
+
+    /// Run the pipeline.
+    ///
+    /// # Errors
+    /// - [`IoError`][crate::Error::IoError] if the input cannot be read.
+    /// - [`MissingGhostscript`][crate::Error::MissingGhostscript] if
+    ///   ghostscript cannot be started.
+    /// - [`GhostscriptError`][crate::Error::GhostscriptError] if the PDF to PostScript
+    ///   conversion fails.
+    /// - [`LimitExceeded`][crate::Error::LimitExceeded] if ghostscript exceeds
+    ///   a limit, see [`limits`][Pipeline::limits].
+    /// - Errors from running pstoedit, see [`Command::run`].
+    pub fn run<P, Q>(&self, input: P, output: Q) -> Result<()>
+    where
+        P: AsRef<Path>,
+        Q: AsRef<Path>,
+    {
+        let dir = TempDir::new()?;
+        let mut input = input.as_ref();
+        let converted = dir.path().join("input.ps");
+        if self.pdf_to_ps && InputKind::detect_file(input)? == InputKind::Pdf {
+            self.gs_pdf_to_ps(input, &converted)?;
+            input = &converted;
+        }
+        self.command
+            .clone()
+            .input(crate::path_bytes(input)?)?
+            .output(crate::path_bytes(output.as_ref())?)?
+            .run()
+    }
+
+    /// Run the pipeline on streams.
+    ///
+    /// The input is spooled to a temporary file in chunks, as pstoedit can
+    /// only read files, and the output is copied to `output` in chunks once
+    /// the conversion is finished. Writes to `output` block as usual, so a
+    /// slow consumer throttles the delivery without buffering the output in
+    /// memory. Temporary files are removed as soon as they are no longer
+    /// needed. The format must be set on the command, as it cannot be derived
+    /// from a file name.
+    ///
+    /// Returns the number of bytes written to `output`.
+    ///
+    /// # Errors
+    /// - [`InputTooLarge`][crate::Error::InputTooLarge] if the input exceeds
+    ///   the [`spool_limit`][Pipeline::spool_limit].
+    /// - [`IoError`][crate::Error::IoError] if reading the input, spooling,
+    ///   or writing the output fails.
+    /// - Errors of [`run`][Pipeline::run].
+    pub fn run_stream<R, W>(&self, input: R, mut output: W) -> Result<u64>
+    where
+        R: Read,
+        W: Write,
+    {
+        let dir = TempDir::new()?;
+        let spooled = dir.path().join("input");
+        let mut file = BufWriter::new(File::create(&spooled)?);
+        let limit = self.spool_limit.unwrap_or(u64::MAX);
+        let size = io::copy(&mut input.take(limit.saturating_add(1)), &mut file)?;
+        if size > limit {
+            return Err(Error::InputTooLarge(limit));
+        }
+        file.into_inner().map_err(io::IntoInnerError::into_error)?;
+        // pstoedit handles PDF input based on the file name extension
+        let extension = match InputKind::detect_file(&spooled) {
+            Ok(InputKind::Pdf) => "pdf",
+            _ => "ps",
+        };
+        let input = spooled.with_extension(extension);
+        fs::rename(&spooled, &input)?;
+        let converted = dir.path().join("output");
+        self.run(&input, &converted)?;
+        fs::remove_file(&input)?;
+        let written = io::copy(&mut File::open(&converted)?, &mut output)?;
+        output.flush()?;
+        Ok(written)
+    }
+
+    /// Convert PDF to PostScript using ghostscript.
+    fn gs_pdf_to_ps(&self, input: &Path, output: &Path) -> Result<()> {
+        let gs = self.command.gs_path().and_then(crate::cstr_path);
+        let output = gs::output_arg(output);
+        gs::run(
+            gs,
+            [OsStr::new("-sDEVICE=ps2write"), &output, input.as_ref()],
+            &self.limits,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn missing_gs() {
+        let dir = TempDir::new().unwrap();
+        let input = dir.path().join("input.pdf");
+        fs::write(&input, "%PDF-1.4\n").unwrap();
+        let mut cmd = Command::new();
+        cmd.gs("/nonexistent/gs").unwrap();
+        let result = Pipeline::new(cmd)
+            .pdf_to_ps()
+            .run(&input, dir.path().join("output.fps"));
+        assert!(matches!(result, Err(Error::MissingGhostscript)));
+    }
+
+    #[test]
+    fn stream() {
+        crate::init().unwrap();
+        let mut cmd = Command::new();
+        cmd.format("psf").unwrap();
+        let mut pipeline = Pipeline::new(cmd);
+        let input = b"%!PS\nshowpage\n";
+        let mut output = Vec::new();
+        let written = pipeline.run_stream(&input[..], &mut output).unwrap();
+        assert_eq!(written, output.len() as u64);
+        assert!(output.starts_with(b"%!"));
+
+        pipeline.spool_limit(4);
+        let result = pipeline.run_stream(&input[..], io::sink());
+        assert!(matches!(result, Err(Error::InputTooLarge(4))));
+    }
+}
@@ -0,0 +1,540 @@
+//! Child processes subject to resource limits.
+
+use crate::engine::{Limits, Resource};
+use crate::{Error, Result};
+use std::path::Path;
+use std::process::{Command, ExitStatus};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+
+/// Handle to terminate a process and all its descendants from another thread.
+///
+/// Processes are terminated as a whole using a process group on Unix and a
+/// job object on Windows.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Abort(Arc<Mutex<AbortState>>);
+
+#[derive(Debug, Default)]
+struct AbortState {
+    aborted: bool,
+    running: Option<Running>,
+}
+
+/// Process group of the running process.
+#[cfg(unix)]
+type Running = libc::pid_t;
+/// Job object of the running process.
+#[cfg(windows)]
+type Running = Arc<job::Job>;
+#[cfg(not(any(unix, windows)))]
+type Running = std::convert::Infallible;
+
+impl Abort {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Terminate the running process, if any, and prevent a process from
+    /// being started.
+    pub(crate) fn abort(&self) {
+        let mut state = self.lock();
+        state.aborted = true;
+        if let Some(running) = &state.running {
+            terminate(running);
+        }
+    }
+
+    pub(crate) fn is_aborted(&self) -> bool {
+        self.lock().aborted
+    }
+
+    fn lock(&self) -> MutexGuard<'_, AbortState> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(unix)]
+fn terminate(group: &libc::pid_t) {
+    // Safety: the process group is still registered, so it was not reused
+    unsafe { libc::killpg(*group, libc::SIGKILL) };
+}
+
+#[cfg(windows)]
+fn terminate(job: &Arc<job::Job>) {
+    let _ = job.terminate();
+}
+
+#[cfg(not(any(unix, windows)))]
+fn terminate(running: &Running) {
+    match *running {}
+}
+
+/// Resources used by a child process and its descendants.
+#[derive(Debug, Default)]
+struct Usage {
+    cpu_time: Duration,
+    peak_memory: Option<u64>,
+}
+
+/// Run process to completion, subject to `limits`.
+///
+/// The limits are inherited by descendants of the process, such as
+/// ghostscript started by pstoedit. `output` is the file inspected to detect
+/// whether the output size limit was hit. The process can be terminated using
+/// `abort`.
+///
+/// # Errors
+/// - [`Cancelled`][Error::Cancelled] if the process was aborted.
+/// - [`LimitExceeded`][Error::LimitExceeded] if the process failed and a
+///   limit was hit.
+/// - [`IoError`][Error::IoError] if the process cannot be started or waited
+///   for.
+pub(crate) fn run(
+    command: &mut Command,
+    limits: &Limits,
+    output: Option<&Path>,
+    abort: Option<&Abort>,
+) -> Result<ExitStatus> {
+    let (status, usage) = spawn_wait(command, limits, abort)?;
+    if status.success() {
+        return Ok(status);
+    }
+    if abort.is_some_and(Abort::is_aborted) {
+        return Err(Error::Cancelled);
+    }
+    match exceeded(status, &usage, limits, output) {
+        Some(resource) => Err(Error::LimitExceeded(resource)),
+        None => Ok(status),
+    }
+}
+
+/// Determine which limit, if any, was hit by a failed process.
+///
+/// Hitting a limit does not necessarily terminate the process, e.g. when
+/// ghostscript handles a failed allocation, so this is a best-effort guess
+/// based on the usage.
+fn exceeded(
+    status: ExitStatus,
+    usage: &Usage,
+    limits: &Limits,
+    output: Option<&Path>,
+) -> Option<Resource> {
+    // Checked first, as the process is killed once it outlives the soft CPU
+    // limit, which would otherwise be taken for hitting the memory limit
+    if matches!(limits.cpu_time, Some(limit) if usage.cpu_time >= limit) {
+        return Some(Resource::CpuTime);
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        match status.signal() {
+            Some(libc::SIGXCPU) => return Some(Resource::CpuTime),
+            Some(libc::SIGXFSZ) => return Some(Resource::OutputSize),
+            Some(libc::SIGSEGV | libc::SIGBUS | libc::SIGABRT | libc::SIGKILL)
+                if limits.memory.is_some() =>
+            {
+                return Some(Resource::Memory)
+            }
+            _ => {}
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = status;
+    // Allocations fail before the peak reaches the limit exactly
+    if let (Some(limit), Some(peak)) = (limits.memory, usage.peak_memory) {
+        if peak >= limit - limit / 16 {
+            return Some(Resource::Memory);
+        }
+    }
+    let output_size = output
+        .and_then(|path| path.metadata().ok())
+        .map(|m| m.len());
+    if let (Some(limit), Some(size)) = (limits.output_size, output_size) {
+        if size >= limit {
+            return Some(Resource::OutputSize);
+        }
+    }
+    None
+}
+
+/// Spawn process, registering it with `abort` if given.
+fn spawn_registered<F>(
+    command: &mut Command,
+    abort: Option<&Abort>,
+    register: F,
+) -> Result<std::process::Child>
+where
+    F: FnOnce(&std::process::Child) -> std::io::Result<Option<Running>>,
+{
+    let mut state = abort.map(Abort::lock);
+    if matches!(&state, Some(state) if state.aborted) {
+        return Err(Error::Cancelled);
+    }
+    let mut child = command.spawn()?;
+    match register(&child) {
+        Ok(running) => {
+            if let Some(state) = &mut state {
+                state.running = running;
+            }
+            Ok(child)
+        }
+        Err(err) => {
+            let _ = child.kill();
+            let _ = child.wait();
+            Err(err.into())
+        }
+    }
+}
+
+#[cfg(unix)]
+fn spawn_wait(
+    command: &mut Command,
+    limits: &Limits,
+    abort: Option<&Abort>,
+) -> Result<(ExitStatus, Usage)> {
+    use std::convert::TryFrom;
+    use std::io;
+    use std::os::unix::process::{CommandExt, ExitStatusExt};
+
+    let rlimits: Vec<_> = [
+        (libc::RLIMIT_CPU, limits.cpu_time.map(cpu_seconds)),
+        (libc::RLIMIT_AS, limits.memory),
+        (libc::RLIMIT_FSIZE, limits.output_size),
+    ]
+    .iter()
+    .filter_map(|&(resource, limit)| Some((resource, limit? as libc::rlim_t)))
+    .collect();
+    let niceness = limits.priority.map(crate::engine::Priority::niceness);
+    // Safety: only async-signal-safe functions are called after forking
+    unsafe {
+        command.pre_exec(move || {
+            if let Some(niceness) = niceness {
+                // Lowering the niceness requires privileges, so only raise it;
+                // an error return of -1 is below any niceness set here
+                if libc::getpriority(libc::PRIO_PROCESS, 0) < niceness
+                    && libc::setpriority(libc::PRIO_PROCESS, 0, niceness) != 0
+                {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+            for &(resource, limit) in &rlimits {
+                // The hard CPU limit kills the process, so leave a second for
+                // the signal of the soft limit
+                let hard = if resource == libc::RLIMIT_CPU {
+                    limit.saturating_add(1)
+                } else {
+                    limit
+                };
+                let rlimit = libc::rlimit {
+                    rlim_cur: limit,
+                    rlim_max: hard,
+                };
+                if libc::setrlimit(resource, &rlimit) != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+            Ok(())
+        });
+    }
+    if abort.is_some() {
+        // Make the process the leader of a new group, which its descendants
+        // join
+        command.process_group(0);
+    }
+    let child = spawn_registered(command, abort, |child| Ok(Some(child.id() as libc::pid_t)))?;
+    let pid = child.id() as libc::pid_t;
+    if let Some(abort) = abort {
+        // Wait without reaping the process, so that its process group cannot
+        // be reused before it is unregistered
+        // Safety: siginfo is plain data, for which all zeros is valid
+        let mut info: libc::siginfo_t = unsafe { std::mem::zeroed() };
+        while unsafe {
+            libc::waitid(
+                libc::P_PID,
+                pid as libc::id_t,
+                &mut info,
+                libc::WEXITED | libc::WNOWAIT,
+            )
+        } < 0
+        {
+            let err = io::Error::last_os_error();
+            if err.kind() != io::ErrorKind::Interrupted {
+                return Err(err.into());
+            }
+        }
+        abort.lock().running = None;
+    }
+    let mut status = 0;
+    // Safety: rusage is plain data, for which all zeros is valid
+    let mut rusage: libc::rusage = unsafe { std::mem::zeroed() };
+    // The usage reported by wait4 includes descendants that were waited for
+    while unsafe { libc::wait4(pid, &mut status, 0, &mut rusage) } < 0 {
+        let err = io::Error::last_os_error();
+        if err.kind() != io::ErrorKind::Interrupted {
+            return Err(err.into());
+        }
+    }
+    let time = |t: libc::timeval| Duration::new(t.tv_sec as u64, t.tv_usec as u32 * 1000);
+    // The maximum resident set size is in bytes on macOS, and in kilobytes
+    // elsewhere
+    let scale = if cfg!(any(target_os = "macos", target_os = "ios")) {
+        1
+    } else {
+        1024
+    };
+    let usage = Usage {
+        cpu_time: time(rusage.ru_utime) + time(rusage.ru_stime),
+        peak_memory: u64::try_from(rusage.ru_maxrss).ok().map(|rss| rss * scale),
+    };
+    Ok((ExitStatus::from_raw(status), usage))
+}
+
+/// CPU time limit in whole seconds, as required by `RLIMIT_CPU`.
+#[cfg(unix)]
+fn cpu_seconds(limit: Duration) -> u64 {
+    (limit.as_secs() + u64::from(limit.subsec_nanos() > 0)).max(1)
+}
+
+/// Descendants of the process are part of the same job object, and are
+/// terminated when the job is closed after the process exits, e.g. because a
+/// limit was hit.
+#[cfg(windows)]
+fn spawn_wait(
+    command: &mut Command,
+    limits: &Limits,
+    abort: Option<&Abort>,
+) -> Result<(ExitStatus, Usage)> {
+    let job = Arc::new(job::Job::new(limits)?);
+    let mut child = spawn_registered(command, abort, |child| {
+        job.assign(child)?;
+        Ok(Some(Arc::clone(&job)))
+    })?;
+    let status = child.wait()?;
+    if let Some(abort) = abort {
+        abort.lock().running = None;
+    }
+    Ok((status, job.usage()?))
+}
+
+#[cfg(not(any(unix, windows)))]
+fn spawn_wait(
+    command: &mut Command,
+    _limits: &Limits,
+    abort: Option<&Abort>,
+) -> Result<(ExitStatus, Usage)> {
+    let mut child = spawn_registered(command, abort, |_| Ok(None))?;
+    Ok((child.wait()?, Usage::default()))
+}
+
+#[cfg(windows)]
+mod job {
+    use super::Usage;
+    use crate::engine::{Limits, Priority};
+    use std::os::windows::io::AsRawHandle;
+    use std::process::Child;
+    use std::time::Duration;
+    use std::{io, mem, ptr};
+    use windows_sys::Win32::Foundation::{CloseHandle, HANDLE};
+    use windows_sys::Win32::System::JobObjects::{
+        AssignProcessToJobObject, CreateJobObjectW, JobObjectBasicAccountingInformation,
+        JobObjectExtendedLimitInformation, QueryInformationJobObject, SetInformationJobObject,
+        TerminateJobObject, JOBOBJECT_BASIC_ACCOUNTING_INFORMATION,
+        JOBOBJECT_EXTENDED_LIMIT_INFORMATION, JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE,
+        JOB_OBJECT_LIMIT_PRIORITY_CLASS, JOB_OBJECT_LIMIT_PROCESS_MEMORY,
+        JOB_OBJECT_LIMIT_PROCESS_TIME,
+    };
+    use windows_sys::Win32::System::Threading::{BELOW_NORMAL_PRIORITY_CLASS, IDLE_PRIORITY_CLASS};
+
+    /// Job object, terminating all its processes when dropped.
+    #[derive(Debug)]
+    pub(crate) struct Job(HANDLE);
+
+    impl Job {
+        pub(crate) fn new(limits: &Limits) -> io::Result<Self> {
+            // Safety: null attributes and name are allowed
+            let handle = unsafe { CreateJobObjectW(ptr::null(), ptr::null()) };
+            if handle.is_null() {
+                return Err(io::Error::last_os_error());
+            }
+            let job = Self(handle);
+            let mut info = JOBOBJECT_EXTENDED_LIMIT_INFORMATION::default();
+            let basic = &mut info.BasicLimitInformation;
+            basic.LimitFlags = JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+            if let Some(limit) = limits.cpu_time {
+                basic.LimitFlags |= JOB_OBJECT_LIMIT_PROCESS_TIME;
+                // In units of 100 ns
+                basic.PerProcessUserTimeLimit =
+                    (limit.as_nanos() / 100).min(i64::MAX as u128) as i64;
+            }
+            if let Some(limit) = limits.memory {
+                basic.LimitFlags |= JOB_OBJECT_LIMIT_PROCESS_MEMORY;
+                info.ProcessMemoryLimit = limit.min(usize::MAX as u64) as usize;
+            }
+            if let Some(priority) = limits.priority {
+                let basic = &mut info.BasicLimitInformation;
+                basic.LimitFlags |= JOB_OBJECT_LIMIT_PRIORITY_CLASS;
+                basic.PriorityClass = match priority {
+                    Priority::BelowNormal => BELOW_NORMAL_PRIORITY_CLASS,
+                    Priority::Idle => IDLE_PRIORITY_CLASS,
+                };
+            }
+            // Safety: the information matches the class and size
+            let ok = unsafe {
+                SetInformationJobObject(
+                    job.0,
+                    JobObjectExtendedLimitInformation,
+                    &info as *const _ as *const _,
+                    mem::size_of_val(&info) as u32,
+                )
+            };
+            if ok == 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(job)
+        }
+
+        /// Add process to the job. Processes it starts afterwards are part of
+        /// the job as well.
+        pub(crate) fn assign(&self, child: &Child) -> io::Result<()> {
+            // Safety: both handles are valid
+            let ok = unsafe { AssignProcessToJobObject(self.0, child.as_raw_handle() as HANDLE) };
+            if ok == 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        }
+
+        /// Terminate all processes in the job.
+        pub(crate) fn terminate(&self) -> io::Result<()> {
+            // Safety: the handle is valid
+            if unsafe { TerminateJobObject(self.0, 1) } == 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        }
+
+        pub(super) fn usage(&self) -> io::Result<Usage> {
+            let mut accounting = JOBOBJECT_BASIC_ACCOUNTING_INFORMATION::default();
+            let mut limits = JOBOBJECT_EXTENDED_LIMIT_INFORMATION::default();
+            // Safety: the information matches the class and size
+            unsafe {
+                if QueryInformationJobObject(
+                    self.0,
+                    JobObjectBasicAccountingInformation,
+                    &mut accounting as *mut _ as *mut _,
+                    mem::size_of_val(&accounting) as u32,
+                    ptr::null_mut(),
+                ) == 0
+                    || QueryInformationJobObject(
+                        self.0,
+                        JobObjectExtendedLimitInformation,
+                        &mut limits as *mut _ as *mut _,
+                        mem::size_of_val(&limits) as u32,
+                        ptr::null_mut(),
+                    ) == 0
+                {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+            let time = accounting.TotalUserTime + accounting.TotalKernelTime;
+            Ok(Usage {
+                cpu_time: Duration::from_nanos(time as u64 * 100),
+                peak_memory: Some(limits.PeakProcessMemoryUsed as u64),
+            })
+        }
+    }
+
+    // Safety: job handles can be used from any thread
+    unsafe impl Send for Job {}
+    unsafe impl Sync for Job {}
+
+    impl Drop for Job {
+        fn drop(&mut self) {
+            // Safety: the handle is valid and not used afterwards
+            unsafe { CloseHandle(self.0) };
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn sh(script: &str) -> Command {
+        let mut command = Command::new("sh");
+        command.args(["-c", script]);
+        command
+    }
+
+    #[test]
+    fn cpu_time() {
+        let limits = Limits::new().cpu_time(Duration::from_secs(1));
+        let result = run(&mut sh("while :; do :; done"), &limits, None, None);
+        assert!(matches!(
+            result,
+            Err(Error::LimitExceeded(Resource::CpuTime))
+        ));
+    }
+
+    #[test]
+    fn cpu_time_killed() {
+        // Ignoring the soft limit gets the process killed at the hard limit
+        let limits = Limits::new()
+            .cpu_time(Duration::from_secs(1))
+            .memory(1 << 30);
+        let script = "trap '' XCPU; while :; do :; done";
+        let result = run(&mut sh(script), &limits, None, None);
+        assert!(matches!(
+            result,
+            Err(Error::LimitExceeded(Resource::CpuTime))
+        ));
+    }
+
+    #[test]
+    fn output_size() {
+        let dir = crate::temp::TempDir::new().unwrap();
+        let output = dir.path().join("output");
+        let script = format!("exec head -c 4096 /dev/zero > {}", output.display());
+        let limits = Limits::new().output_size(1024);
+        let result = run(&mut sh(&script), &limits, Some(&output), None);
+        assert!(matches!(
+            result,
+            Err(Error::LimitExceeded(Resource::OutputSize))
+        ));
+    }
+
+    #[test]
+    fn abort() {
+        let abort = Abort::new();
+        let thread_abort = abort.clone();
+        let thread = std::thread::spawn(move || {
+            run(
+                &mut sh("sleep 10 & wait"),
+                &Limits::new(),
+                None,
+                Some(&thread_abort),
+            )
+        });
+        let start = std::time::Instant::now();
+        while abort.lock().running.is_none() {
+            std::thread::yield_now();
+        }
+        abort.abort();
+        assert!(matches!(thread.join().unwrap(), Err(Error::Cancelled)));
+        assert!(start.elapsed() < Duration::from_secs(5));
+        let result = run(&mut sh("exit 0"), &Limits::new(), None, Some(&abort));
+        assert!(matches!(result, Err(Error::Cancelled)));
+    }
+
+    #[test]
+    fn peak_memory() {
+        let (_, usage) = spawn_wait(&mut sh("exit 0"), &Limits::new(), None).unwrap();
+        assert!(usage.peak_memory.unwrap() > 1024);
+    }
+
+    #[test]
+    fn unlimited() {
+        let status = run(&mut sh("exit 3"), &Limits::new(), None, None).unwrap();
+        assert_eq!(status.code(), Some(3));
+    }
+}
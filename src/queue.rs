@@ -0,0 +1,167 @@
+//! Serial execution of commands submitted from multiple threads.
+//!
+//! pstoedit is not reentrant, so commands are run one at a time. A [`Queue`]
+//! runs commands on a dedicated worker thread in order of submission, and
+//! returns a [`Handle`] for each to obtain its result.
+//!
+//! # Examples
+//! ```no_run
+//! use pstoedit::queue::Queue;
+//! use pstoedit::Command;
+//! use std::thread;
+//!
+//! pstoedit::init()?;
+//! let queue = Queue::new();
+//! thread::scope(|s| {
+//!     for name in &["a", "b", "c"] {
+//!         let queue = &queue;
+//!         s.spawn(move || {
+//!             let mut cmd = Command::new();
+//!             cmd.format("svg")?
+//!                 .input(format!("{}.ps", name))?
+//!                 .output(format!("{}.svg", name))?;
+//!             queue.submit(cmd).wait()
+//!         });
+//!     }
+//! });
+//! # Ok::<(), pstoedit::Error>(())
+//! ```
+
+use crate::engine::{Engine, InProcess};
+use crate::{Command, Error, Result};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::thread::{self, JoinHandle};
+
+/// Command to run and channel to send its result on.
+type Job = (Command, Sender<Result<()>>);
+
+/// Queue running commands one at a time on a worker thread.
+///
+/// See [module-level documentation][self] for more details.
+#[derive(Debug)]
+pub struct Queue {
+    sender: Option<Sender<Job>>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl Queue {
+    /// Create queue running commands in-process.
+    pub fn new() -> Self {
+        Self::with_engine(InProcess)
+    }
+
+    /// Create queue running commands using an engine.
+    pub fn with_engine<E>(engine: E) -> Self
+    where
+        E: Engine + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let worker = thread::spawn(move || {
+            for (command, result) in receiver {
+                // The handle may have been dropped, which is fine
+                let _ = result.send(engine.run(&command));
+            }
+        });
+        Self {
+            sender: Some(sender),
+            worker: Some(worker),
+        }
+    }
+
+    /// Submit command to be run after all previously submitted commands.
+    pub fn submit(&self, command: Command) -> Handle {
+        let (sender, receiver) = mpsc::channel();
+        if let Some(queue) = &self.sender {
+            // If the worker has stopped, the handle reports this when waiting
+            let _ = queue.send((command, sender));
+        }
+        Handle(receiver)
+    }
+}
+
+impl Default for Queue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for Queue {
+    /// Wait until all submitted commands have been run.
+    fn drop(&mut self) {
+        drop(self.sender.take());
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// Handle to the result of a command submitted to a [`Queue`].
+#[derive(Debug)]
+pub struct Handle(Receiver<Result<()>>);
+
+impl Handle {
+    /// Wait until the command has been run and return its result.
+    ///
+    /// # Errors
+    /// Errors from running the command, or
+    /// [`QueueStopped`][Error::QueueStopped] if the worker thread stopped
+    /// before running it.
+    pub fn wait(self) -> Result<()> {
+        self.0.recv().unwrap_or(Err(Error::QueueStopped))
+    }
+
+    /// Return the result of the command if it has been run, without waiting.
+    ///
+    /// # Errors
+    /// See [`wait`][Handle::wait].
+    pub fn try_wait(&self) -> Option<Result<()>> {
+        match self.0.try_recv() {
+            Ok(result) => Some(result),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Err(Error::QueueStopped)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Engine that fails commands with more than one argument.
+    struct ArgCount;
+
+    impl Engine for ArgCount {
+        fn run(&self, command: &Command) -> Result<()> {
+            match command.argv().len() {
+                1 => Ok(()),
+                n => Err(Error::PstoeditError(n as _)),
+            }
+        }
+    }
+
+    #[test]
+    fn results() {
+        let queue = Queue::with_engine(ArgCount);
+        let ok = queue.submit(Command::new());
+        let mut cmd = Command::new();
+        cmd.arg("-gstest").unwrap();
+        let err = queue.submit(cmd);
+        assert!(matches!(err.wait(), Err(Error::PstoeditError(2))));
+        assert!(ok.wait().is_ok());
+    }
+
+    #[test]
+    fn panicking_engine() {
+        struct Panic;
+        impl Engine for Panic {
+            fn run(&self, _: &Command) -> Result<()> {
+                panic!("engine failure")
+            }
+        }
+        let queue = Queue::with_engine(Panic);
+        let first = queue.submit(Command::new());
+        assert!(matches!(first.wait(), Err(Error::QueueStopped)));
+        let second = queue.submit(Command::new());
+        assert!(matches!(second.wait(), Err(Error::QueueStopped)));
+    }
+}
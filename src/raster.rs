@@ -0,0 +1,61 @@
+use crate::engine::Limits;
+use crate::temp::TempDir;
+use crate::{gs, Result};
+use image::DynamicImage;
+use std::path::Path;
+
+/// Render the first page of a document to a bitmap using ghostscript.
+///
+/// This does not involve pstoedit, and is useful to generate previews next to
+/// vector conversions. The ghostscript executable is searched in `PATH`.
+///
+/// # Examples
+/// ```no_run
+/// let preview = pstoedit::rasterize("input.ps", 72)?;
+/// println!("{}x{}", preview.width(), preview.height());
+/// # Ok::<(), pstoedit::Error>(())
+/// ```
+///
+/// # Errors
+/// - [`MissingGhostscript`][crate::Error::MissingGhostscript] if ghostscript
+///   cannot be started.
+/// - [`GhostscriptError`][crate::Error::GhostscriptError] if ghostscript fails.
+/// - [`ImageError`][crate::Error::ImageError] if the bitmap cannot be decoded.
+#[cfg_attr(docsrs, doc(cfg(feature = "image")))]
+pub fn rasterize<P>(input: P, dpi: u32) -> Result<DynamicImage>
+where
+    P: AsRef<Path>,
+{
+    let dir = TempDir::new()?;
+    let output = dir.path().join("page.png");
+    let resolution = format!("-r{}", dpi);
+    gs::run(
+        None,
+        [
+            "-sDEVICE=png16m".as_ref(),
+            "-dFirstPage=1".as_ref(),
+            "-dLastPage=1".as_ref(),
+            resolution.as_ref(),
+            gs::output_arg(&output).as_os_str(),
+            input.as_ref().as_os_str(),
+        ],
+        &Limits::new(),
+    )?;
+    Ok(image::open(output)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn letter_page() {
+        let dir = TempDir::new().unwrap();
+        let input = dir.path().join("input.ps");
+        let ps = "%!PS\n<< /PageSize [612 792] >> setpagedevice\n0 0 100 100 rectfill\nshowpage\n";
+        fs::write(&input, ps).unwrap();
+        let image = rasterize(&input, 36).unwrap();
+        assert_eq!((image.width(), image.height()), (306, 396));
+    }
+}
@@ -0,0 +1,59 @@
+use crate::Warning;
+use std::time::Duration;
+
+/// Report on a single run of a [`Command`][crate::Command].
+///
+/// Obtained from [`Command::run_report`][crate::Command::run_report] or
+/// [`Subprocess::run_report`][crate::engine::Subprocess::run_report].
+#[derive(Clone, Debug)]
+pub struct RunReport {
+    pub(crate) elapsed: Duration,
+    pub(crate) output_size: Option<u64>,
+    pub(crate) pages: Option<u32>,
+    pub(crate) warnings: Vec<Warning>,
+    pub(crate) cached: bool,
+}
+
+impl RunReport {
+    /// Time spent in pstoedit.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Size of the output file in bytes.
+    ///
+    /// This is `None` if no output file was set using
+    /// [`Command::output`][crate::Command::output], or if it could not be
+    /// inspected after the run.
+    pub fn output_size(&self) -> Option<u64> {
+        self.output_size
+    }
+
+    /// Number of pages of the input.
+    ///
+    /// pstoedit does not report the pages it processed, so this is the number
+    /// declared by the `%%Pages` comment of PostScript and EPS input, or
+    /// otherwise the number of its `%%Page` comments, see
+    /// [`dsc::Document`][crate::dsc::Document]. It is `None` for PDF input
+    /// and for input without these comments, whose pages can be counted
+    /// using [`page_count`][crate::page_count] instead.
+    pub fn pages(&self) -> Option<u32> {
+        self.pages
+    }
+
+    /// Warnings reported during the run, in order.
+    ///
+    /// An empty list means that no warnings were recognized, not that the
+    /// output is necessarily faithful to the input.
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+
+    /// Whether the run was skipped because the output file was up to date,
+    /// see [`Command::incremental`][crate::Command::incremental].
+    ///
+    /// Skipped runs take no time and report no warnings.
+    pub fn cached(&self) -> bool {
+        self.cached
+    }
+}
@@ -0,0 +1,101 @@
+use crate::Error;
+use std::time::Duration;
+
+/// Policy to retry failed runs of a [`Command`][crate::Command].
+///
+/// Failures that may be transient, such as a crashing interpreter or a race on
+/// temporary files, cannot be told apart from other pstoedit failures through
+/// its C API. By default, any [`PstoeditError`][Error::PstoeditError],
+/// [`SubprocessError`][Error::SubprocessError] or [`IoError`][Error::IoError]
+/// is retried, which can be narrowed down using
+/// [`condition`][RetryPolicy::condition].
+///
+/// The delay between attempts grows exponentially, starting at 100 ms and
+/// doubling after every attempt by default.
+///
+/// # Examples
+/// See [`Command::retry`][crate::Command::retry].
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    attempts: u32,
+    delay: Duration,
+    backoff: u32,
+    condition: fn(&Error) -> bool,
+}
+
+impl RetryPolicy {
+    /// Create policy allowing `attempts` runs in total.
+    pub fn new(attempts: u32) -> Self {
+        Self {
+            attempts,
+            delay: Duration::from_millis(100),
+            backoff: 2,
+            condition: is_transient,
+        }
+    }
+
+    /// Set the delay before the first retry.
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Set the factor the delay is multiplied with after every retry.
+    pub fn backoff(mut self, factor: u32) -> Self {
+        self.backoff = factor;
+        self
+    }
+
+    /// Set the condition for an error to be retried.
+    pub fn condition(mut self, condition: fn(&Error) -> bool) -> Self {
+        self.condition = condition;
+        self
+    }
+
+    /// Whether to retry after the given failed attempt, starting at 1.
+    pub(crate) fn should_retry(&self, attempt: u32, err: &Error) -> bool {
+        attempt < self.attempts && (self.condition)(err)
+    }
+
+    /// Delay after the given failed attempt, starting at 1.
+    pub(crate) fn delay_after(&self, attempt: u32) -> Duration {
+        let factor = self.backoff.saturating_pow(attempt - 1);
+        self.delay.checked_mul(factor).unwrap_or(Duration::MAX)
+    }
+}
+
+/// Default condition for errors to be retried.
+fn is_transient(err: &Error) -> bool {
+    matches!(
+        err,
+        Error::PstoeditError(_) | Error::SubprocessError(_) | Error::IoError(_)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attempts() {
+        let policy = RetryPolicy::new(3);
+        let err = Error::PstoeditError(1);
+        assert!(policy.should_retry(1, &err));
+        assert!(policy.should_retry(2, &err));
+        assert!(!policy.should_retry(3, &err));
+        assert!(!policy.should_retry(1, &Error::NotInitialized));
+        let policy = policy.condition(|_| true);
+        assert!(policy.should_retry(1, &Error::NotInitialized));
+    }
+
+    #[test]
+    fn backoff() {
+        let policy = RetryPolicy::new(5)
+            .delay(Duration::from_millis(10))
+            .backoff(3);
+        assert_eq!(policy.delay_after(1), Duration::from_millis(10));
+        assert_eq!(policy.delay_after(3), Duration::from_millis(90));
+        let policy = RetryPolicy::new(3).delay(Duration::MAX);
+        assert_eq!(policy.delay_after(2), Duration::MAX);
+    }
+}
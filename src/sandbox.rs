@@ -0,0 +1,237 @@
+//! Landlock sandbox for child processes.
+
+use crate::Result;
+use landlock::{
+    path_beneath_rules, Access, AccessFs, AccessNet, Ruleset, RulesetAttr, RulesetCreatedAttr,
+    RulesetStatus, ABI,
+};
+use std::io;
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Landlock ABI version requested; features of later versions are used where
+/// the kernel supports them.
+const ABI_VERSION: ABI = ABI::V4;
+
+/// Directories needed to run pstoedit and ghostscript, which may be read but
+/// not written.
+const SYSTEM_PATHS: &[&str] = &[
+    "/bin",
+    "/etc",
+    "/lib",
+    "/lib32",
+    "/lib64",
+    "/nix/store",
+    "/opt",
+    "/sbin",
+    "/usr",
+    "/dev/urandom",
+];
+
+/// Restrictions on the file system and network access of
+/// [`Subprocess`][crate::engine::Subprocess] runs, using Landlock.
+///
+/// The process may read system directories, such as `/usr` and `/etc`, and
+/// the input file, write the output file and `/dev/null`, and access the
+/// temporary directory. Unless a temporary directory is set using
+/// [`Command::temp_dir`][crate::Command::temp_dir] or
+/// [`set_temp_dir`][crate::set_temp_dir], that is a private directory created
+/// for the run, rather than the shared temporary directory of the system.
+/// Other file system access, including `/proc`, and TCP connections are
+/// denied. Additional paths can be allowed, e.g. for fonts outside the system
+/// directories.
+///
+/// Landlock requires Linux 5.13 or later. On older kernels, or kernels
+/// without Landlock enabled, runs fail with an
+/// [`IoError`][crate::Error::IoError] of kind
+/// [`Unsupported`][std::io::ErrorKind::Unsupported], unless running without
+/// restrictions is allowed using
+/// [`allow_unsandboxed`][Sandbox::allow_unsandboxed]. Restrictions that the
+/// kernel only partially supports are applied as far as possible, e.g. TCP
+/// restrictions require Linux 6.7 or later.
+///
+/// # Examples
+/// ```no_run
+/// use pstoedit::engine::{Engine, Sandbox, Subprocess};
+/// use pstoedit::Command;
+///
+/// let engine = Subprocess::new().sandbox(Sandbox::new().allow_read("/srv/fonts"));
+/// let mut cmd = Command::new();
+/// cmd.format("svg")?.input("untrusted.ps")?.output("output.svg")?;
+/// engine.run(&cmd)?;
+/// # Ok::<(), pstoedit::Error>(())
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Sandbox {
+    read: Vec<PathBuf>,
+    write: Vec<PathBuf>,
+    allow_unsandboxed: bool,
+}
+
+impl Sandbox {
+    /// Create sandbox allowing only the default paths.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allow reading files beneath `path`.
+    pub fn allow_read<P>(mut self, path: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.read.push(path.into());
+        self
+    }
+
+    /// Allow reading and writing files beneath `path`.
+    pub fn allow_write<P>(mut self, path: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        self.write.push(path.into());
+        self
+    }
+
+    /// Run the process without restrictions if the kernel does not support
+    /// Landlock, instead of failing.
+    pub fn allow_unsandboxed(mut self, enable: bool) -> Self {
+        self.allow_unsandboxed = enable;
+        self
+    }
+
+    /// Restrict `command` when it is spawned.
+    ///
+    /// The output file is created beforehand, so that only that file, rather
+    /// than its directory, needs to be writable.
+    pub(crate) fn apply(
+        &self,
+        command: &mut Command,
+        input: Option<&Path>,
+        output: Option<&Path>,
+        temp_dir: &Path,
+    ) -> Result<()> {
+        if let Some(output) = output {
+            std::fs::OpenOptions::new()
+                .write(true)
+                .create(true)
+                .truncate(false)
+                .open(output)?;
+        }
+        let read = SYSTEM_PATHS
+            .iter()
+            .map(Path::new)
+            .chain(input)
+            .chain(self.read.iter().map(PathBuf::as_path));
+        let write = std::iter::once(Path::new("/dev/null"))
+            .chain(Some(temp_dir))
+            .chain(output)
+            .chain(self.write.iter().map(PathBuf::as_path));
+        let ruleset = Ruleset::default()
+            .handle_access(AccessFs::from_all(ABI_VERSION))?
+            .handle_access(AccessNet::from_all(ABI_VERSION))?
+            .create()?
+            .add_rules(path_beneath_rules(read, AccessFs::from_read(ABI_VERSION)))?
+            .add_rules(path_beneath_rules(write, AccessFs::from_all(ABI_VERSION)))?;
+        let mut ruleset = Some(ruleset);
+        let allow_unsandboxed = self.allow_unsandboxed;
+        // Only the OS error code of errors is passed to the parent process.
+        // Safety: restricting only makes system calls, and does not allocate
+        // for the errors returned
+        unsafe {
+            command.pre_exec(move || {
+                let ruleset = match ruleset.take() {
+                    Some(ruleset) => ruleset,
+                    None => return Ok(()),
+                };
+                let status = ruleset
+                    .restrict_self()
+                    .map_err(|_| io::Error::from_raw_os_error(libc::EPERM))?;
+                if status.ruleset == RulesetStatus::NotEnforced && !allow_unsandboxed {
+                    return Err(io::Error::from_raw_os_error(libc::ENOSYS));
+                }
+                Ok(())
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::{Engine, Sandbox, Subprocess};
+    use crate::temp::TempDir;
+    use crate::{path_bytes, Command, Error};
+    use std::fs;
+    use std::io::ErrorKind;
+
+    #[test]
+    fn sandbox() {
+        // The driver is looked up by the extension of the output file
+        crate::init().unwrap();
+        let dir = TempDir::new().unwrap();
+        let temp_dir = TempDir::new().unwrap();
+        let input = dir.path().join("input.ps");
+        let output = dir.path().join("output.ps");
+        let secret = dir.path().join("secret");
+        fs::write(&input, "%!PS\n").unwrap();
+        fs::write(&secret, "secret").unwrap();
+        let engine = Subprocess::new().program("sh").sandbox(Sandbox::new());
+        let run = |engine: &Subprocess, script: &str| {
+            let mut cmd = Command::new();
+            cmd.args_slice(&["-c", script]).unwrap();
+            cmd.input(path_bytes(&input).unwrap()).unwrap();
+            cmd.output(path_bytes(&output).unwrap()).unwrap();
+            cmd.temp_dir(temp_dir.path());
+            engine.run(&cmd)
+        };
+        let copy = "cat \"$0\" > \"$1\" && echo > \"$TMPDIR/scratch\"";
+        if landlock_enabled() {
+            run(&engine, copy).unwrap();
+            assert_eq!(fs::read(&output).unwrap(), b"%!PS\n");
+            run(&engine, &format!("cat {} > \"$1\"", secret.display())).unwrap_err();
+            run(
+                &engine,
+                &format!("echo > {}", dir.path().join("other").display()),
+            )
+            .unwrap_err();
+            run(&engine, "cat /proc/1/cmdline > \"$1\"").unwrap_err();
+        } else {
+            let result = run(&engine, copy);
+            assert!(
+                matches!(result, Err(Error::IoError(err)) if err.kind() == ErrorKind::Unsupported)
+            );
+            let engine = Subprocess::new()
+                .program("sh")
+                .sandbox(Sandbox::new().allow_unsandboxed(true));
+            run(&engine, copy).unwrap();
+            assert_eq!(fs::read(&output).unwrap(), b"%!PS\n");
+        }
+    }
+
+    #[test]
+    fn private_temp_dir() {
+        let engine = Subprocess::new()
+            .program("sh")
+            .sandbox(Sandbox::new().allow_unsandboxed(true));
+        let mut cmd = Command::new();
+        let script = "case \"$TMPDIR\" in */pstoedit-rs-*) test -d \"$TMPDIR\";; *) exit 1;; esac";
+        cmd.args_slice(&["-c", script]).unwrap();
+        engine.run(&cmd).unwrap();
+    }
+
+    /// Whether the kernel supports Landlock, by querying its ABI version.
+    fn landlock_enabled() -> bool {
+        const LANDLOCK_CREATE_RULESET_VERSION: libc::c_uint = 1;
+        // Safety: a null attribute is required when querying the version
+        let version = unsafe {
+            libc::syscall(
+                libc::SYS_landlock_create_ruleset,
+                std::ptr::null::<libc::c_void>(),
+                0,
+                LANDLOCK_CREATE_RULESET_VERSION,
+            )
+        };
+        version >= 1
+    }
+}
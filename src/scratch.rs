@@ -0,0 +1,106 @@
+//! Scratch directories holding the files of a run.
+
+use crate::temp::TempDir;
+use crate::{Command, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Per-run scratch directory, containing a copy of the input, the output
+/// directory `out` and the temporary directory `tmp`.
+#[derive(Debug)]
+pub(crate) struct ScratchDir {
+    dir: TempDir,
+    output: Option<PathBuf>,
+}
+
+impl ScratchDir {
+    /// Create scratch directory for `command`, returning a copy of the command
+    /// reading and writing files in it.
+    pub(crate) fn new(command: &Command) -> Result<(Self, Command)> {
+        let dir = TempDir::new()?;
+        fs::create_dir(dir.path().join("out"))?;
+        fs::create_dir(dir.path().join("tmp"))?;
+        let mut redirected = command.clone();
+        redirected.temp_dir(dir.path().join("tmp"));
+        if let Some(input) = command.input_path().and_then(crate::cstr_path) {
+            let copy = dir.path().join(file_name(input)?);
+            fs::copy(input, &copy)?;
+            redirected.input(crate::path_bytes(&copy)?)?;
+        }
+        let output = command.output_path().and_then(crate::cstr_path);
+        if let Some(output) = output {
+            let path = dir.path().join("out").join(file_name(output)?);
+            redirected.output(crate::path_bytes(&path)?)?;
+        }
+        let scratch = Self {
+            dir,
+            output: output.map(Path::to_owned),
+        };
+        Ok((scratch, redirected))
+    }
+
+    /// Directory to run the command in.
+    pub(crate) fn path(&self) -> &Path {
+        self.dir.path()
+    }
+
+    /// Move files written to the output directory next to the original output
+    /// file, replacing existing files.
+    ///
+    /// This includes files written besides the output file, such as images
+    /// or the pages of split output.
+    pub(crate) fn collect(&self) -> Result<Vec<PathBuf>> {
+        let dst = match self.output.as_deref().and_then(Path::parent) {
+            Some(dst) => dst,
+            None => return Ok(Vec::new()),
+        };
+        let mut collected = Vec::new();
+        for entry in fs::read_dir(self.dir.path().join("out"))? {
+            let from = entry?.path();
+            let to = dst.join(file_name(&from)?);
+            // Renaming fails if the temporary directory is on another file system
+            if fs::rename(&from, &to).is_err() {
+                fs::copy(&from, &to)?;
+            }
+            collected.push(to);
+        }
+        Ok(collected)
+    }
+}
+
+fn file_name(path: &Path) -> Result<&std::ffi::OsStr> {
+    path.file_name().ok_or_else(|| {
+        let msg = format!("{} is not a file", path.display());
+        std::io::Error::new(std::io::ErrorKind::InvalidInput, msg).into()
+    })
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use crate::engine::{Engine, Subprocess};
+    use crate::temp::TempDir;
+    use crate::{path_bytes, Command};
+    use std::fs;
+
+    #[test]
+    fn scratch_dir() {
+        let src = TempDir::new().unwrap();
+        let dst = TempDir::new().unwrap();
+        let input = src.path().join("input.ps");
+        let output = dst.path().join("output.ps");
+        fs::write(&input, "%!PS\n").unwrap();
+        let script = "test \"$0\" = \"$PWD/input.ps\" && test \"$TMPDIR\" = \"$PWD/tmp\" \
+            && cp input.ps \"$1\" && echo side > out/side.txt";
+        let mut cmd = Command::new();
+        cmd.args_slice(&["-c", script]).unwrap();
+        cmd.input(path_bytes(&input).unwrap()).unwrap();
+        cmd.output(path_bytes(&output).unwrap()).unwrap();
+        Subprocess::new()
+            .program("sh")
+            .scratch_dir(true)
+            .run(&cmd)
+            .unwrap();
+        assert_eq!(fs::read(&output).unwrap(), b"%!PS\n");
+        assert_eq!(fs::read(dst.path().join("side.txt")).unwrap(), b"side\n");
+    }
+}
@@ -0,0 +1,47 @@
+use crate::temp::TempDir;
+use crate::{Command, Result};
+use std::fs;
+use std::path::Path;
+
+/// Convert a document to SVG and parse the result.
+///
+/// The document is converted using the `svg` driver, and the output is parsed
+/// by [`usvg`] with default options, yielding a tree that is ready to render.
+///
+/// # Examples
+/// ```no_run
+/// pstoedit::init()?;
+/// let tree = pstoedit::convert_to_svg_tree("input.ps")?;
+/// println!("{:?}", tree.size());
+/// # Ok::<(), pstoedit::Error>(())
+/// ```
+///
+/// # Errors
+/// - Errors from running pstoedit, see [`Command::run`].
+/// - [`IoError`][crate::Error::IoError] if the intermediate file cannot be
+///   created or read.
+/// - [`SvgError`][crate::Error::SvgError] if the output cannot be parsed.
+#[cfg_attr(docsrs, doc(cfg(feature = "usvg")))]
+pub fn convert_to_svg_tree<P>(input: P) -> Result<usvg::Tree>
+where
+    P: AsRef<Path>,
+{
+    let dir = TempDir::new()?;
+    let output = dir.path().join("output.svg");
+    Command::new()
+        .format("svg")?
+        .input(crate::path_bytes(input.as_ref())?)?
+        .output(crate::path_bytes(&output)?)?
+        .run()?;
+    let data = fs::read(output)?;
+    Ok(usvg::Tree::from_data(&data, &usvg::Options::default())?)
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn parse_error() {
+        let err = usvg::Tree::from_data(b"not svg", &usvg::Options::default()).unwrap_err();
+        assert!(matches!(crate::Error::from(err), crate::Error::SvgError(_)));
+    }
+}
@@ -0,0 +1,259 @@
+//! Helpers for testing of conversions.
+//!
+//! [`MockEngine`] records commands instead of running them, so code using an
+//! [`Engine`] can be tested without pstoedit or ghostscript.
+//!
+//! The [`fixtures`] module contains small PostScript documents to convert.
+//!
+//! [`Golden`] converts fixture files and compares the output with stored
+//! golden files. Lines that typically differ between runs, such as creation
+//! dates, are ignored. If the environment variable `PSTOEDIT_UPDATE_GOLDENS`
+//! is set, the output is written to the golden files instead.
+//!
+//! # Examples
+//! ```no_run
+//! use pstoedit::test_support::Golden;
+//!
+//! pstoedit::init()?;
+//! Golden::new("svg").check("tests/fixtures/plot.ps", "tests/goldens/plot.svg")?;
+//! # Ok::<(), pstoedit::Error>(())
+//! ```
+
+pub mod fixtures;
+
+use crate::engine::Engine;
+use crate::temp::TempDir;
+use crate::{Command, Error, Result};
+use std::os::raw::c_int;
+use std::path::Path;
+use std::sync::Mutex;
+use std::{env, fs};
+
+/// Environment variable that causes golden files to be updated.
+pub const UPDATE_VAR: &str = "PSTOEDIT_UPDATE_GOLDENS";
+
+/// Patterns of lines that are ignored by default.
+const DEFAULT_VOLATILE: &[&str] = &["CreationDate", "Creator", "Producer", "pstoedit"];
+
+/// Golden-file comparison of conversions to a single format.
+///
+/// See [module-level documentation][self] for more details.
+#[derive(Clone, Debug)]
+pub struct Golden {
+    format: String,
+    volatile: Vec<String>,
+}
+
+impl Golden {
+    /// Create comparison for a format, ignoring lines containing
+    /// `CreationDate`, `Creator`, `Producer`, or `pstoedit`.
+    pub fn new<S>(format: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            format: format.into(),
+            volatile: DEFAULT_VOLATILE.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    /// Additionally ignore lines containing `pattern`.
+    pub fn volatile<S>(&mut self, pattern: S) -> &mut Self
+    where
+        S: Into<String>,
+    {
+        self.volatile.push(pattern.into());
+        self
+    }
+
+    /// Whether `actual` equals `expected`, apart from ignored lines.
+    pub fn matches(&self, expected: &[u8], actual: &[u8]) -> bool {
+        self.lines(expected).eq(self.lines(actual))
+    }
+
+    /// Convert `fixture` and compare the output to `golden`.
+    ///
+    /// # Errors
+    /// Errors from running pstoedit, or an [`IoError`][crate::Error::IoError]
+    /// if the golden file cannot be read or written.
+    ///
+    /// # Panics
+    /// If the output does not match the golden file.
+    pub fn check<P, Q>(&self, fixture: P, golden: Q) -> Result<()>
+    where
+        P: AsRef<Path>,
+        Q: AsRef<Path>,
+    {
+        let (fixture, golden) = (fixture.as_ref(), golden.as_ref());
+        let dir = TempDir::new()?;
+        let output = dir.path().join("output");
+        Command::new()
+            .format(self.format.as_str())?
+            .input(crate::path_bytes(fixture)?)?
+            .output(crate::path_bytes(&output)?)?
+            .run()?;
+        let actual = fs::read(&output)?;
+        if env::var_os(UPDATE_VAR).is_some() {
+            fs::write(golden, actual)?;
+            return Ok(());
+        }
+        let expected = fs::read(golden)?;
+        if !self.matches(&expected, &actual) {
+            panic!(
+                "conversion of {} does not match {} (set {} to update)",
+                fixture.display(),
+                golden.display(),
+                UPDATE_VAR
+            );
+        }
+        Ok(())
+    }
+
+    /// Iterator over lines that are not ignored.
+    fn lines<'a>(&'a self, data: &'a [u8]) -> impl Iterator<Item = &'a [u8]> + 'a {
+        data.split(|&b| b == b'\n')
+            .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+            .filter(move |line| {
+                !self
+                    .volatile
+                    .iter()
+                    .any(|pattern| contains(line, pattern.as_bytes()))
+            })
+    }
+}
+
+/// Single command recorded by [`MockEngine`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Invocation {
+    /// All arguments, starting with the program name.
+    pub args: Vec<String>,
+    /// Ghostscript executable, if set.
+    pub gs: Option<String>,
+}
+
+/// Engine that records commands instead of running pstoedit.
+///
+/// If an output file is set for a command, the canned output is written to it.
+///
+/// # Examples
+/// ```
+/// use pstoedit::engine::Engine;
+/// use pstoedit::test_support::MockEngine;
+/// use pstoedit::Command;
+///
+/// let engine = MockEngine::new();
+/// engine.run(Command::new().arg("-gstest")?)?;
+/// assert_eq!(engine.invocations()[0].args, ["pstoedit", "-gstest"]);
+/// # Ok::<(), pstoedit::Error>(())
+/// ```
+#[derive(Debug, Default)]
+pub struct MockEngine {
+    output: Vec<u8>,
+    error_code: Option<c_int>,
+    invocations: Mutex<Vec<Invocation>>,
+}
+
+impl MockEngine {
+    /// Create engine that succeeds and writes empty output files.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Write `output` to the output file of every command.
+    pub fn with_output<B>(mut self, output: B) -> Self
+    where
+        B: Into<Vec<u8>>,
+    {
+        self.output = output.into();
+        self
+    }
+
+    /// Fail every command with [`PstoeditError`][Error::PstoeditError].
+    pub fn with_error_code(mut self, code: c_int) -> Self {
+        self.error_code = Some(code);
+        self
+    }
+
+    /// Commands run so far.
+    pub fn invocations(&self) -> Vec<Invocation> {
+        self.invocations.lock().unwrap().clone()
+    }
+}
+
+impl Engine for MockEngine {
+    fn run(&self, command: &Command) -> Result<()> {
+        let invocation = Invocation {
+            args: command
+                .argv()
+                .iter()
+                .map(|arg| arg.to_string_lossy().into_owned())
+                .collect(),
+            gs: command
+                .gs_path()
+                .map(|gs| gs.to_string_lossy().into_owned()),
+        };
+        self.invocations.lock().unwrap().push(invocation);
+        if let Some(code) = self.error_code {
+            return Err(Error::PstoeditError(code));
+        }
+        if let Some(path) = command.output_path().and_then(crate::cstr_path) {
+            fs::write(path, &self.output)?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether `haystack` contains `needle`.
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    needle.is_empty() || haystack.windows(needle.len()).any(|w| w == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mock_engine() {
+        let dir = TempDir::new().unwrap();
+        let output = dir.path().join("output.svg");
+        let engine = MockEngine::new().with_output("<svg/>");
+        let mut cmd = Command::new();
+        cmd.format("svg")
+            .unwrap()
+            .input("input.ps")
+            .unwrap()
+            .output(crate::path_bytes(&output).unwrap())
+            .unwrap();
+        engine.run(&cmd).unwrap();
+        assert_eq!(fs::read(&output).unwrap(), b"<svg/>");
+        let invocations = engine.invocations();
+        assert_eq!(invocations.len(), 1);
+        assert_eq!(invocations[0].args[1..4], ["-f", "svg", "input.ps"]);
+        assert_eq!(invocations[0].gs, None);
+    }
+
+    #[test]
+    fn mock_engine_error() {
+        let engine = MockEngine::new().with_error_code(1);
+        let result = engine.run(&Command::new());
+        assert!(matches!(result, Err(Error::PstoeditError(1))));
+        assert_eq!(engine.invocations().len(), 1);
+    }
+
+    #[test]
+    fn ignore_volatile() {
+        let golden = Golden::new("svg");
+        let expected = b"<!-- CreationDate: 2020 -->\n<svg/>\n";
+        let actual = b"<!-- CreationDate: 2024 -->\r\n<svg/>\r\n";
+        assert!(golden.matches(expected, actual));
+        assert!(!golden.matches(expected, b"<svg></svg>\n"));
+    }
+
+    #[test]
+    fn custom_volatile() {
+        let mut golden = Golden::new("svg");
+        assert!(!golden.matches(b"id=1\n", b"id=2\n"));
+        golden.volatile("id=");
+        assert!(golden.matches(b"id=1\n", b"id=2\n"));
+    }
+}
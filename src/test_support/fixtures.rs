@@ -0,0 +1,145 @@
+//! Small PostScript documents for conversion tests.
+//!
+//! Every document is a valid DSC-conforming PostScript or EPS file. Use
+//! [`write_temp`] to obtain a file that can be passed to pstoedit.
+//!
+//! # Examples
+//! ```no_run
+//! use pstoedit::test_support::fixtures;
+//! use pstoedit::Command;
+//!
+//! pstoedit::init()?;
+//! let input = fixtures::write_temp(fixtures::TEXT, "ps")?;
+//! Command::new()
+//!     .format("psf")?
+//!     .input(input.path().to_str().unwrap())?
+//!     .output("output.fps")?
+//!     .run()?;
+//! # Ok::<(), pstoedit::Error>(())
+//! ```
+
+use crate::temp::TempDir;
+use crate::Result;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Single page with a triangle and a few stroked lines.
+pub const LINES: &[u8] = b"%!PS-Adobe-3.0
+%%BoundingBox: 0 0 200 200
+%%Pages: 1
+%%EndComments
+%%Page: 1 1
+2 setlinewidth
+newpath 20 20 moveto 180 20 lineto 100 160 lineto closepath stroke
+1 0 0 setrgbcolor
+newpath 20 180 moveto 180 180 lineto stroke
+0 0 1 setrgbcolor
+newpath 100 20 moveto 100 180 lineto stroke
+showpage
+%%EOF
+";
+
+/// Single page with a line of text.
+pub const TEXT: &[u8] = b"%!PS-Adobe-3.0
+%%BoundingBox: 0 0 300 100
+%%Pages: 1
+%%EndComments
+%%Page: 1 1
+/Helvetica findfont 24 scalefont setfont
+20 40 moveto (Hello, pstoedit!) show
+showpage
+%%EOF
+";
+
+/// Single page with a 2 by 2 pixel RGB image.
+pub const IMAGE: &[u8] = b"%!PS-Adobe-3.0
+%%BoundingBox: 0 0 100 100
+%%Pages: 1
+%%EndComments
+%%Page: 1 1
+gsave
+10 10 translate 80 80 scale
+2 2 8 [2 0 0 -2 0 2] <ff000000ff000000ffffffff> false 3 colorimage
+grestore
+showpage
+%%EOF
+";
+
+/// Three pages, each with a filled square of a different color.
+pub const MULTI_PAGE: &[u8] = b"%!PS-Adobe-3.0
+%%BoundingBox: 0 0 100 100
+%%Pages: 3
+%%EndComments
+%%Page: 1 1
+1 0 0 setrgbcolor 10 10 80 80 rectfill
+showpage
+%%Page: 2 2
+0 1 0 setrgbcolor 10 10 80 80 rectfill
+showpage
+%%Page: 3 3
+0 0 1 setrgbcolor 10 10 80 80 rectfill
+showpage
+%%EOF
+";
+
+/// Encapsulated PostScript with a circle and a bounding box.
+pub const EPS: &[u8] = b"%!PS-Adobe-3.0 EPSF-3.0
+%%BoundingBox: 10 10 110 110
+%%HiResBoundingBox: 10.0 10.0 110.0 110.0
+%%Pages: 1
+%%EndComments
+newpath 60 60 50 0 360 arc closepath
+0.5 setgray fill
+%%EOF
+";
+
+/// Temporary file that is removed when dropped.
+#[derive(Debug)]
+pub struct TempFile {
+    path: PathBuf,
+    _dir: TempDir,
+}
+
+impl TempFile {
+    /// Path of the file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// Write `data` to a temporary file with the given extension.
+///
+/// # Errors
+/// [`IoError`][crate::Error::IoError] if the file cannot be written.
+pub fn write_temp<B>(data: B, extension: &str) -> Result<TempFile>
+where
+    B: AsRef<[u8]>,
+{
+    let dir = TempDir::new()?;
+    let path = dir.path().join("fixture").with_extension(extension);
+    fs::write(&path, data)?;
+    Ok(TempFile { path, _dir: dir })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn temp_file() {
+        let file = write_temp(EPS, "eps").unwrap();
+        let path = file.path().to_owned();
+        assert_eq!(path.extension().unwrap(), "eps");
+        assert_eq!(fs::read(&path).unwrap(), EPS);
+        drop(file);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn headers() {
+        for fixture in &[LINES, TEXT, IMAGE, MULTI_PAGE] {
+            assert!(fixture.starts_with(b"%!PS-Adobe-3.0\n"));
+        }
+        assert!(EPS.starts_with(b"%!PS-Adobe-3.0 EPSF-3.0\n"));
+    }
+}
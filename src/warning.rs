@@ -0,0 +1,123 @@
+use std::fmt;
+
+/// Non-fatal problem reported during a successful run.
+///
+/// Warnings indicate that the output may be degraded, e.g. because a font
+/// was substituted or the driver does not support part of the document. They
+/// are returned by [`RunReport::warnings`][crate::RunReport::warnings].
+///
+/// Warnings are recognized in the diagnostic output of pstoedit and
+/// ghostscript, which is not a stable interface, so recognition is best
+/// effort.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+#[non_exhaustive]
+pub enum Warning {
+    /// A font, given by name or file, could not be found or loaded.
+    FontNotFound(String),
+    /// A font was replaced by another one.
+    FontSubstituted {
+        /// Font requested by the document.
+        font: String,
+        /// Font used instead.
+        substitute: String,
+    },
+    /// The driver does not support a construct in the document, which is
+    /// omitted or approximated, e.g. bitmap images.
+    Unsupported(String),
+    /// Another warning, given by its message.
+    Other(String),
+}
+
+impl Warning {
+    /// Collect the warnings in the output of pstoedit or ghostscript, in the
+    /// order in which they occur.
+    ///
+    /// # Examples
+    /// ```
+    /// use pstoedit::Warning;
+    ///
+    /// let output = b"Substituting font Courier for LetterGothic.\n";
+    /// assert_eq!(
+    ///     Warning::from_output(output),
+    ///     [Warning::FontSubstituted {
+    ///         font: "LetterGothic".to_owned(),
+    ///         substitute: "Courier".to_owned(),
+    ///     }]
+    /// );
+    /// ```
+    pub fn from_output(output: &[u8]) -> Vec<Self> {
+        String::from_utf8_lossy(output)
+            .lines()
+            .filter_map(|line| Self::from_line(line.trim()))
+            .collect()
+    }
+
+    fn from_line(line: &str) -> Option<Self> {
+        if let Some(font) = line.split("font file ").nth(1) {
+            let font = font.trim().trim_end_matches('.');
+            return Some(Warning::FontNotFound(font.to_owned()));
+        }
+        if let Some(fonts) = line.strip_prefix("Substituting font ") {
+            let (substitute, font) = fonts.trim_end_matches('.').split_once(" for ")?;
+            return Some(Warning::FontSubstituted {
+                font: font.trim().to_owned(),
+                substitute: substitute.trim().to_owned(),
+            });
+        }
+        let message = line
+            .strip_prefix("Warning:")
+            .or_else(|| line.strip_prefix("warning:"))?
+            .trim()
+            .to_owned();
+        if message.contains("not supported") {
+            Some(Warning::Unsupported(message))
+        } else {
+            Some(Warning::Other(message))
+        }
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Warning::FontNotFound(font) => write!(f, "font `{}` not found", font),
+            Warning::FontSubstituted { font, substitute } => {
+                write!(f, "font `{}` substituted by `{}`", font, substitute)
+            }
+            Warning::Unsupported(message) | Warning::Other(message) => f.write_str(message),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_output() {
+        let output = "GPL Ghostscript 10.02.1\n\
+            Can't find (or can't open) font file %rom%Resource/Font/Foo.\n\
+            Substituting font Times-Roman for Foo.\n\
+            Loading NimbusRoman-Regular font from %rom%Resource/Font/NimbusRoman-Regular...\n\
+            Warning: Level 2 version of image and imagemask not supported for this backend\n\
+            warning: text rotated by 45 degrees\n";
+        assert_eq!(
+            Warning::from_output(output.as_bytes()),
+            [
+                Warning::FontNotFound("%rom%Resource/Font/Foo".to_owned()),
+                Warning::FontSubstituted {
+                    font: "Foo".to_owned(),
+                    substitute: "Times-Roman".to_owned(),
+                },
+                Warning::Unsupported(
+                    "Level 2 version of image and imagemask not supported for this backend"
+                        .to_owned()
+                ),
+                Warning::Other("text rotated by 45 degrees".to_owned()),
+            ]
+        );
+        assert!(Warning::from_output(b"stub: running\n").is_empty());
+    }
+}
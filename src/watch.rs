@@ -0,0 +1,226 @@
+//! Re-conversion of documents when they change.
+//!
+//! [`watch`] monitors a directory and its subdirectories. When a file whose
+//! extension matches a [`Rule`] is created or modified, the command of the
+//! rule is run with that file as input. This suits workflows in which figures
+//! are edited repeatedly, such as EPS figures included in LaTeX documents.
+//!
+//! # Examples
+//! ```no_run
+//! use pstoedit::watch::{watch, Rule};
+//! use pstoedit::Command;
+//!
+//! pstoedit::init()?;
+//! let mut cmd = Command::new();
+//! cmd.format("pdf")?.output_dir("build");
+//! for conversion in watch("figures", &[Rule::new("eps", cmd)])? {
+//!     match conversion.result() {
+//!         Ok(_) => eprintln!("converted {}", conversion.input().display()),
+//!         Err(err) => eprintln!("{}: {}", conversion.input().display(), err),
+//!     }
+//! }
+//! # Ok::<(), pstoedit::Error>(())
+//! ```
+
+use crate::{Command, Result, RunReport};
+use notify::event::{EventKind, ModifyKind};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::Duration;
+
+/// Time without further changes to wait for before converting, as editors
+/// often write a file in multiple steps.
+const SETTLE_TIME: Duration = Duration::from_millis(100);
+
+/// Conversion of files with an extension, see [`watch`].
+#[derive(Clone, Debug)]
+pub struct Rule {
+    extension: String,
+    command: Command,
+}
+
+impl Rule {
+    /// Create rule running `command` for files with `extension`, which is
+    /// matched case-insensitively and without leading dot.
+    ///
+    /// The changed file is set as [`input`][Command::input] of the command.
+    /// If the command has neither an [`output`][Command::output] nor an
+    /// [`output_dir`][Command::output_dir], the output is written next to
+    /// the input, named after it.
+    pub fn new<S>(extension: S, command: Command) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            extension: extension.into(),
+            command,
+        }
+    }
+
+    /// Whether the rule applies to `path`.
+    fn matches(&self, path: &Path) -> bool {
+        path.extension()
+            .and_then(|extension| extension.to_str())
+            .is_some_and(|extension| extension.eq_ignore_ascii_case(&self.extension))
+    }
+
+    /// Run the command of the rule for `input`.
+    fn run(&self, input: &Path) -> Result<RunReport> {
+        let mut command = self.command.clone();
+        command.input(crate::path_bytes(input)?)?;
+        if command.output_path().is_none() && !command.has_output_dir() {
+            if let Some(dir) = input.parent() {
+                command.output_dir(dir);
+            }
+        }
+        command.run_report()
+    }
+}
+
+/// Watch `dir` and its subdirectories, converting files according to the
+/// first matching rule when they change.
+///
+/// Returns an iterator that waits for changes and runs the conversions on
+/// the iterating thread, yielding a [`Conversion`] for each. Watching stops
+/// when the iterator is dropped. Existing files are not converted until they
+/// change.
+///
+/// Output files should not match any rule, as writing them would trigger
+/// another conversion.
+///
+/// # Errors
+/// [`WatchError`][crate::Error::WatchError] if the directory cannot be
+/// watched.
+pub fn watch<P>(dir: P, rules: &[Rule]) -> Result<Watch>
+where
+    P: AsRef<Path>,
+{
+    let (sender, events) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(sender)?;
+    watcher.watch(dir.as_ref(), RecursiveMode::Recursive)?;
+    Ok(Watch {
+        _watcher: watcher,
+        events,
+        rules: rules.to_vec(),
+        pending: VecDeque::new(),
+    })
+}
+
+/// Iterator over the conversions of changed files, see [`watch`].
+#[derive(Debug)]
+pub struct Watch {
+    _watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<notify::Event>>,
+    rules: Vec<Rule>,
+    pending: VecDeque<PathBuf>,
+}
+
+impl Watch {
+    /// Add the files changed by an event to the pending files.
+    fn add_event(&mut self, event: notify::Result<notify::Event>) {
+        let event = match event {
+            Ok(event) => event,
+            Err(_err) => {
+                #[cfg(feature = "log")]
+                log::warn!("error watching files: {}", _err);
+                return;
+            }
+        };
+        let changed = match event.kind {
+            EventKind::Create(_) => true,
+            EventKind::Modify(ModifyKind::Metadata(_)) => false,
+            EventKind::Modify(_) => true,
+            _ => false,
+        };
+        if !changed {
+            return;
+        }
+        for path in event.paths {
+            if self.rules.iter().any(|rule| rule.matches(&path)) && !self.pending.contains(&path) {
+                self.pending.push_back(path);
+            }
+        }
+    }
+}
+
+impl Iterator for Watch {
+    type Item = Conversion;
+
+    fn next(&mut self) -> Option<Conversion> {
+        loop {
+            while let Some(input) = self.pending.pop_front() {
+                // The file may have been removed or renamed after changing
+                if !input.is_file() {
+                    continue;
+                }
+                if let Some(rule) = self.rules.iter().find(|rule| rule.matches(&input)) {
+                    let result = rule.run(&input);
+                    return Some(Conversion { input, result });
+                }
+            }
+            let event = self.events.recv().ok()?;
+            self.add_event(event);
+            loop {
+                match self.events.recv_timeout(SETTLE_TIME) {
+                    Ok(event) => self.add_event(event),
+                    Err(RecvTimeoutError::Timeout) => break,
+                    Err(RecvTimeoutError::Disconnected) => return None,
+                }
+            }
+        }
+    }
+}
+
+/// Conversion of a changed file, yielded by [`Watch`].
+#[derive(Debug)]
+pub struct Conversion {
+    input: PathBuf,
+    result: Result<RunReport>,
+}
+
+impl Conversion {
+    /// Changed file that was converted.
+    pub fn input(&self) -> &Path {
+        &self.input
+    }
+
+    /// Report on the conversion if it succeeded, or its error.
+    pub fn result(&self) -> &Result<RunReport> {
+        &self.result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp::TempDir;
+    use std::fs;
+    use std::thread;
+
+    #[test]
+    fn watch_dir() {
+        crate::init().unwrap();
+        let dir = TempDir::new().unwrap();
+        let mut cmd = Command::new();
+        cmd.format("svg").unwrap();
+        let mut conversions = watch(dir.path(), &[Rule::new("ps", cmd)]).unwrap();
+        let input = dir.path().join("figure.PS");
+        let writer = {
+            let input = input.clone();
+            let ignored = dir.path().join("notes.txt");
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(50));
+                fs::write(ignored, "notes").unwrap();
+                fs::write(input, "%!PS\n").unwrap();
+            })
+        };
+        let conversion = conversions.next().unwrap();
+        writer.join().unwrap();
+        assert_eq!(conversion.input(), input);
+        conversion.result().as_ref().unwrap();
+        let output = dir.path().join("figure.svg");
+        assert!(fs::metadata(output).unwrap().len() > 0);
+    }
+}